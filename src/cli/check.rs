@@ -0,0 +1,34 @@
+use std::{collections::HashMap, path::PathBuf};
+
+use clap::Args;
+use odyssey::{
+    comput::lca::Database, errors::Result, parsers::project::check_project, utils::search::Search,
+};
+
+use crate::cli::location::LocationArgs;
+
+#[derive(Debug, Args)]
+#[command(args_conflicts_with_subcommands = true)]
+pub struct CheckCommand {
+    pub path: PathBuf,
+
+    #[command(flatten)]
+    pub location: LocationArgs,
+}
+
+/// Prints the problems of the project, and returns their number.
+pub fn cli_check(args: &CheckCommand) -> Result<usize> {
+    let search = Search::new()?;
+    let mut databases: HashMap<String, Box<dyn Database>> = HashMap::new();
+    let (problems, files) =
+        check_project(&args.path, &search, &mut databases, args.location.policy())?;
+    for problem in problems.iter() {
+        println!("{}", problem);
+    }
+    if problems.is_empty() {
+        println!("No problem found in {} file(s)", files);
+    } else {
+        println!("{} problem(s) found in {} file(s)", problems.len(), files);
+    }
+    Ok(problems.len())
+}
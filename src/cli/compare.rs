@@ -0,0 +1,162 @@
+use std::{collections::HashMap, io::Write, path::PathBuf};
+
+use clap::Args;
+use odyssey::{
+    comput::{comparison::Comparison, lca::Database},
+    errors::{OdysseyErrors, Result},
+    utils::search::Search,
+};
+
+use crate::cli::{
+    location::LocationArgs,
+    output::{open_output, write_aligned, OutputFormat},
+    run::compute_results,
+};
+
+#[derive(Debug, Args)]
+#[command(args_conflicts_with_subcommands = true)]
+pub struct CompareCommand {
+    /// Projects describing each alternative
+    #[arg(num_args = 2.., required = true)]
+    pub paths: Vec<PathBuf>,
+
+    /// Position (starting at 1) of the alternative the others are compared to
+    #[arg(short, long, default_value_t = 1)]
+    pub reference: usize,
+
+    /// Also express each alternative as a ratio to the reference
+    #[arg(short, long, default_value_t = false)]
+    pub normalize: bool,
+
+    #[arg(short, long, value_enum, default_value_t = OutputFormat::Table)]
+    pub format: OutputFormat,
+
+    /// Write the comparison to this file instead of stdout
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+
+    #[command(flatten)]
+    pub location: LocationArgs,
+
+    /// Express the results per this amount of the functional unit of the project
+    #[arg(long)]
+    pub per: Option<f64>,
+}
+
+pub fn cli_compare(args: &CompareCommand) -> Result<()> {
+    if args.reference == 0 || args.reference > args.paths.len() {
+        return Err(OdysseyErrors::InvalidArgument(format!(
+            "--reference must be between 1 and {}, the number of alternatives",
+            args.paths.len()
+        )));
+    }
+    let reference = args.reference - 1;
+    let search = Search::new()?;
+    let mut databases: HashMap<String, Box<dyn Database>> = HashMap::new();
+    let mut alternatives = vec![];
+    for path in args.paths.iter() {
+        let mut results =
+            compute_results(path, &search, &mut databases, args.location.policy(), false)?;
+        if let Some(per) = args.per {
+            results.per(per)?;
+        }
+        for note in results.notes.iter() {
+            eprintln!("{}", note);
+        }
+        alternatives.push(results);
+    }
+    let comparison = Comparison::new(&alternatives, reference, args.normalize);
+
+    let mut writer = open_output(args.output.as_deref())?;
+    match args.format {
+        OutputFormat::Table => write_table(&comparison, &mut writer)?,
+        OutputFormat::Csv => write_csv(&comparison, &mut writer)?,
+        OutputFormat::Json => {
+            serde_json::to_writer_pretty(&mut writer, &comparison)?;
+            writeln!(writer)?;
+        }
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+fn format_optional(value: Option<f64>, format: impl Fn(f64) -> String) -> String {
+    value.map(format).unwrap_or("-".to_string())
+}
+
+fn write_table(comparison: &Comparison, writer: &mut dyn Write) -> Result<()> {
+    let reference = &comparison.alternatives[comparison.reference];
+    let others: Vec<usize> = (0..comparison.alternatives.len())
+        .filter(|i| *i != comparison.reference)
+        .collect();
+
+    let mut header = vec!["category".to_string(), "unit".to_string()];
+    header.extend(comparison.alternatives.iter().cloned());
+    for i in others.iter() {
+        header.push(format!("{} - {}", comparison.alternatives[*i], reference));
+        header.push(format!(
+            "{} / {} - 1",
+            comparison.alternatives[*i], reference
+        ));
+    }
+    let normalize = comparison.rows.iter().any(|r| r.normalized.is_some());
+    if normalize {
+        for alternative in comparison.alternatives.iter() {
+            header.push(format!("{} / {}", alternative, reference));
+        }
+    }
+
+    let mut lines = vec![header];
+    for row in comparison.rows.iter() {
+        let mut line = vec![row.category.name.clone(), row.category.unit.clone()];
+        line.extend(row.values.iter().map(|v| format!("{:.4e}", v)));
+        for i in others.iter() {
+            line.push(format!("{:.4e}", row.absolute[*i]));
+            line.push(format_optional(row.relative[*i], |v| {
+                format!("{:+.1} %", 100. * v)
+            }));
+        }
+        if let Some(normalized) = &row.normalized {
+            line.extend(
+                normalized
+                    .iter()
+                    .map(|v| format_optional(*v, |v| format!("{:.3}", v))),
+            );
+        }
+        lines.push(line);
+    }
+    write_aligned(&lines, 2, writer)
+}
+
+fn write_csv(comparison: &Comparison, writer: &mut dyn Write) -> Result<()> {
+    let mut csv_writer = csv::Writer::from_writer(writer);
+    csv_writer.write_record([
+        "category",
+        "name",
+        "unit",
+        "alternative",
+        "reference",
+        "value",
+        "absolute_difference",
+        "relative_difference",
+        "normalized",
+    ])?;
+    for row in comparison.rows.iter() {
+        for (i, alternative) in comparison.alternatives.iter().enumerate() {
+            let normalized = row.normalized.as_ref().and_then(|n| n[i]);
+            csv_writer.write_record([
+                &row.category.id,
+                &row.category.name,
+                &row.category.unit,
+                alternative,
+                &(i == comparison.reference).to_string(),
+                &row.values[i].to_string(),
+                &row.absolute[i].to_string(),
+                &row.relative[i].map(|v| v.to_string()).unwrap_or_default(),
+                &normalized.map(|v| v.to_string()).unwrap_or_default(),
+            ])?;
+        }
+    }
+    csv_writer.flush()?;
+    Ok(())
+}
@@ -0,0 +1,47 @@
+use std::io::Write;
+
+use clap::Args;
+use odyssey::{
+    errors::{OdysseyErrors, Result},
+    parsers::ecospold2::Ecoinvent,
+    utils::constants::DATABASES_PATH,
+};
+
+#[derive(Debug, Args)]
+#[command(disable_version_flag = true)]
+pub struct DatasetArgs {
+    /// Version of the imported ecoinvent release
+    #[arg(long)]
+    version: String,
+
+    /// Value of a variable to evaluate the mathematical relations again with, as `name=value`
+    #[arg(long = "set", value_parser = parse_value)]
+    values: Vec<(String, f64)>,
+
+    /// Id of the activity, as given by `odyssey search`
+    id: String,
+}
+
+/// Prints the parameters, exchanges and properties of an ecoinvent dataset as JSON.
+pub fn show_dataset(args: DatasetArgs) -> Result<()> {
+    let cache = DATABASES_PATH.join(format!("Ecoinvent_{}", args.version));
+    let mut data = Ecoinvent::load_process_data(&cache)?;
+    let mut data = data
+        .remove(&args.id)
+        .ok_or_else(|| OdysseyErrors::MissingId(args.id.clone()))?;
+    if !args.values.is_empty() {
+        data = data.evaluate(&args.values.into_iter().collect())?;
+    }
+    let mut writer = std::io::stdout().lock();
+    serde_json::to_writer_pretty(&mut writer, &data)?;
+    writeln!(writer)?;
+    Ok(())
+}
+
+fn parse_value(value: &str) -> std::result::Result<(String, f64), String> {
+    let (name, value) = value
+        .split_once('=')
+        .ok_or(format!("{value:?} is not of the form name=value"))?;
+    let value = value.trim().parse().map_err(|e| format!("{e}"))?;
+    Ok((name.trim().to_string(), value))
+}
@@ -5,7 +5,8 @@ use std::{
 
 use clap::Args;
 use odyssey::{
-    errors::Result,
+    errors::{OdysseyErrors, Result},
+    parsers::ecospold2::data_cache,
     utils::{
         constants::{DATABASES_FILE, DATABASES_PATH, SEARCH_PATH},
         search::Search,
@@ -16,9 +17,10 @@ use serde::{Deserialize, Serialize};
 use crate::cli::database::{import::ImportDatabaseArgs, DatabaseKind};
 
 #[derive(Debug, Args, Serialize, Deserialize)]
+#[command(disable_version_flag = true)]
 pub struct RemoveDatabaseArgs {
-    /// Optional output file
-    #[arg(short, long, default_value = "none")]
+    /// Version of the database
+    #[arg(long, default_value = "none")]
     version: String,
 
     kind: DatabaseKind,
@@ -50,13 +52,21 @@ pub fn remove_database(infos: RemoveDatabaseArgs) -> Result<()> {
     writer.flush()?;
 
     // Delete cache
-    let name = format!("{:?} {}", infos.kind, infos.version);
+    let name = format!("{:?}_{}", infos.kind, infos.version);
     let cache_path = &*DATABASES_PATH.join(&name);
     std::fs::remove_file(cache_path)?;
+    let data_path = data_cache(cache_path);
+    if std::fs::exists(&data_path)? {
+        std::fs::remove_file(data_path)?;
+    }
 
     // Delete search index
     std::fs::create_dir_all(&*SEARCH_PATH)?;
-    let mut search = Search::new()?;
-    search.delete_database(&name)?;
+    // An index of a previous version can't be searched until it is built again
+    match Search::new() {
+        Ok(mut search) => search.delete_database(&name)?,
+        Err(OdysseyErrors::OutdatedSearchIndex) => {}
+        Err(error) => return Err(error),
+    }
     Ok(())
 }
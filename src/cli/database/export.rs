@@ -0,0 +1,47 @@
+use std::path::PathBuf;
+
+use clap::Args;
+use odyssey::{
+    errors::Result,
+    parsers::{
+        export::{export_jsonld, export_matrix_market},
+        load_database,
+    },
+};
+
+use crate::cli::database::DatabaseKind;
+
+#[derive(Debug, Clone, clap::ValueEnum)]
+pub enum ExportFormat {
+    /// Matrix Market files of the matrices, with CSV tables of their rows and columns
+    MatrixMarket,
+    /// openLCA JSON-LD zip archive
+    JsonLd,
+}
+
+#[derive(Debug, Args)]
+#[command(disable_version_flag = true)]
+pub struct ExportDatabaseArgs {
+    /// Version of the database
+    #[arg(long, default_value = "none")]
+    version: String,
+
+    /// Folder of the Matrix Market files, or path of the JSON-LD archive
+    #[arg(short, long)]
+    output: PathBuf,
+
+    #[arg(short, long, value_enum, default_value_t = ExportFormat::MatrixMarket)]
+    format: ExportFormat,
+
+    kind: DatabaseKind,
+}
+
+pub fn export_database(args: ExportDatabaseArgs) -> Result<()> {
+    let database = load_database(&format!("{:?}", args.kind), &args.version)?;
+    match args.format {
+        ExportFormat::MatrixMarket => export_matrix_market(database.as_ref(), &args.output)?,
+        ExportFormat::JsonLd => export_jsonld(database.as_ref(), &args.output)?,
+    }
+    println!("Exported database to {}", args.output.display());
+    Ok(())
+}
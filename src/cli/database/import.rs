@@ -9,8 +9,12 @@ use clap::Args;
 use console::style;
 use indicatif::ProgressBar;
 use odyssey::{
-    errors::Result,
-    parsers::ecospold2::Ecoinvent,
+    comput::lca::Database,
+    errors::{OdysseyErrors, Result},
+    parsers::{
+        background::Background, custom::load_custom, ecospold1::load_ecospold1,
+        ecospold2::Ecoinvent, ilcd::load_ilcd, openlca::load_openlca, simapro::load_simapro,
+    },
     utils::{
         constants::{DATABASES_FILE, DATABASES_PATH, SEARCH_PATH},
         search::Search,
@@ -21,9 +25,10 @@ use serde::{Deserialize, Serialize};
 use crate::cli::database::DatabaseKind;
 
 #[derive(Debug, Args, Serialize, Deserialize, Clone)]
+#[command(disable_version_flag = true)]
 pub struct ImportDatabaseArgs {
-    /// Optional output file
-    #[arg(short, long, default_value = "none")]
+    /// Version of the database
+    #[arg(long, default_value = "none")]
     pub version: String,
 
     /// Optional output file
@@ -31,9 +36,20 @@ pub struct ImportDatabaseArgs {
     pub path: PathBuf,
 
     pub kind: DatabaseKind,
+
+    /// Skip the datasets which can't be read, instead of failing the import (ecoinvent only)
+    #[arg(long, default_value_t = false)]
+    #[serde(skip)]
+    pub skip_invalid: bool,
 }
 
 pub fn import_database(mut infos: ImportDatabaseArgs) -> Result<()> {
+    if infos.skip_invalid && infos.kind != DatabaseKind::Ecoinvent {
+        return Err(OdysseyErrors::InvalidArgument(format!(
+            "--skip-invalid only applies to ecoinvent databases, not to {:?}",
+            infos.kind
+        )));
+    }
     let name = format!("{:?}_{}", infos.kind, infos.version);
     std::fs::create_dir_all(&*DATABASES_PATH)?;
 
@@ -48,18 +64,102 @@ pub fn import_database(mut infos: ImportDatabaseArgs) -> Result<()> {
     let bar = ProgressBar::new_spinner().with_message("Loading database");
     bar.enable_steady_tick(Duration::from_millis(100));
     let cache_path = &*DATABASES_PATH.join(&name);
-    let database = match infos.kind {
-        DatabaseKind::Ecoinvent => Ecoinvent::load(&infos.version, data_path, Some(cache_path))?,
+    let mut unlinked = vec![];
+    let mut skipped = vec![];
+    let mut left_out = vec![];
+    let mut background = |database: Background| -> Box<dyn Database> {
+        left_out.extend(database.left_out().iter().cloned());
+        Box::new(database)
+    };
+    let database: Box<dyn Database> = match infos.kind {
+        DatabaseKind::Ecoinvent => {
+            let (database, left_out) = Ecoinvent::load(
+                &infos.version,
+                data_path,
+                Some(cache_path),
+                infos.skip_invalid,
+            )?;
+            skipped = left_out.skipped;
+            unlinked = left_out.unlinked;
+            Box::new(database)
+        }
+        DatabaseKind::EcoSpold1 => background(load_ecospold1(&name, data_path, Some(cache_path))?),
+        DatabaseKind::Custom => background(load_custom(&name, data_path, Some(cache_path))?),
+        DatabaseKind::Ilcd => background(load_ilcd(&name, data_path, Some(cache_path))?),
+        DatabaseKind::OpenLca => {
+            let (database, exchanges) = load_openlca(&name, data_path, Some(cache_path))?;
+            unlinked = exchanges;
+            background(database)
+        }
+        DatabaseKind::SimaPro => {
+            let (database, exchanges) = load_simapro(&name, data_path, Some(cache_path))?;
+            unlinked = exchanges;
+            background(database)
+        }
     };
     bar.finish_with_message(format!("{} Loading database", style("✓").green()));
+    if !skipped.is_empty() {
+        eprintln!(
+            "{} {} invalid datasets were skipped:",
+            style("!").yellow(),
+            skipped.len()
+        );
+        for error in skipped.iter() {
+            eprintln!("  {}", error);
+        }
+    }
+    if !left_out.is_empty() {
+        eprintln!(
+            "{} {} processes were left out, with the exchanges linked to them:",
+            style("!").yellow(),
+            left_out.len()
+        );
+        for process in left_out.iter() {
+            eprintln!("  {}", process);
+        }
+    }
+    if !unlinked.is_empty() {
+        eprintln!(
+            "{} {} exchanges without provider were left out:",
+            style("!").yellow(),
+            unlinked.len()
+        );
+        for exchange in unlinked.iter() {
+            eprintln!("  {}", exchange);
+        }
+    }
 
     // Index search
     let bar = ProgressBar::new_spinner().with_message("Indexing database");
     bar.enable_steady_tick(Duration::from_millis(100));
     std::fs::create_dir_all(&*SEARCH_PATH)?;
-    let search = Search::new()?;
-    search.index_database(&database)?;
+    let (search, reset) = Search::new_or_reset()?;
+    search.index_database(database.as_ref())?;
     bar.finish_with_message(format!("{} Indexing database", style("✓").green()));
+    if reset {
+        warn_reset_index(&infos)?;
+    }
+    Ok(())
+}
+
+/// Lists the other registered databases, which are not in the index built again.
+fn warn_reset_index(infos: &ImportDatabaseArgs) -> Result<()> {
+    let databases: Vec<ImportDatabaseArgs> =
+        serde_json::from_reader(BufReader::new(File::open(&*DATABASES_FILE)?))?;
+    let others: Vec<&ImportDatabaseArgs> = databases
+        .iter()
+        .filter(|d| d.kind != infos.kind || d.version != infos.version)
+        .collect();
+    if others.is_empty() {
+        return Ok(());
+    }
+    eprintln!(
+        "{} The search index of a previous version was replaced, import these databases again:",
+        style("!").yellow()
+    );
+    for database in others {
+        eprintln!("  {:?} {}", database.kind, database.version);
+    }
     Ok(())
 }
 
@@ -5,10 +5,14 @@ use odyssey::{errors::Result, utils::constants::DATABASES_FILE};
 use serde::{Deserialize, Serialize};
 
 use crate::cli::database::{
+    dataset::{show_dataset, DatasetArgs},
     delete::{remove_database, RemoveDatabaseArgs},
+    export::{export_database, ExportDatabaseArgs},
     import::{import_database, ImportDatabaseArgs},
 };
+mod dataset;
 mod delete;
+mod export;
 mod import;
 
 #[derive(Subcommand, Debug)]
@@ -18,11 +22,24 @@ pub enum DatabaseCommandes {
     Import(ImportDatabaseArgs),
     List,
     Remove(RemoveDatabaseArgs),
+    /// Exports the matrices of an imported database
+    Export(ExportDatabaseArgs),
+    /// Prints the parameters, exchanges and properties of a dataset of an imported
+    /// ecoinvent release, optionally evaluated again with other values
+    Dataset(DatasetArgs),
 }
 
 #[derive(Debug, clap::ValueEnum, Clone, Serialize, Deserialize, PartialEq)]
 pub enum DatabaseKind {
     Ecoinvent,
+    #[value(name = "ecospold1")]
+    EcoSpold1,
+    Ilcd,
+    #[value(name = "openlca")]
+    OpenLca,
+    #[value(name = "simapro")]
+    SimaPro,
+    Custom,
 }
 
 impl DatabaseCommandes {
@@ -31,6 +48,8 @@ impl DatabaseCommandes {
             DatabaseCommandes::Import(args) => import_database(args),
             DatabaseCommandes::List => list_databases(),
             DatabaseCommandes::Remove(args) => remove_database(args),
+            DatabaseCommandes::Export(args) => export_database(args),
+            DatabaseCommandes::Dataset(args) => show_dataset(args),
         };
         match res {
             Ok(()) => {}
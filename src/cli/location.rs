@@ -0,0 +1,20 @@
+use clap::Args;
+use odyssey::utils::geographies::LocationPolicy;
+
+/// How the locations of the exchanges of a project are matched.
+#[derive(Debug, Args)]
+pub struct LocationArgs {
+    /// Fall back to parent regions (down to RoW and GLO) when a location is not available
+    #[arg(long, default_value_t = false)]
+    pub location_fallback: bool,
+}
+
+impl LocationArgs {
+    pub fn policy(&self) -> LocationPolicy {
+        if self.location_fallback {
+            LocationPolicy::Fallback
+        } else {
+            LocationPolicy::Exact
+        }
+    }
+}
@@ -0,0 +1,565 @@
+use std::{
+    collections::HashMap,
+    io::{self, BufRead, Write},
+    path::{Path, PathBuf},
+};
+
+use clap::Args;
+use odyssey::{
+    comput::{foreground::ForegroundLink, lca::Database},
+    errors::Result,
+    parsers::project::{
+        check_activity, exchange_lines, resolve_biosphere_exchange, resolve_database_exchange,
+        Activity, DatabaseInfos, ExchangeLink,
+    },
+    utils::{
+        geographies::LocationPolicy,
+        search::{ElementaryItem, InventoryItem, Search},
+    },
+};
+use serde_json::{json, Value};
+
+use crate::cli::location::LocationArgs;
+
+/// JSON-RPC error code of unknown methods.
+const METHOD_NOT_FOUND: i64 = -32601;
+
+#[derive(Debug, Args)]
+#[command(args_conflicts_with_subcommands = true)]
+pub struct LspCommand {
+    #[command(flatten)]
+    pub location: LocationArgs,
+}
+
+/// Runs a language server for project files over stdin and stdout.
+pub fn cli_lsp(args: &LspCommand) -> Result<()> {
+    let mut server = Server {
+        search: Search::new()?,
+        databases: HashMap::new(),
+        documents: HashMap::new(),
+        roots: vec![],
+        policy: args.location.policy(),
+    };
+    let mut reader = io::stdin().lock();
+    let mut writer = io::stdout().lock();
+    while let Some(message) = read_message(&mut reader)? {
+        let method = message["method"].as_str().unwrap_or_default();
+        let params = &message["params"];
+        match message.get("id") {
+            // Responses of the client to our requests are ignored
+            Some(_) if method.is_empty() => {}
+            Some(id) => {
+                let response = match server.request(method, params) {
+                    Some(result) => json!({"jsonrpc": "2.0", "id": id, "result": result}),
+                    None => json!({
+                        "jsonrpc": "2.0",
+                        "id": id,
+                        "error": {"code": METHOD_NOT_FOUND, "message": format!("unknown method {method}")},
+                    }),
+                };
+                write_message(&mut writer, &response)?;
+            }
+            None if method == "exit" => break,
+            None => {
+                for notification in server.notification(method, params) {
+                    write_message(&mut writer, &notification)?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+struct Server {
+    search: Search,
+    databases: HashMap<String, Box<dyn Database>>,
+    /// Content of the open documents, by uri
+    documents: HashMap<String, String>,
+    /// Roots of the workspace folders, against which included files are resolved
+    roots: Vec<PathBuf>,
+    policy: LocationPolicy,
+}
+
+impl Server {
+    /// Result of the request `method`, or `None` if it is not supported.
+    fn request(&mut self, method: &str, params: &Value) -> Option<Value> {
+        match method {
+            "initialize" => {
+                // Files are included relatively to the root of their workspace folder
+                match params["workspaceFolders"].as_array() {
+                    Some(folders) => self.add_roots(folders),
+                    None => self
+                        .roots
+                        .extend(params["rootUri"].as_str().and_then(uri_to_path)),
+                }
+                Some(json!({
+                    "capabilities": {
+                        "positionEncoding": "utf-16",
+                        "textDocumentSync": 1,
+                        "completionProvider": {"triggerCharacters": [" "]},
+                        "hoverProvider": true,
+                        "definitionProvider": true,
+                        "workspace": {
+                            "workspaceFolders": {"supported": true, "changeNotifications": true},
+                        },
+                    },
+                    "serverInfo": {"name": "odyssey", "version": env!("CARGO_PKG_VERSION")},
+                }))
+            }
+            "shutdown" => Some(Value::Null),
+            "textDocument/completion" => Some(self.completion(params).unwrap_or(json!([]))),
+            "textDocument/hover" => Some(self.hover(params).unwrap_or(Value::Null)),
+            "textDocument/definition" => Some(self.definition(params).unwrap_or(Value::Null)),
+            _ => None,
+        }
+    }
+
+    /// Handles the notification `method`, and returns the notifications to send back.
+    fn notification(&mut self, method: &str, params: &Value) -> Vec<Value> {
+        if method == "workspace/didChangeWorkspaceFolders" {
+            for folder in params["event"]["removed"].as_array().into_iter().flatten() {
+                if let Some(root) = folder["uri"].as_str().and_then(uri_to_path) {
+                    self.roots.retain(|r| *r != root);
+                }
+            }
+            self.add_roots(params["event"]["added"].as_array().unwrap_or(&vec![]));
+            return vec![];
+        }
+        let Some(uri) = params["textDocument"]["uri"].as_str() else {
+            return vec![];
+        };
+        match method {
+            "textDocument/didOpen" => {
+                let text = params["textDocument"]["text"].as_str().unwrap_or_default();
+                self.documents.insert(uri.to_string(), text.to_string());
+            }
+            "textDocument/didChange" => {
+                // Full synchronization: the last change holds the whole document
+                if let Some(text) = params["contentChanges"]
+                    .as_array()
+                    .and_then(|c| c.last())
+                    .and_then(|c| c["text"].as_str())
+                {
+                    self.documents.insert(uri.to_string(), text.to_string());
+                }
+            }
+            "textDocument/didSave" => {}
+            "textDocument/didClose" => {
+                self.documents.remove(uri);
+                return vec![publish_diagnostics(uri, vec![])];
+            }
+            _ => return vec![],
+        }
+        vec![publish_diagnostics(uri, self.diagnostics(uri))]
+    }
+
+    fn add_roots(&mut self, folders: &[Value]) {
+        self.roots.extend(
+            folders
+                .iter()
+                .filter_map(|f| f["uri"].as_str().and_then(uri_to_path)),
+        );
+    }
+
+    /// Root of the workspace folder holding the file at `path`, the innermost one if
+    /// folders are nested, or the working directory if none holds it.
+    fn root(&self, path: &Path) -> PathBuf {
+        self.roots
+            .iter()
+            .filter(|root| path.starts_with(root))
+            .max_by_key(|root| root.components().count())
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    fn diagnostics(&mut self, uri: &str) -> Vec<Value> {
+        let (Some(text), Some(path)) = (self.documents.get(uri), uri_to_path(uri)) else {
+            return vec![];
+        };
+        let root = self.root(&path);
+        let (problems, _) = check_activity(
+            &path,
+            &root,
+            text,
+            &self.search,
+            &mut self.databases,
+            self.policy,
+        );
+        problems
+            .into_iter()
+            .map(|problem| {
+                let line = problem.line.unwrap_or(1) - 1;
+                json!({
+                    "range": line_range(text, line),
+                    "severity": 1,
+                    "source": "odyssey",
+                    "message": problem.message,
+                })
+            })
+            .collect()
+    }
+
+    /// Activity names matching the `name:` being typed, restricted to the
+    /// database, location and unit of the exchange.
+    fn completion(&mut self, params: &Value) -> Option<Value> {
+        let (uri, line, character) = position(params)?;
+        let text = self.documents.get(uri)?;
+        let before = line_prefix(text.lines().nth(line)?, character);
+        let value = before
+            .trim_start()
+            .trim_start_matches('-')
+            .trim_start()
+            .strip_prefix("name:")?;
+        let query = value.trim().trim_matches(|c| c == '"' || c == '\'');
+        if query.is_empty() {
+            return None;
+        }
+        let start = utf16_len(&before[..before.len() - value.trim_start().len()]);
+
+        let exchange = exchange_block(text, line)?;
+        let database = serde_yaml::from_value::<DatabaseInfos>(exchange["database"].clone())
+            .ok()
+            .map(|d| d.full_name());
+        let items = self
+            .search
+            .search_items(
+                query,
+                database.as_deref(),
+                exchange["location"].as_str(),
+                exchange["unit"].as_str(),
+            )
+            .unwrap_or_default();
+        let range = json!({
+            "start": {"line": line, "character": start},
+            "end": {"line": line, "character": character},
+        });
+        Some(Value::Array(
+            items
+                .into_iter()
+                .map(|item| {
+                    let new_text = if item.name.contains(": ") || item.name.contains(" #") {
+                        format!("{:?}", item.name)
+                    } else {
+                        item.name.clone()
+                    };
+                    json!({
+                        "label": item.name,
+                        "detail": format!(
+                            "{} {} [{}]",
+                            item.location.as_deref().unwrap_or("-"),
+                            item.unit,
+                            item.database
+                        ),
+                        "documentation": item.alt_name,
+                        "textEdit": {"range": range, "newText": new_text},
+                    })
+                })
+                .collect(),
+        ))
+    }
+
+    /// Details of the background activity or elementary flow the exchange under
+    /// the cursor resolves to, or the reason it doesn't resolve.
+    fn hover(&mut self, params: &Value) -> Option<Value> {
+        let (uri, line, _) = position(params)?;
+        let text = self.documents.get(uri)?;
+        let path = uri_to_path(uri)?;
+        let activity: Activity = serde_yaml::from_str(text).ok()?;
+        let exchange = activity.exchanges.get(exchange_at(text, line)?)?;
+        let contents = match &exchange.link {
+            ExchangeLink::File { .. } => return None,
+            ExchangeLink::Database { database } => resolve_database_exchange(
+                &path,
+                database,
+                exchange,
+                &self.search,
+                &mut self.databases,
+                self.policy,
+            )
+            .map(|resolved| self.describe_link(&resolved.link)),
+            ExchangeLink::Biosphere { biosphere } => {
+                resolve_biosphere_exchange(&path, biosphere, exchange, &mut self.databases)
+                    .map(|resolved| self.describe_link(&resolved.link))
+            }
+        };
+        let contents = match contents {
+            Ok(contents) => contents?,
+            Err(e) => e.to_string(),
+        };
+        Some(json!({"contents": {"kind": "markdown", "value": contents}}))
+    }
+
+    fn describe_link(&self, link: &ForegroundLink) -> Option<String> {
+        match link {
+            ForegroundLink::Activity(_) => None,
+            ForegroundLink::Database { database, id } => self
+                .databases
+                .get(database)?
+                .find_candidate(id)
+                .map(describe_item),
+            ForegroundLink::Biosphere { database, id } => self
+                .databases
+                .get(database)?
+                .find_elementary(id)
+                .map(describe_elementary),
+        }
+    }
+
+    /// Location of the file included on the line under the cursor.
+    fn definition(&self, params: &Value) -> Option<Value> {
+        let (uri, line, _) = position(params)?;
+        let text = self.documents.get(uri)?;
+        let file = text
+            .lines()
+            .nth(line)?
+            .trim_start()
+            .trim_start_matches('-')
+            .trim_start()
+            .strip_prefix("file:")?
+            .trim()
+            .trim_matches(|c| c == '"' || c == '\'');
+        let path = self
+            .root(&uri_to_path(uri)?)
+            .join(file)
+            .canonicalize()
+            .ok()?;
+        Some(json!({
+            "uri": path_to_uri(&path),
+            "range": {
+                "start": {"line": 0, "character": 0},
+                "end": {"line": 0, "character": 0},
+            },
+        }))
+    }
+}
+
+fn describe_item(item: &InventoryItem) -> String {
+    let mut res = format!("**{}**\n\n", item.name);
+    if let Some(alt_name) = &item.alt_name {
+        res += &format!("{}\n\n", alt_name);
+    }
+    res += &format!(
+        "- database: {}\n- location: {}\n- unit: {}\n- id: `{}`",
+        item.database,
+        item.location.as_deref().unwrap_or("-"),
+        item.unit,
+        item.id
+    );
+    res
+}
+
+fn describe_elementary(item: &ElementaryItem) -> String {
+    format!(
+        "**{}**\n\n- compartment: {}/{}\n- unit: {}\n- id: `{}`",
+        item.name,
+        item.compartment,
+        item.subcompartment.as_deref().unwrap_or("-"),
+        item.unit,
+        item.id
+    )
+}
+
+fn publish_diagnostics(uri: &str, diagnostics: Vec<Value>) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "method": "textDocument/publishDiagnostics",
+        "params": {"uri": uri, "diagnostics": diagnostics},
+    })
+}
+
+/// Uri, line and character of a text document position.
+fn position(params: &Value) -> Option<(&str, usize, usize)> {
+    Some((
+        params["textDocument"]["uri"].as_str()?,
+        params["position"]["line"].as_u64()? as usize,
+        params["position"]["character"].as_u64()? as usize,
+    ))
+}
+
+/// Part of `line` before the `character`, counted in UTF-16 code units as positions
+/// of the protocol are.
+fn line_prefix(line: &str, character: usize) -> &str {
+    let mut units = 0;
+    for (index, c) in line.char_indices() {
+        if units >= character {
+            return &line[..index];
+        }
+        units += c.len_utf16();
+    }
+    line
+}
+
+/// Length of `text` in UTF-16 code units.
+fn utf16_len(text: &str) -> usize {
+    text.encode_utf16().count()
+}
+
+fn line_range(text: &str, line: usize) -> Value {
+    let length = text.lines().nth(line).map(utf16_len).unwrap_or(0);
+    json!({
+        "start": {"line": line, "character": 0},
+        "end": {"line": line, "character": length},
+    })
+}
+
+/// Index of the exchange whose entry contains the `line` (starting at 0).
+fn exchange_at(text: &str, line: usize) -> Option<usize> {
+    exchange_lines(text)
+        .iter()
+        .rposition(|start| start - 1 <= line)
+}
+
+/// Content of the exchange entry containing the `line`, as parsed so far.
+fn exchange_block(text: &str, line: usize) -> Option<serde_yaml::Value> {
+    let starts = exchange_lines(text);
+    let index = exchange_at(text, line)?;
+    let start = starts[index] - 1;
+    let end = starts.get(index + 1).map(|s| s - 1).unwrap_or(line + 1);
+    let block: Vec<String> = text
+        .lines()
+        .enumerate()
+        .skip(start)
+        .take(end.max(line + 1) - start)
+        .map(|(i, l)| match i == start {
+            // The entry marker becomes indentation, so the block is a mapping
+            true => l.replacen('-', " ", 1),
+            false => l.to_string(),
+        })
+        .collect();
+    serde_yaml::from_str(&block.join("\n")).ok()
+}
+
+fn uri_to_path(uri: &str) -> Option<PathBuf> {
+    let path = uri.strip_prefix("file://")?;
+    let mut bytes = vec![];
+    let mut chars = path.bytes();
+    while let Some(c) = chars.next() {
+        match c {
+            b'%' => {
+                let hex: String = chars.by_ref().take(2).map(char::from).collect();
+                if hex.len() != 2 {
+                    return None;
+                }
+                bytes.push(u8::from_str_radix(&hex, 16).ok()?);
+            }
+            c => bytes.push(c),
+        }
+    }
+    Some(PathBuf::from(String::from_utf8(bytes).ok()?))
+}
+
+fn path_to_uri(path: &Path) -> String {
+    let mut res = "file://".to_string();
+    for c in path.display().to_string().bytes() {
+        match c {
+            b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'/' | b'-' | b'_' | b'.' | b'~' => {
+                res.push(char::from(c))
+            }
+            c => res += &format!("%{:02X}", c),
+        }
+    }
+    res
+}
+
+/// Reads a message of the base protocol, or `None` at the end of the input.
+fn read_message(reader: &mut impl BufRead) -> Result<Option<Value>> {
+    loop {
+        let mut length = None;
+        loop {
+            let mut header = String::new();
+            if reader.read_line(&mut header)? == 0 {
+                return Ok(None);
+            }
+            let header = header.trim_end();
+            if header.is_empty() {
+                break;
+            }
+            if let Some(value) = header.strip_prefix("Content-Length:") {
+                length = value.trim().parse::<usize>().ok();
+            }
+        }
+        let Some(length) = length else {
+            continue;
+        };
+        let mut body = vec![0; length];
+        reader.read_exact(&mut body)?;
+        match serde_json::from_slice(&body) {
+            Ok(message) => return Ok(Some(message)),
+            Err(e) => eprintln!("Invalid message: {}", e),
+        }
+    }
+}
+
+fn write_message(writer: &mut impl Write, message: &Value) -> Result<()> {
+    let body = serde_json::to_string(message)?;
+    write!(writer, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+    writer.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_messages() {
+        let message = json!({"jsonrpc": "2.0", "id": 1, "method": "shutdown"});
+        let mut buffer = vec![];
+        write_message(&mut buffer, &message).unwrap();
+        write_message(&mut buffer, &json!({"text": "é"})).unwrap();
+        let body = serde_json::to_string(&message).unwrap();
+        let header = format!("Content-Length: {}\r\n\r\n", body.len());
+        assert!(buffer.starts_with(format!("{header}{body}").as_bytes()));
+
+        // Other headers, messages without length and invalid bodies are skipped
+        let mut input = b"Content-Type: application/vscode-jsonrpc\r\n\r\n".to_vec();
+        input.extend(b"Content-Length: 3\r\n\r\n{x}");
+        input.extend(&buffer);
+        let mut reader = input.as_slice();
+        assert_eq!(read_message(&mut reader).unwrap(), Some(message));
+        assert_eq!(
+            read_message(&mut reader).unwrap(),
+            Some(json!({"text": "é"}))
+        );
+        assert_eq!(read_message(&mut reader).unwrap(), None);
+    }
+
+    #[test]
+    fn test_uris() {
+        let path = Path::new("/home/me/my project/façade.yaml");
+        let uri = path_to_uri(path);
+        assert_eq!(uri, "file:///home/me/my%20project/fa%C3%A7ade.yaml");
+        assert_eq!(uri_to_path(&uri).as_deref(), Some(path));
+        assert_eq!(uri_to_path("untitled:Untitled-1"), None);
+        assert_eq!(uri_to_path("file:///a%2"), None);
+    }
+
+    #[test]
+    fn test_positions() {
+        // `é` is one UTF-16 code unit, `😀` two
+        let line = "- name: é😀 steel";
+        assert_eq!(line_prefix(line, 9), "- name: é");
+        assert_eq!(line_prefix(line, 11), "- name: é😀");
+        assert_eq!(line_prefix(line, 100), line);
+        assert_eq!(utf16_len(line), 17);
+        assert_eq!(line_range(line, 0)["end"]["character"], 17);
+    }
+
+    #[test]
+    fn test_exchange_block() {
+        let text = "exchanges:\n- file: a.yaml\n  amount: 1\n- database:\n    name: ecoinvent\n    version: '3.11'\n  location: FR\n  name: steel\n  amount: 2\n";
+        let block = exchange_block(text, 7).unwrap();
+        assert_eq!(block["location"].as_str(), Some("FR"));
+        assert_eq!(block["database"]["name"].as_str(), Some("ecoinvent"));
+        assert_eq!(
+            exchange_block(text, 2).unwrap()["file"].as_str(),
+            Some("a.yaml")
+        );
+        assert!(exchange_block(text, 0).is_none());
+
+        // The last entry is only parsed up to the line being typed
+        let typing = "exchanges:\n- database:\n    name: ecoinvent\n    version: '3.11'\n  name: ste\n  unit: [\n";
+        let block = exchange_block(typing, 4).unwrap();
+        assert_eq!(block["name"].as_str(), Some("ste"));
+    }
+}
@@ -1,12 +1,26 @@
+mod check;
+mod compare;
 mod database;
+mod location;
+mod lsp;
+mod output;
+mod report;
+mod resolve;
 mod run;
+mod schema;
 mod search;
 
 use clap::{Parser, Subcommand};
 use database::DatabaseCommandes;
 
 use crate::cli::{
+    check::{cli_check, CheckCommand},
+    compare::{cli_compare, CompareCommand},
+    lsp::{cli_lsp, LspCommand},
+    report::{cli_report, ReportCommand},
+    resolve::{cli_resolve, ResolveCommand},
     run::{run_lca, RunCommand},
+    schema::{cli_schema, SchemaCommand},
     search::{cli_search, SearchCommand},
 };
 
@@ -32,10 +46,35 @@ impl Cli {
                 Ok(()) => {}
                 Err(e) => eprintln!("Error while searching: {}", e),
             },
-            Commands::Run(args) => match run_lca(&args.path) {
+            Commands::Run(args) => match run_lca(&args) {
                 Ok(()) => {}
                 Err(e) => eprintln!("Error while runing: {}", e),
             },
+            Commands::Resolve(args) => match cli_resolve(&args) {
+                Ok(()) => {}
+                Err(e) => eprintln!("Error while resolving: {}", e),
+            },
+            Commands::Report(args) => match cli_report(&args) {
+                Ok(()) => {}
+                Err(e) => eprintln!("Error while reporting: {}", e),
+            },
+            Commands::Compare(args) => match cli_compare(&args) {
+                Ok(()) => {}
+                Err(e) => eprintln!("Error while comparing: {}", e),
+            },
+            Commands::Check(args) => match cli_check(&args) {
+                Ok(0) => {}
+                Ok(_) => std::process::exit(1),
+                Err(e) => eprintln!("Error while checking: {}", e),
+            },
+            Commands::Schema(args) => match cli_schema(&args) {
+                Ok(()) => {}
+                Err(e) => eprintln!("Error while writing the schema: {}", e),
+            },
+            Commands::Lsp(args) => match cli_lsp(&args) {
+                Ok(()) => {}
+                Err(e) => eprintln!("Error in the language server: {}", e),
+            },
         }
     }
 }
@@ -51,4 +90,35 @@ enum Commands {
 
     /// Execute inventory, impact assessment and life cycle assessment
     Run(RunCommand),
+
+    /// Pin the background references of a project to stable ids
+    Resolve(ResolveCommand),
+
+    /// Render the assessment of a project as an HTML or Markdown report
+    Report(ReportCommand),
+
+    /// Compare the impacts of several alternatives
+    Compare(CompareCommand),
+
+    /// Check a project and list all its problems, without computing anything
+    Check(CheckCommand),
+
+    /// Print the JSON Schema of project files
+    Schema(SchemaCommand),
+
+    /// Run a language server for project files over stdio
+    Lsp(LspCommand),
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::CommandFactory;
+
+    use super::*;
+
+    #[test]
+    fn test_cli() {
+        // Arguments of subcommands must not clash with the global and automatic ones
+        Cli::command().debug_assert();
+    }
 }
@@ -0,0 +1,119 @@
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+};
+
+use odyssey::{comput::results::LcaResults, errors::Result};
+
+#[derive(Debug, clap::ValueEnum, Clone, Copy, PartialEq, Default)]
+pub enum OutputFormat {
+    /// Aligned table, one line per impact category
+    #[default]
+    Table,
+    /// One line per result row and impact category
+    Csv,
+    Json,
+}
+
+/// Writes `results` in `format` to `output`, or to stdout if none.
+pub fn write_results(
+    results: &LcaResults,
+    format: OutputFormat,
+    output: Option<&Path>,
+) -> Result<()> {
+    let mut writer = open_output(output)?;
+    match format {
+        OutputFormat::Table => write_table(results, &mut writer)?,
+        OutputFormat::Csv => write_csv(results, &mut writer)?,
+        OutputFormat::Json => {
+            serde_json::to_writer_pretty(&mut writer, results)?;
+            writeln!(writer)?;
+        }
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Writer to the `output` file, or to stdout if none.
+pub fn open_output(output: Option<&Path>) -> Result<Box<dyn Write>> {
+    Ok(match output {
+        Some(path) => Box::new(BufWriter::new(File::create(path)?)),
+        None => Box::new(io::stdout().lock()),
+    })
+}
+
+fn write_csv(results: &LcaResults, writer: &mut dyn Write) -> Result<()> {
+    let mut csv_writer = csv::Writer::from_writer(writer);
+    csv_writer.write_record([
+        "kind", "label", "category", "method", "name", "unit", "value",
+    ])?;
+    for row in results.rows.iter() {
+        let kind = serde_json::to_value(row.kind)?;
+        for (category, value) in results.categories.iter().zip(row.values.iter()) {
+            csv_writer.write_record([
+                kind.as_str().unwrap_or_default(),
+                &row.label,
+                &category.id,
+                &category.method,
+                &category.name,
+                &category.unit,
+                &value.to_string(),
+            ])?;
+        }
+    }
+    csv_writer.flush()?;
+    Ok(())
+}
+
+fn write_table(results: &LcaResults, writer: &mut dyn Write) -> Result<()> {
+    if let Some(functional_unit) = &results.functional_unit {
+        writeln!(writer, "Results per {}", functional_unit)?;
+    }
+    let mut lines = vec![];
+    let mut header = vec![
+        "category".to_string(),
+        "unit".to_string(),
+        "method".to_string(),
+    ];
+    header.extend(results.rows.iter().map(|r| r.label.clone()));
+    lines.push(header);
+    for (i, category) in results.categories.iter().enumerate() {
+        let mut line = vec![
+            category.name.clone(),
+            category.unit.clone(),
+            category.method.clone(),
+        ];
+        line.extend(results.rows.iter().map(|r| format!("{:.4e}", r.values[i])));
+        lines.push(line);
+    }
+
+    write_aligned(&lines, 3, writer)
+}
+
+/// Writes `lines` as a table, aligning the first `left` columns to the left
+/// and the others, numeric, to the right.
+pub fn write_aligned(lines: &[Vec<String>], left: usize, writer: &mut dyn Write) -> Result<()> {
+    let widths: Vec<usize> = (0..lines[0].len())
+        .map(|j| {
+            lines
+                .iter()
+                .map(|l| l[j].chars().count())
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+    for line in lines.iter() {
+        let cells: Vec<String> = line
+            .iter()
+            .zip(widths.iter())
+            .enumerate()
+            .map(|(j, (cell, width))| match j {
+                j if j < left => format!("{:<width$}", cell),
+                _ => format!("{:>width$}", cell),
+            })
+            .collect();
+        writeln!(writer, "{}", cells.join("  ").trim_end())?;
+    }
+    Ok(())
+}
@@ -0,0 +1,334 @@
+use std::{collections::HashMap, fmt::Write as _, fs, path::PathBuf};
+
+use clap::Args;
+use odyssey::{
+    comput::{
+        lca::Database,
+        results::{LcaResults, ResultRow, RowKind},
+    },
+    errors::Result,
+    utils::search::Search,
+};
+
+use crate::cli::{location::LocationArgs, run::compute_results};
+
+/// Number of activities listed as top contributors.
+const TOP_CONTRIBUTORS: usize = 5;
+
+#[derive(Debug, clap::ValueEnum, Clone, Copy, PartialEq, Default)]
+pub enum ReportFormat {
+    #[default]
+    Html,
+    Md,
+}
+
+#[derive(Debug, Args)]
+#[command(args_conflicts_with_subcommands = true)]
+pub struct ReportCommand {
+    pub path: PathBuf,
+
+    #[arg(short, long, value_enum, default_value_t = ReportFormat::Html)]
+    pub format: ReportFormat,
+
+    /// Write the report to this file instead of stdout
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+
+    #[command(flatten)]
+    pub location: LocationArgs,
+
+    /// Express the results per this amount of the functional unit of the project
+    #[arg(long)]
+    pub per: Option<f64>,
+}
+
+pub fn cli_report(args: &ReportCommand) -> Result<()> {
+    let search = Search::new()?;
+    let mut databases: HashMap<String, Box<dyn Database>> = HashMap::new();
+    let mut results = compute_results(
+        &args.path,
+        &search,
+        &mut databases,
+        args.location.policy(),
+        true,
+    )?;
+    if let Some(per) = args.per {
+        results.per(per)?;
+    }
+    let report = match args.format {
+        ReportFormat::Html => render_html(&results),
+        ReportFormat::Md => render_markdown(&results),
+    };
+    match &args.output {
+        Some(path) => fs::write(path, report)?,
+        None => print!("{}", report),
+    }
+    Ok(())
+}
+
+/// Columns of the results table: the total, then each exchange of the reference activity.
+fn result_columns(results: &LcaResults) -> Vec<&ResultRow> {
+    let mut res: Vec<&ResultRow> = results
+        .rows
+        .iter()
+        .filter(|r| r.kind == RowKind::Total)
+        .collect();
+    res.extend(results.rows.iter().filter(|r| r.kind == RowKind::Exchange));
+    res
+}
+
+fn stage_rows(results: &LcaResults) -> Vec<&ResultRow> {
+    results
+        .rows
+        .iter()
+        .filter(|r| r.kind == RowKind::Stage)
+        .collect()
+}
+
+fn column_label(row: &ResultRow) -> &str {
+    match row.kind {
+        RowKind::Total => "Total",
+        _ => &row.label,
+    }
+}
+
+fn functional_unit(results: &LcaResults) -> String {
+    match &results.functional_unit {
+        Some(functional_unit) => functional_unit.to_string(),
+        None => format!("1 unit of the reference product of {}", results.project),
+    }
+}
+
+fn render_markdown(results: &LcaResults) -> String {
+    let mut res = String::new();
+    let _ = writeln!(res, "# Life cycle assessment of {}\n", results.project);
+
+    let _ = writeln!(res, "## Goal\n");
+    let _ = writeln!(res, "Functional unit: {}.\n", functional_unit(results));
+
+    let _ = writeln!(res, "## Databases and methods\n");
+    for database in results.databases.iter() {
+        let _ = writeln!(res, "- Database: {}", database);
+    }
+    for method in results.methods() {
+        let _ = writeln!(res, "- Method: {}", method);
+    }
+    res.push('\n');
+
+    let _ = writeln!(res, "## Results\n");
+    let columns = result_columns(results);
+    let _ = write!(res, "| Category | Unit |");
+    for column in columns.iter() {
+        let _ = write!(res, " {} |", escape_cell(column_label(column)));
+    }
+    let _ = write!(res, "\n|---|---|");
+    res.push_str(&"---:|".repeat(columns.len()));
+    res.push('\n');
+    for (i, category) in results.categories.iter().enumerate() {
+        let _ = write!(
+            res,
+            "| {} | {} |",
+            escape_cell(&category.name),
+            escape_cell(&category.unit)
+        );
+        for column in columns.iter() {
+            let _ = write!(res, " {:.4e} |", column.values[i]);
+        }
+        res.push('\n');
+    }
+    res.push('\n');
+
+    let stages = stage_rows(results);
+    if !stages.is_empty() {
+        let _ = writeln!(res, "## Results per life cycle stage\n");
+        let _ = write!(res, "| Category | Unit |");
+        for stage in stages.iter() {
+            let _ = write!(res, " {} |", escape_cell(&stage.label));
+        }
+        let _ = write!(res, "\n|---|---|");
+        res.push_str(&"---:|".repeat(stages.len()));
+        res.push('\n');
+        for (i, category) in results.categories.iter().enumerate() {
+            let _ = write!(
+                res,
+                "| {} | {} |",
+                escape_cell(&category.name),
+                escape_cell(&category.unit)
+            );
+            for stage in stages.iter() {
+                let _ = write!(res, " {:.4e} |", stage.values[i]);
+            }
+            res.push('\n');
+        }
+        res.push('\n');
+    }
+
+    if let Some(category) = results.categories.first() {
+        let _ = writeln!(res, "## Top contributors ({})\n", category.name);
+        let _ = writeln!(
+            res,
+            "| Activity | {} | Share |",
+            escape_cell(&category.unit)
+        );
+        let _ = writeln!(res, "|---|---:|---:|");
+        for (row, share) in results.top_contributors(0, TOP_CONTRIBUTORS) {
+            let _ = writeln!(
+                res,
+                "| {} | {:.4e} | {:.1} % |",
+                escape_cell(&row.label),
+                row.values[0],
+                100. * share
+            );
+        }
+        res.push('\n');
+    }
+
+    let _ = writeln!(res, "## Data quality notes\n");
+    if results.notes.is_empty() {
+        let _ = writeln!(res, "All exchanges were resolved as specified.");
+    }
+    for note in results.notes.iter() {
+        let _ = writeln!(res, "- {}", note);
+    }
+    res
+}
+
+/// Escapes the pipes of `text`, which would end its Markdown table cell.
+fn escape_cell(text: &str) -> String {
+    text.replace('|', "\\|")
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn render_html(results: &LcaResults) -> String {
+    let mut res = String::new();
+    let title = format!("Life cycle assessment of {}", escape(&results.project));
+    let _ = writeln!(
+        res,
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>",
+        title
+    );
+    let _ = writeln!(
+        res,
+        "<style>\
+         body {{ font-family: sans-serif; margin: 2em; }} \
+         table {{ border-collapse: collapse; }} \
+         th, td {{ border: 1px solid #ccc; padding: 0.3em 0.6em; }} \
+         td.value {{ text-align: right; font-family: monospace; }}\
+         </style>\n</head>\n<body>"
+    );
+    let _ = writeln!(res, "<h1>{}</h1>", title);
+
+    let _ = writeln!(res, "<h2>Goal</h2>");
+    let _ = writeln!(
+        res,
+        "<p>Functional unit: {}.</p>",
+        escape(&functional_unit(results))
+    );
+
+    let _ = writeln!(res, "<h2>Databases and methods</h2>\n<ul>");
+    for database in results.databases.iter() {
+        let _ = writeln!(res, "<li>Database: {}</li>", escape(database));
+    }
+    for method in results.methods() {
+        let _ = writeln!(res, "<li>Method: {}</li>", escape(method));
+    }
+    let _ = writeln!(res, "</ul>");
+
+    let _ = writeln!(res, "<h2>Results</h2>\n<table>");
+    let columns = result_columns(results);
+    let _ = write!(res, "<tr><th>Category</th><th>Unit</th>");
+    for column in columns.iter() {
+        let _ = write!(res, "<th>{}</th>", escape(column_label(column)));
+    }
+    let _ = writeln!(res, "</tr>");
+    for (i, category) in results.categories.iter().enumerate() {
+        let _ = write!(
+            res,
+            "<tr><td title=\"{}\">{}</td><td>{}</td>",
+            escape(&category.description),
+            escape(&category.name),
+            escape(&category.unit)
+        );
+        for column in columns.iter() {
+            let _ = write!(res, "<td class=\"value\">{:.4e}</td>", column.values[i]);
+        }
+        let _ = writeln!(res, "</tr>");
+    }
+    let _ = writeln!(res, "</table>");
+
+    let stages = stage_rows(results);
+    if !stages.is_empty() {
+        let _ = writeln!(res, "<h2>Results per life cycle stage</h2>\n<table>");
+        let _ = write!(res, "<tr><th>Category</th><th>Unit</th>");
+        for stage in stages.iter() {
+            let _ = write!(res, "<th>{}</th>", escape(&stage.label));
+        }
+        let _ = writeln!(res, "</tr>");
+        for (i, category) in results.categories.iter().enumerate() {
+            let _ = write!(
+                res,
+                "<tr><td>{}</td><td>{}</td>",
+                escape(&category.name),
+                escape(&category.unit)
+            );
+            for stage in stages.iter() {
+                let _ = write!(res, "<td class=\"value\">{:.4e}</td>", stage.values[i]);
+            }
+            let _ = writeln!(res, "</tr>");
+        }
+        let _ = writeln!(res, "</table>");
+    }
+
+    if let Some(category) = results.categories.first() {
+        let _ = writeln!(
+            res,
+            "<h2>Top contributors ({})</h2>\n<table>",
+            escape(&category.name)
+        );
+        let _ = writeln!(
+            res,
+            "<tr><th>Activity</th><th>{}</th><th>Share</th></tr>",
+            escape(&category.unit)
+        );
+        for (row, share) in results.top_contributors(0, TOP_CONTRIBUTORS) {
+            let _ = writeln!(
+                res,
+                "<tr><td>{}</td><td class=\"value\">{:.4e}</td><td class=\"value\">{:.1} %</td></tr>",
+                escape(&row.label),
+                row.values[0],
+                100. * share
+            );
+        }
+        let _ = writeln!(res, "</table>");
+    }
+
+    let _ = writeln!(res, "<h2>Data quality notes</h2>");
+    if results.notes.is_empty() {
+        let _ = writeln!(res, "<p>All exchanges were resolved as specified.</p>");
+    } else {
+        let _ = writeln!(res, "<ul>");
+        for note in results.notes.iter() {
+            let _ = writeln!(res, "<li>{}</li>", escape(note));
+        }
+        let _ = writeln!(res, "</ul>");
+    }
+    let _ = writeln!(res, "</body>\n</html>");
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_escape_cell() {
+        assert_eq!(escape_cell("steel | recycled"), "steel \\| recycled");
+        assert_eq!(escape_cell("steel"), "steel");
+    }
+}
@@ -0,0 +1,33 @@
+use std::{collections::HashMap, path::PathBuf};
+
+use clap::Args;
+use odyssey::{
+    comput::lca::Database, errors::Result, parsers::project::pin_ids, utils::search::Search,
+};
+
+use crate::cli::location::LocationArgs;
+
+#[derive(Debug, Args)]
+#[command(args_conflicts_with_subcommands = true)]
+pub struct ResolveCommand {
+    pub path: PathBuf,
+
+    #[command(flatten)]
+    pub location: LocationArgs,
+}
+
+pub fn cli_resolve(args: &ResolveCommand) -> Result<()> {
+    let search = Search::new()?;
+    let mut databases: HashMap<String, Box<dyn Database>> = HashMap::new();
+    let pinned = pin_ids(&args.path, &search, &mut databases, args.location.policy())?;
+    for note in pinned.notes {
+        eprintln!("{}", note);
+    }
+    if pinned.files.is_empty() {
+        println!("All references are already pinned");
+    }
+    for (path, count) in pinned.files {
+        println!("Pinned {} reference(s) in {}", count, path.display());
+    }
+    Ok(())
+}
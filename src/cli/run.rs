@@ -1,162 +1,75 @@
-use std::path::PathBuf;
-use std::{collections::HashMap, fs::File, io::BufReader, path::Path};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 
 use clap::Args;
-use odyssey::comput::impacts::ImpactCategory;
+use odyssey::comput::results::LcaResults;
+use odyssey::parsers::project::build_foreground;
+use odyssey::utils::geographies::LocationPolicy;
 use odyssey::utils::search::Search;
-use odyssey::{
-    comput::lca::Database, errors::Result, parsers::load_database, utils::matrix::MappedVector,
+use odyssey::{comput::lca::Database, errors::Result};
+
+use crate::cli::{
+    location::LocationArgs,
+    output::{write_results, OutputFormat},
 };
-use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Args)]
 #[command(args_conflicts_with_subcommands = true)]
 pub struct RunCommand {
     pub path: PathBuf,
-}
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct DatabaseInfos {
-    name: String,
-    version: String,
-}
+    /// Also print the direct contribution of each foreground activity
+    #[arg(short, long, default_value_t = false)]
+    pub contributions: bool,
 
-#[derive(Debug, Serialize, Deserialize)]
-#[serde(untagged)]
-pub enum ExchangeLink {
-    File { file: String },
-    Database { database: DatabaseInfos },
-}
+    #[command(flatten)]
+    pub location: LocationArgs,
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct Exchange {
-    #[serde(flatten)]
-    link: ExchangeLink,
-    location: Option<String>,
-    unit: Option<String>,
-    name: Option<String>,
-    amount: f64,
-}
+    #[arg(short, long, value_enum, default_value_t = OutputFormat::Table)]
+    pub format: OutputFormat,
+
+    /// Write the results to this file instead of stdout
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct Activity {
-    exchanges: Vec<Exchange>,
+    /// Express the results per this amount of the functional unit of the project
+    #[arg(long)]
+    pub per: Option<f64>,
 }
 
-fn import_from_database(
-    database_infos: &DatabaseInfos,
-    databases: &mut HashMap<String, Box<dyn Database>>,
-    rfs: &mut HashMap<String, MappedVector<String>>,
-    search: &Search,
-    exchange: &Exchange,
-    amount: f64,
-) -> Result<()> {
-    let database_name = format!("{}_{}", database_infos.name, database_infos.version);
-    let exchange_name = exchange.name.clone().unwrap();
-    let id = search.search_for_ids(
-        &exchange_name,
-        Some(&database_name),
-        exchange.location.as_deref(),
-        exchange.unit.as_deref(),
+pub fn run_lca(args: &RunCommand) -> Result<()> {
+    let search = Search::new()?;
+    let mut databases: HashMap<String, Box<dyn Database>> = HashMap::new();
+    let mut results = compute_results(
+        &args.path,
+        &search,
+        &mut databases,
+        args.location.policy(),
+        args.contributions,
     )?;
-    match &id[..] {
-        [] => panic!("No matching activity for {}", exchange_name),
-        [a] => {
-            let database = databases
-                .entry(database_name.clone())
-                .or_insert(load_database(
-                    &database_infos.name,
-                    &database_infos.version,
-                )?);
-
-            let local_rf = rfs
-                .entry(database_name)
-                .or_insert(database.empty_reference_flow());
-
-            local_rf.set(a.clone(), amount * exchange.amount).unwrap();
-        }
-        _ => panic!("Multiple matching activities for {}", exchange_name),
+    if let Some(per) = args.per {
+        results.per(per)?;
+    }
+    for note in results.notes.iter() {
+        eprintln!("{}", note);
     }
-    Ok(())
+    write_results(&results, args.format, args.output.as_deref())
 }
 
-fn import_from_file(
+/// Builds the foreground of the project at `path` and assesses it.
+/// The databases loaded in `databases` are reused.
+pub fn compute_results(
     path: &Path,
-    databases: &mut HashMap<String, Box<dyn Database>>,
-    rfs: &mut HashMap<String, MappedVector<String>>,
     search: &Search,
-    amount: f64,
-) -> Result<()> {
-    let file = File::open(path)?;
-    let reader = BufReader::new(&file);
-    let activity: Activity = serde_yaml::from_reader(reader)?;
-
-    for e in activity.exchanges {
-        import_flow(&e, databases, rfs, search, amount)?;
-    }
-    Ok(())
-}
-
-fn import_flow(
-    e: &Exchange,
     databases: &mut HashMap<String, Box<dyn Database>>,
-    rfs: &mut HashMap<String, MappedVector<String>>,
-    search: &Search,
-    amount: f64,
-) -> Result<()> {
-    match &e.link {
-        ExchangeLink::File { file } => {
-            import_from_file(Path::new(file), databases, rfs, search, amount * e.amount)?
-        }
-        ExchangeLink::Database { database } => {
-            import_from_database(database, databases, rfs, search, e, amount)?
-        }
-    }
-    Ok(())
-}
-
-pub fn run_lca(path: &Path) -> Result<()> {
-    let search = Search::new()?;
-
-    let file = File::open(path)?;
-    let reader = BufReader::new(&file);
-    let activity: Activity = serde_yaml::from_reader(reader)?;
-
-    let mut global_res = ImpactCategory::get_empty_vector();
-    print!("\"flow\"");
-    for i in 0..global_res.values.len() {
-        if let Some(ImpactCategory::EF31(e)) = global_res.mapping.get_by_right(&i) {
-            print!(";{:?}", e);
-        }
-    }
-    println!();
-
-    for e in activity.exchanges {
-        let mut res = ImpactCategory::get_empty_vector();
-        let mut databases: HashMap<String, Box<dyn Database>> = HashMap::new();
-        let mut rfs: HashMap<String, MappedVector<String>> = HashMap::new();
-        import_flow(&e, &mut databases, &mut rfs, &search, 1f64)?;
-
-        for (db, rf) in rfs.iter() {
-            res += databases.get_mut(db).unwrap().lca(rf)?;
-        }
-        global_res += res.clone();
-        print!("{:?}", e.name.unwrap_or("None".to_string()));
-        for i in 0..res.values.len() {
-            if let Some(ImpactCategory::EF31(_)) = res.mapping.get_by_right(&i) {
-                print!(";{:.4e}", res.values[i])
-            }
-        }
-        println!();
-    }
-
-    print!("\"all\"");
-    for i in 0..global_res.values.len() {
-        if let Some(ImpactCategory::EF31(_)) = global_res.mapping.get_by_right(&i) {
-            print!(";{:.4e}", global_res.values[i])
-        }
-    }
-    println!();
-
-    Ok(())
+    policy: LocationPolicy,
+    contributions: bool,
+) -> Result<LcaResults> {
+    let mut foreground = build_foreground(path, search, databases, policy)?;
+    LcaResults::compute(
+        path.display().to_string(),
+        &mut foreground,
+        databases,
+        contributions,
+    )
 }
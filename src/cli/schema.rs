@@ -0,0 +1,21 @@
+use std::{fs, path::PathBuf};
+
+use clap::Args;
+use odyssey::{errors::Result, parsers::project::project_schema};
+
+#[derive(Debug, Args)]
+#[command(args_conflicts_with_subcommands = true)]
+pub struct SchemaCommand {
+    /// Write the schema to this file instead of stdout
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+}
+
+pub fn cli_schema(args: &SchemaCommand) -> Result<()> {
+    let schema = serde_json::to_string_pretty(&project_schema())?;
+    match &args.output {
+        Some(path) => fs::write(path, schema + "\n")?,
+        None => println!("{}", schema),
+    }
+    Ok(())
+}
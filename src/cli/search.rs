@@ -1,5 +1,8 @@
 use clap::Args;
-use odyssey::utils::search::Search;
+use odyssey::{
+    errors::Result,
+    utils::search::{ActivityFilters, ActivityType, Search},
+};
 
 #[derive(Debug, Args)]
 #[command(args_conflicts_with_subcommands = true)]
@@ -14,13 +17,30 @@ pub struct SearchCommand {
     #[arg(short, long)]
     pub database: Option<String>,
 
+    /// ISIC section (e.g. `C`) or beginning of the ISIC class (e.g. `24`) of the activities
+    #[arg(long)]
+    pub isic: Option<String>,
+
+    /// Beginning of the CPC code of the products (e.g. `41`)
+    #[arg(long)]
+    pub cpc: Option<String>,
+
+    /// Type of the activities: transformation, market, market-group or other
+    #[arg(long)]
+    pub activity_type: Option<ActivityType>,
+
     #[arg(short, long, default_value_t = false)]
     pub json: bool,
 
     pub query: String,
 }
 
-pub fn cli_search(args: SearchCommand) -> tantivy::Result<()> {
+pub fn cli_search(args: SearchCommand) -> Result<()> {
+    let filters = ActivityFilters {
+        isic: args.isic,
+        cpc: args.cpc,
+        activity_type: args.activity_type,
+    };
     let search_results = Search::new()?;
     let search_results = if args.json {
         search_results.search_for_json(
@@ -28,6 +48,7 @@ pub fn cli_search(args: SearchCommand) -> tantivy::Result<()> {
             args.database.as_deref(),
             args.location.as_deref(),
             args.unit.as_deref(),
+            &filters,
         )?
     } else {
         search_results.search(
@@ -35,6 +56,7 @@ pub fn cli_search(args: SearchCommand) -> tantivy::Result<()> {
             args.database.as_deref(),
             args.location.as_deref(),
             args.unit.as_deref(),
+            &filters,
         )?
     };
     search_results.iter().for_each(|s| println!("{}", s.1));
@@ -0,0 +1,73 @@
+use serde::{Deserialize, Serialize};
+
+use crate::comput::results::{Category, LcaResults};
+
+/// Totals of one impact category for every alternative of a [Comparison].
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ComparisonRow {
+    pub category: Category,
+    /// Total of each alternative.
+    pub values: Vec<f64>,
+    /// Difference of each alternative with the reference.
+    pub absolute: Vec<f64>,
+    /// Difference of each alternative with the reference, relative to the
+    /// reference. `None` if the reference is zero.
+    pub relative: Vec<Option<f64>>,
+    /// Ratio of each alternative to the reference, if requested.
+    /// `None` if the reference is zero.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub normalized: Option<Vec<Option<f64>>>,
+}
+
+/// Comparison of the totals of several alternatives of a product.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Comparison {
+    pub alternatives: Vec<String>,
+    /// Index of the alternative the others are compared to.
+    pub reference: usize,
+    pub rows: Vec<ComparisonRow>,
+}
+
+impl Comparison {
+    /// Compares the totals of `alternatives` to the one at index `reference`,
+    /// and if `normalize`, expresses them as ratios to it.
+    pub fn new(alternatives: &[LcaResults], reference: usize, normalize: bool) -> Self {
+        let categories = alternatives
+            .first()
+            .map(|a| a.categories.clone())
+            .unwrap_or_default();
+        let totals: Vec<&[f64]> = alternatives
+            .iter()
+            .map(|a| a.total().unwrap_or_default())
+            .collect();
+        let rows = categories
+            .into_iter()
+            .enumerate()
+            .map(|(i, category)| {
+                let values: Vec<f64> = totals
+                    .iter()
+                    .map(|t| t.get(i).copied().unwrap_or(0.))
+                    .collect();
+                let reference_value = values.get(reference).copied().unwrap_or(0.);
+                let ratio = |v: f64| (reference_value != 0.).then(|| v / reference_value.abs());
+                ComparisonRow {
+                    category,
+                    absolute: values.iter().map(|v| v - reference_value).collect(),
+                    relative: values.iter().map(|v| ratio(v - reference_value)).collect(),
+                    normalized: normalize.then(|| {
+                        values
+                            .iter()
+                            .map(|v| (reference_value != 0.).then(|| v / reference_value))
+                            .collect()
+                    }),
+                    values,
+                }
+            })
+            .collect();
+        Self {
+            alternatives: alternatives.iter().map(|a| a.project.clone()).collect(),
+            reference,
+            rows,
+        }
+    }
+}
@@ -0,0 +1,633 @@
+use std::collections::HashMap;
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    comput::{impacts::ImpactCategory, lca::Database},
+    errors::{OdysseyErrors, Result},
+    utils::matrix::{MappedMatrix, MappedMatrixBuilder, MappedVector},
+};
+
+/// Label of the impacts not assigned to any life cycle stage.
+pub const UNASSIGNED_STAGE: &str = "unassigned";
+
+/// What the reference activity of a project delivers, e.g. 10 m2 of panel.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, JsonSchema)]
+pub struct FunctionalUnit {
+    pub name: String,
+    pub amount: f64,
+    pub unit: String,
+}
+
+impl std::fmt::Display for FunctionalUnit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {} of {}", self.amount, self.unit, self.name)
+    }
+}
+
+/// Target of a foreground exchange, once resolved.
+#[derive(Debug, Clone)]
+pub enum ForegroundLink {
+    /// Reference product of another foreground activity.
+    Activity(String),
+    /// Activity `id` of the background `database`.
+    Database { database: String, id: String },
+    /// Elementary flow `id` of the background `database`.
+    Biosphere { database: String, id: String },
+}
+
+#[derive(Debug, Clone)]
+pub struct ForegroundExchange {
+    pub name: Option<String>,
+    pub link: ForegroundLink,
+    /// Amount required to produce one unit of the activity.
+    /// Outputs (co-products, avoided products) are negative.
+    pub amount: f64,
+    /// Life cycle stage the exchange is assigned to, if any.
+    pub stage: Option<String>,
+}
+
+/// Builder for [Foreground].
+#[derive(Debug, Default)]
+pub struct ForegroundBuilder {
+    root: String,
+    activities: Vec<(String, String)>,
+    exchanges: HashMap<String, Vec<ForegroundExchange>>,
+    stages: HashMap<String, String>,
+    functional_unit: Option<FunctionalUnit>,
+    notes: Vec<String>,
+}
+
+impl ForegroundBuilder {
+    /// Creates a builder whose reference activity is `root`.
+    pub fn new(root: String) -> Self {
+        Self {
+            root,
+            activities: vec![],
+            exchanges: HashMap::new(),
+            stages: HashMap::new(),
+            functional_unit: None,
+            notes: vec![],
+        }
+    }
+
+    pub fn contains(&self, id: &str) -> bool {
+        self.exchanges.contains_key(id)
+    }
+
+    /// Adds the activity `id`, displayed as `name`.
+    pub fn add_activity(&mut self, id: String, name: String) {
+        if !self.contains(&id) {
+            self.exchanges.insert(id.clone(), vec![]);
+            self.activities.push((id, name));
+        }
+    }
+
+    pub fn add_exchange(&mut self, activity: &str, exchange: ForegroundExchange) {
+        if let Some(exchanges) = self.exchanges.get_mut(activity) {
+            exchanges.push(exchange);
+        }
+    }
+
+    /// Assigns the whole `activity` to `stage`, unless the exchange that
+    /// consumes it is assigned to another stage.
+    pub fn set_stage(&mut self, activity: &str, stage: String) {
+        self.stages.insert(activity.to_string(), stage);
+    }
+
+    /// Sets what one unit of the reference activity delivers.
+    pub fn set_functional_unit(&mut self, functional_unit: FunctionalUnit) {
+        self.functional_unit = Some(functional_unit);
+    }
+
+    /// Records a remark about how the system was built (e.g. a substituted location).
+    pub fn add_note(&mut self, note: String) {
+        self.notes.push(note);
+    }
+
+    pub fn build(self) -> Foreground {
+        let mut technology = MappedMatrixBuilder::new();
+        for (id, _) in self.activities.iter() {
+            technology.add_col(id.clone());
+            technology.add_row(id.clone());
+            technology.add_triplet(id.clone(), id.clone(), 1.);
+        }
+        for (activity, exchanges) in self.exchanges.iter() {
+            for exchange in exchanges {
+                if let ForegroundLink::Activity(product) = &exchange.link {
+                    technology.add_triplet(product.clone(), activity.clone(), -exchange.amount);
+                }
+            }
+        }
+        let technology = technology.build();
+
+        let mut links: HashMap<String, MappedMatrixBuilder<String, String>> = HashMap::new();
+        let mut interventions: HashMap<String, MappedMatrixBuilder<String, String>> =
+            HashMap::new();
+        for (activity, exchanges) in self.exchanges.iter() {
+            for exchange in exchanges {
+                let (matrices, database, id) = match &exchange.link {
+                    ForegroundLink::Activity(_) => continue,
+                    ForegroundLink::Database { database, id } => (&mut links, database, id),
+                    ForegroundLink::Biosphere { database, id } => {
+                        (&mut interventions, database, id)
+                    }
+                };
+                matrices
+                    .entry(database.clone())
+                    .or_insert_with(|| {
+                        let mut matrix = MappedMatrixBuilder::new();
+                        matrix.copy_cols_into_cols(&technology);
+                        matrix
+                    })
+                    .add_triplet(id.clone(), activity.clone(), exchange.amount);
+            }
+        }
+
+        Foreground {
+            root: self.root,
+            activities: self.activities,
+            exchanges: self.exchanges,
+            stages: self.stages,
+            functional_unit: self.functional_unit,
+            notes: self.notes,
+            technology,
+            links: links.into_iter().map(|(k, v)| (k, v.build())).collect(),
+            interventions: interventions
+                .into_iter()
+                .map(|(k, v)| (k, v.build()))
+                .collect(),
+        }
+    }
+}
+
+/// Foreground system of a project.
+///
+/// Each foreground activity produces one unit of its reference product, and
+/// consumes products of other foreground activities (technology matrix),
+/// activities of background databases (one link matrix per database), or
+/// directly exchanges elementary flows of background databases (one
+/// intervention matrix per database).
+#[derive(Debug)]
+pub struct Foreground {
+    root: String,
+    activities: Vec<(String, String)>,
+    exchanges: HashMap<String, Vec<ForegroundExchange>>,
+    stages: HashMap<String, String>,
+    functional_unit: Option<FunctionalUnit>,
+    notes: Vec<String>,
+    technology: MappedMatrix<String, String>,
+    links: HashMap<String, MappedMatrix<String, String>>,
+    interventions: HashMap<String, MappedMatrix<String, String>>,
+}
+
+impl Foreground {
+    /// Id of the reference activity of the system.
+    pub fn root(&self) -> &str {
+        &self.root
+    }
+
+    /// Ids and display names of the foreground activities.
+    pub fn activities(&self) -> &[(String, String)] {
+        &self.activities
+    }
+
+    /// Exchanges of the foreground `activity`.
+    pub fn exchanges(&self, activity: &str) -> &[ForegroundExchange] {
+        self.exchanges
+            .get(activity)
+            .map(|e| e.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// What one unit of the reference activity delivers, if specified.
+    pub fn functional_unit(&self) -> Option<&FunctionalUnit> {
+        self.functional_unit.as_ref()
+    }
+
+    /// Remarks recorded while building the system.
+    pub fn notes(&self) -> &[String] {
+        &self.notes
+    }
+
+    /// Names of the background databases linked to the system, sorted.
+    pub fn databases(&self) -> Vec<String> {
+        let mut res: Vec<String> = self
+            .links
+            .keys()
+            .chain(self.interventions.keys())
+            .cloned()
+            .collect();
+        res.sort();
+        res.dedup();
+        res
+    }
+
+    pub fn empty_reference_flow(&self) -> MappedVector<String> {
+        self.technology.zeros_like_rows()
+    }
+
+    /// Reference flow of one unit of the root activity.
+    pub fn reference_flow(&self) -> MappedVector<String> {
+        let mut f = self.empty_reference_flow();
+        f.set(self.root.clone(), 1.);
+        f
+    }
+
+    /// Scaling of each foreground activity required to deliver `f`.
+    ///
+    /// Fails if the technology matrix is singular, e.g. when a loop of activities
+    /// consumes as much as it produces.
+    pub fn supply(&mut self, f: &MappedVector<String>) -> Result<MappedVector<String>> {
+        if !self.technology.is_invertible() {
+            return Err(OdysseyErrors::SingularForeground(self.root.clone()));
+        }
+        Ok(self.technology.solve(f))
+    }
+
+    /// Performs the life cycle assessment of the foreground reference flow `f`.
+    pub fn lca(
+        &mut self,
+        f: &MappedVector<String>,
+        databases: &mut HashMap<String, Box<dyn Database>>,
+    ) -> Result<MappedVector<ImpactCategory>> {
+        let s = self.supply(f)?;
+        let mut res = ImpactCategory::get_empty_vector();
+        for (database, link) in self.links.iter_mut() {
+            let demand = link.dot(&s);
+            res += background_lca(databases, database, false, vector_flows(&demand))?;
+        }
+        for (database, intervention) in self.interventions.iter_mut() {
+            let emissions = intervention.dot(&s);
+            res += background_lca(databases, database, true, vector_flows(&emissions))?;
+        }
+        Ok(res)
+    }
+
+    /// Performs the life cycle assessment of the `index`th exchange of `activity`.
+    pub fn lca_exchange(
+        &mut self,
+        activity: &str,
+        index: usize,
+        databases: &mut HashMap<String, Box<dyn Database>>,
+    ) -> Result<MappedVector<ImpactCategory>> {
+        let exchange =
+            self.exchanges(activity)
+                .get(index)
+                .cloned()
+                .ok_or(OdysseyErrors::MissingId(format!(
+                    "No exchange {index} in foreground activity {activity}"
+                )))?;
+        match exchange.link {
+            ForegroundLink::Activity(product) => {
+                let mut f = self.empty_reference_flow();
+                f.set(product, exchange.amount);
+                self.lca(&f, databases)
+            }
+            ForegroundLink::Database { database, id } => {
+                background_lca(databases, &database, false, vec![(id, exchange.amount)])
+            }
+            ForegroundLink::Biosphere { database, id } => {
+                background_lca(databases, &database, true, vec![(id, exchange.amount)])
+            }
+        }
+    }
+
+    /// Impacts of the direct exchanges of each foreground activity, scaled
+    /// by the supply required to deliver `f`.
+    pub fn contributions(
+        &mut self,
+        f: &MappedVector<String>,
+        databases: &mut HashMap<String, Box<dyn Database>>,
+    ) -> Result<Vec<(String, MappedVector<ImpactCategory>)>> {
+        let s = self.supply(f)?;
+        let mut res = vec![];
+        for (id, name) in self.activities.iter() {
+            let scale = s.row(id).map(|i| s.values[*i]).unwrap_or(0.);
+            let mut flows: HashMap<(&str, bool), Vec<(String, f64)>> = HashMap::new();
+            for exchange in self.exchanges(id) {
+                let (database, id, biosphere) = match &exchange.link {
+                    ForegroundLink::Activity(_) => continue,
+                    ForegroundLink::Database { database, id } => (database, id, false),
+                    ForegroundLink::Biosphere { database, id } => (database, id, true),
+                };
+                flows
+                    .entry((database, biosphere))
+                    .or_default()
+                    .push((id.clone(), scale * exchange.amount));
+            }
+            let mut impacts = ImpactCategory::get_empty_vector();
+            for ((database, biosphere), flows) in flows {
+                impacts += background_lca(databases, database, biosphere, flows)?;
+            }
+            res.push((name.clone(), impacts));
+        }
+        Ok(res)
+    }
+
+    /// Whether any exchange or activity is assigned to a life cycle stage.
+    pub fn has_stages(&self) -> bool {
+        !self.stages.is_empty() || self.exchanges.values().flatten().any(|e| e.stage.is_some())
+    }
+
+    /// Stage of an exchange: its own, or for a foreground product, the stage
+    /// of the activity producing it.
+    fn exchange_stage(&self, exchange: &ForegroundExchange) -> Option<String> {
+        exchange.stage.clone().or_else(|| match &exchange.link {
+            ForegroundLink::Activity(product) => self.stages.get(product).cloned(),
+            _ => None,
+        })
+    }
+
+    /// Impacts of delivering `f`, aggregated per life cycle stage, in order
+    /// of first appearance. Impacts not assigned to any stage are reported
+    /// as [UNASSIGNED_STAGE].
+    ///
+    /// The supply of an activity consumed through an exchange without stage
+    /// is shared between the stages of the activities consuming it, in
+    /// proportion of what they consume.
+    pub fn stages(
+        &mut self,
+        f: &MappedVector<String>,
+        databases: &mut HashMap<String, Box<dyn Database>>,
+    ) -> Result<Vec<(String, MappedVector<ImpactCategory>)>> {
+        let s = self.supply(f)?;
+        let scale =
+            |v: &MappedVector<String>, id: &String| v.row(id).map(|i| v.values[*i]).unwrap_or(0.);
+
+        // Supply of each activity assigned to each stage: x_s = P^-1 g_s where
+        // P only propagates through the exchanges without stage, and g_s is
+        // the supply directly assigned to s.
+        let mut propagation = MappedMatrixBuilder::new();
+        for (id, _) in self.activities.iter() {
+            propagation.add_col(id.clone());
+            propagation.add_row(id.clone());
+            propagation.add_triplet(id.clone(), id.clone(), 1.);
+        }
+        let mut stages: Vec<(String, MappedVector<String>)> = vec![];
+        let mut assign = |stage: Option<String>, activity: &String, amount: f64| {
+            let stage = stage.unwrap_or(UNASSIGNED_STAGE.to_string());
+            let index = match stages.iter().position(|(s, _)| *s == stage) {
+                Some(index) => index,
+                None => {
+                    stages.push((stage, self.empty_reference_flow()));
+                    stages.len() - 1
+                }
+            };
+            stages[index].1.set(activity.clone(), amount);
+        };
+        for (id, _) in self.activities.iter() {
+            let demand = scale(f, id);
+            if demand != 0. {
+                assign(self.stages.get(id).cloned(), id, demand);
+            }
+            for exchange in self.exchanges(id) {
+                let ForegroundLink::Activity(product) = &exchange.link else {
+                    // Background exchanges keep their own stage, which must be reported
+                    if exchange.stage.is_some() {
+                        assign(exchange.stage.clone(), id, 0.);
+                    }
+                    continue;
+                };
+                match self.exchange_stage(exchange) {
+                    Some(stage) => assign(Some(stage), product, scale(&s, id) * exchange.amount),
+                    None => propagation.add_triplet(product.clone(), id.clone(), -exchange.amount),
+                }
+            }
+        }
+        let mut propagation = propagation.build();
+        if !propagation.is_invertible() {
+            return Err(OdysseyErrors::SingularForeground(self.root.clone()));
+        }
+        let stages: Vec<(String, MappedVector<String>)> = stages
+            .into_iter()
+            .map(|(stage, g)| (stage, propagation.solve(&g)))
+            .collect();
+
+        let mut res = vec![];
+        for (stage, x) in stages.iter() {
+            let mut flows: HashMap<(&str, bool), Vec<(String, f64)>> = HashMap::new();
+            for (id, _) in self.activities.iter() {
+                for exchange in self.exchanges(id) {
+                    let (database, flow, biosphere) = match &exchange.link {
+                        ForegroundLink::Activity(_) => continue,
+                        ForegroundLink::Database { database, id } => (database, id, false),
+                        ForegroundLink::Biosphere { database, id } => (database, id, true),
+                    };
+                    let amount = match &exchange.stage {
+                        Some(s_exchange) if s_exchange == stage => scale(&s, id) * exchange.amount,
+                        Some(_) => continue,
+                        None => scale(x, id) * exchange.amount,
+                    };
+                    flows
+                        .entry((database, biosphere))
+                        .or_default()
+                        .push((flow.clone(), amount));
+                }
+            }
+            let mut impacts = ImpactCategory::get_empty_vector();
+            for ((database, biosphere), flows) in flows {
+                impacts += background_lca(databases, database, biosphere, flows)?;
+            }
+            res.push((stage.clone(), impacts));
+        }
+
+        Ok(res)
+    }
+}
+
+fn vector_flows(v: &MappedVector<String>) -> Vec<(String, f64)> {
+    v.mapping
+        .iter()
+        .map(|(id, index)| (id.clone(), v.values[*index]))
+        .collect()
+}
+
+/// Performs the life cycle assessment of the background `flows` of `database`.
+/// If `biosphere` is set, `flows` are elementary flows that are directly characterized.
+fn background_lca(
+    databases: &mut HashMap<String, Box<dyn Database>>,
+    database: &str,
+    biosphere: bool,
+    flows: Vec<(String, f64)>,
+) -> Result<MappedVector<ImpactCategory>> {
+    let database = databases
+        .get_mut(database)
+        .ok_or(OdysseyErrors::MissingDatabase(database.to_string()))?;
+    let mut v = if biosphere {
+        database.empty_inventory()
+    } else {
+        database.empty_reference_flow()
+    };
+    for (id, amount) in flows {
+        if v.set(id.clone(), amount).is_none() {
+            return Err(OdysseyErrors::MissingId(format!(
+                "Missing flow {id:?} in {}",
+                database.name()
+            )));
+        }
+    }
+    if biosphere {
+        database.lcia(&v)
+    } else {
+        database.lca(&v)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::{
+        comput::impacts::EF31,
+        utils::search::{ElementaryItem, InventoryItem},
+        MV,
+    };
+
+    /// Background database whose impact in `Gwp100` is the sum of its reference flow.
+    struct Flat;
+
+    impl Database for Flat {
+        fn name(&self) -> String {
+            "flat".to_string()
+        }
+        fn list_candidates(&self) -> Vec<&InventoryItem> {
+            vec![]
+        }
+        fn find_candidate(&self, _: &str) -> Option<&InventoryItem> {
+            None
+        }
+        fn lci(&mut self, f: &MappedVector<String>) -> Result<MappedVector<String>> {
+            Ok(f.clone())
+        }
+        fn lcia(&mut self, s: &MappedVector<String>) -> Result<MappedVector<ImpactCategory>> {
+            let mut res = ImpactCategory::get_empty_vector();
+            res.set(ImpactCategory::EF31(EF31::Gwp100), s.values.iter().sum());
+            Ok(res)
+        }
+        fn list_elementaries(&self) -> Vec<&ElementaryItem> {
+            vec![]
+        }
+        fn find_elementary(&self, _: &str) -> Option<&ElementaryItem> {
+            None
+        }
+        fn empty_reference_flow(&self) -> MappedVector<String> {
+            MV!["x".to_string() => 0., "y".to_string() => 0.]
+        }
+        fn empty_inventory(&self) -> MappedVector<String> {
+            MV!["co2".to_string() => 0., "ch4".to_string() => 0.]
+        }
+        fn empty_impacts(&self) -> MappedVector<ImpactCategory> {
+            ImpactCategory::get_empty_vector()
+        }
+    }
+
+    fn exchange(link: ForegroundLink, amount: f64) -> ForegroundExchange {
+        ForegroundExchange {
+            name: None,
+            link,
+            amount,
+            stage: None,
+        }
+    }
+
+    /// `a` consumes 2 `b`, and `b` consumes 0.2 `a` (loop), 1 `x` and
+    /// produces 0.25 `y` (co-product) from the background. `a` directly
+    /// emits 0.1 `co2`.
+    ///
+    /// s_a = 1 + 0.2 s_b, s_b = 2 s_a  =>  s_a = 5/3, s_b = 10/3
+    /// Background demand: x = 10/3, y = -5/6, emissions: co2 = 1/6
+    #[test]
+    fn test_loop_and_coproduct() {
+        let mut builder = ForegroundBuilder::new("a".to_string());
+        builder.add_activity("a".to_string(), "a".to_string());
+        builder.add_activity("b".to_string(), "b".to_string());
+        builder.add_exchange("a", exchange(ForegroundLink::Activity("b".to_string()), 2.));
+        builder.add_exchange(
+            "b",
+            exchange(ForegroundLink::Activity("a".to_string()), 0.2),
+        );
+        let db = |id: &str| ForegroundLink::Database {
+            database: "flat".to_string(),
+            id: id.to_string(),
+        };
+        builder.add_exchange("b", exchange(db("x"), 1.));
+        builder.add_exchange("b", exchange(db("y"), -0.25));
+        let co2 = ForegroundLink::Biosphere {
+            database: "flat".to_string(),
+            id: "co2".to_string(),
+        };
+        builder.add_exchange("a", exchange(co2, 0.1));
+        let mut foreground = builder.build();
+
+        let mut databases: HashMap<String, Box<dyn Database>> = HashMap::new();
+        databases.insert("flat".to_string(), Box::new(Flat));
+
+        let f = foreground.reference_flow();
+        let s = foreground.supply(&f).unwrap();
+        assert!((s.values[*s.row(&"a".to_string()).unwrap()] - 5. / 3.).abs() < 1e-9);
+        assert!((s.values[*s.row(&"b".to_string()).unwrap()] - 10. / 3.).abs() < 1e-9);
+
+        let gwp = ImpactCategory::EF31(EF31::Gwp100);
+        let total = foreground.lca(&f, &mut databases).unwrap();
+        assert!((total.values[*total.row(&gwp).unwrap()] - (2.5 + 1. / 6.)).abs() < 1e-9);
+    }
+
+    /// `a` consumes 1 `x` (no stage) and 2 `b` in `materials`. `b` consumes
+    /// 0.5 `x` and 1 `c`, whose file is in `transport`. `c` consumes 1 `x`.
+    #[test]
+    fn test_stages() {
+        let mut builder = ForegroundBuilder::new("a".to_string());
+        for id in ["a", "b", "c"] {
+            builder.add_activity(id.to_string(), id.to_string());
+        }
+        builder.set_stage("c", "transport".to_string());
+        let x = || ForegroundLink::Database {
+            database: "flat".to_string(),
+            id: "x".to_string(),
+        };
+        builder.add_exchange("a", exchange(x(), 1.));
+        let mut materials = exchange(ForegroundLink::Activity("b".to_string()), 2.);
+        materials.stage = Some("materials".to_string());
+        builder.add_exchange("a", materials);
+        builder.add_exchange("b", exchange(x(), 0.5));
+        builder.add_exchange("b", exchange(ForegroundLink::Activity("c".to_string()), 1.));
+        builder.add_exchange("c", exchange(x(), 1.));
+        let mut foreground = builder.build();
+
+        let mut databases: HashMap<String, Box<dyn Database>> = HashMap::new();
+        databases.insert("flat".to_string(), Box::new(Flat));
+
+        let gwp = ImpactCategory::EF31(EF31::Gwp100);
+        let f = foreground.reference_flow();
+        let stages: HashMap<String, f64> = foreground
+            .stages(&f, &mut databases)
+            .unwrap()
+            .into_iter()
+            .map(|(stage, impacts)| (stage, impacts.values[*impacts.row(&gwp).unwrap()]))
+            .collect();
+        assert_eq!(stages.len(), 3);
+        assert!((stages[UNASSIGNED_STAGE] - 1.).abs() < 1e-9);
+        assert!((stages["materials"] - 1.).abs() < 1e-9);
+        assert!((stages["transport"] - 2.).abs() < 1e-9);
+    }
+
+    /// `a` and `b` each consume one unit of the other, so that nothing is left.
+    #[test]
+    fn test_singular_loop() {
+        let mut builder = ForegroundBuilder::new("a".to_string());
+        for id in ["a", "b"] {
+            builder.add_activity(id.to_string(), id.to_string());
+        }
+        builder.add_exchange("a", exchange(ForegroundLink::Activity("b".to_string()), 1.));
+        builder.add_exchange("b", exchange(ForegroundLink::Activity("a".to_string()), 1.));
+        let mut foreground = builder.build();
+
+        let f = foreground.reference_flow();
+        assert!(matches!(
+            foreground.supply(&f),
+            Err(OdysseyErrors::SingularForeground(root)) if root == "a"
+        ));
+    }
+}
@@ -3,7 +3,7 @@ use serde::{Deserialize, Serialize};
 use strum::IntoEnumIterator;
 use strum_macros::EnumIter;
 
-use crate::comput::impacts::ImpactCategory;
+use crate::comput::impacts::{CategoryMetadata, ImpactCategory};
 
 #[derive(PartialEq, std::cmp::Eq, Clone, Serialize, Deserialize, Debug, Hash, EnumIter)]
 pub enum EF31 {
@@ -42,4 +42,40 @@ impl EF31 {
         });
         mapping
     }
+
+    pub fn metadata(&self) -> CategoryMetadata {
+        let (name, unit, description) = match self {
+            EF31::Gwp100 => ("Climate change", "kg CO2-Eq", "Radiative forcing over 100 years of greenhouse gas emissions and removals"),
+            EF31::Acidification => ("Acidification", "mol H+-Eq", "Accumulated exceedance of the critical load of acidifying deposition"),
+            EF31::BiogenicGwp100 => ("Climate change: biogenic", "kg CO2-Eq", "Climate change from biogenic carbon emissions and removals"),
+            EF31::FossilGwp100 => ("Climate change: fossil", "kg CO2-Eq", "Climate change from fossil greenhouse gas emissions"),
+            EF31::ClimateChangeLandUse => ("Climate change: land use and land use change", "kg CO2-Eq", "Climate change from carbon stock changes due to land use and land transformation"),
+            EF31::ParticulMatter => ("Particulate matter formation", "disease incidence", "Disease incidence due to emissions of fine particulate matter and its precursors"),
+            EF31::EcotoxicityFreshwater => ("Ecotoxicity: freshwater", "CTUe", "Potentially affected fraction of freshwater species due to toxic emissions"),
+            EF31::EcotoxicityFreshwaterInorganics => ("Ecotoxicity: freshwater, inorganics", "CTUe", "Freshwater ecotoxicity due to inorganic substances"),
+            EF31::EcotoxicityFreshwaterOrganics => ("Ecotoxicity: freshwater, organics", "CTUe", "Freshwater ecotoxicity due to organic substances"),
+            EF31::EutrophicationMarine => ("Eutrophication: marine", "kg N-Eq", "Nitrogen reaching marine waters and causing algal growth"),
+            EF31::EutrophicationFreshwater => ("Eutrophication: freshwater", "kg P-Eq", "Phosphorus reaching freshwater and causing algal growth"),
+            EF31::EutrophicationTerrestrial => ("Eutrophication: terrestrial", "mol N-Eq", "Accumulated exceedance of the critical load of nitrogen deposition on soils"),
+            EF31::HumanToxicityCarcinogenic => ("Human toxicity: carcinogenic", "CTUh", "Cancer cases due to toxic emissions"),
+            EF31::HumanToxicityCarcinogenicInorganics => ("Human toxicity: carcinogenic, inorganics", "CTUh", "Cancer cases due to inorganic substances"),
+            EF31::HumanToxicityCarcinogenicOrganics => ("Human toxicity: carcinogenic, organics", "CTUh", "Cancer cases due to organic substances"),
+            EF31::HumanToxicityNonCacrinogenic => ("Human toxicity: non-carcinogenic", "CTUh", "Non-cancer disease cases due to toxic emissions"),
+            EF31::HumanToxicityNonCacinogenicInorganics => ("Human toxicity: non-carcinogenic, inorganics", "CTUh", "Non-cancer disease cases due to inorganic substances"),
+            EF31::HumanToxicityNonCacinogenicOrganics => ("Human toxicity: non-carcinogenic, organics", "CTUh", "Non-cancer disease cases due to organic substances"),
+            EF31::IonisingRadiation => ("Ionising radiation: human health", "kBq U235-Eq", "Human exposure to ionising radiation, relative to uranium 235"),
+            EF31::LandUse => ("Land use", "dimensionless", "Soil quality index aggregating erosion, filtration, groundwater and production impacts"),
+            EF31::OzoneDepletion => ("Ozone depletion", "kg CFC-11-Eq", "Destruction of stratospheric ozone, relative to CFC-11"),
+            EF31::PhotochemicalOxidant => ("Photochemical oxidant formation: human health", "kg NMVOC-Eq", "Tropospheric ozone formation, relative to non-methane volatile organic compounds"),
+            EF31::EnergyResourcesNonRenewable => ("Energy resources: non-renewable", "MJ, net calorific value", "Depletion of fossil and nuclear energy carriers"),
+            EF31::EnergyResourcesMetalsMinerals => ("Material resources: metals/minerals", "kg Sb-Eq", "Depletion of abiotic resources, relative to antimony"),
+            EF31::WaterUse => ("Water use: user deprivation potential", "m3 world-Eq deprived", "Water consumption weighted by the scarcity of the watershed"),
+        };
+        CategoryMetadata {
+            name,
+            unit,
+            method: "EF v3.1",
+            description,
+        }
+    }
 }
@@ -9,6 +9,16 @@ pub use ef31::EF31;
 
 use crate::utils::matrix::MappedVector;
 
+/// Descriptive information about an impact category, for outputs.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq)]
+pub struct CategoryMetadata {
+    pub name: &'static str,
+    pub unit: &'static str,
+    /// Impact assessment method defining the category
+    pub method: &'static str,
+    pub description: &'static str,
+}
+
 #[derive(PartialEq, std::cmp::Eq, Clone, Serialize, Deserialize, Debug, Hash)]
 pub enum ImpactCategory {
     EF31(EF31),
@@ -21,4 +31,18 @@ impl ImpactCategory {
         let length = mappings.len();
         MappedVector::new(Arc::new(mappings), vec![0.; length])
     }
+
+    /// Stable identifier of the category, e.g. `EF31/Gwp100`.
+    pub fn id(&self) -> String {
+        match self {
+            ImpactCategory::EF31(c) => format!("EF31/{:?}", c),
+        }
+    }
+
+    /// Display name, unit and description of the category.
+    pub fn metadata(&self) -> CategoryMetadata {
+        match self {
+            ImpactCategory::EF31(c) => c.metadata(),
+        }
+    }
 }
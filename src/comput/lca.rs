@@ -1,9 +1,65 @@
+use serde::{Deserialize, Serialize};
+
 use crate::{
     comput::impacts::ImpactCategory,
-    errors::Result,
-    utils::{matrix::MappedVector, search::InventoryItem},
+    errors::{OdysseyErrors, Result},
+    utils::{
+        geographies::Geographies,
+        matrix::{MappedMatrix, MappedVector},
+        search::{ElementaryItem, InventoryItem},
+    },
 };
 
+/// Technosphere exchange of an activity with an activity of another database.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Link {
+    /// Name and version of the database of the consumed activity.
+    pub database: String,
+    pub version: String,
+    /// Id of the consumed activity.
+    pub id: String,
+    /// Id of the consuming activity.
+    pub activity: String,
+    /// Amount consumed to produce one unit of `activity`. Outputs are negative.
+    pub amount: f64,
+}
+
+impl Link {
+    /// Name under which the database of the consumed activity is cached.
+    pub fn database_name(&self) -> String {
+        format!("{}_{}", self.database, self.version)
+    }
+}
+
+/// Technology, intervention and characterization matrices of a database.
+pub struct Matrices<'a> {
+    pub technology: &'a MappedMatrix<String, String>,
+    pub intervention: &'a MappedMatrix<String, String>,
+    pub characterization: &'a MappedMatrix<ImpactCategory, String>,
+}
+
+impl<'a> Matrices<'a> {
+    /// Matrices of `database`, which must expose them to be linked to other
+    /// databases or exported.
+    pub fn new(database: &'a dyn Database) -> Result<Self> {
+        match (
+            database.technology(),
+            database.intervention(),
+            database.characterization(),
+        ) {
+            (Some(technology), Some(intervention), Some(characterization)) => Ok(Matrices {
+                technology,
+                intervention,
+                characterization,
+            }),
+            _ => Err(OdysseyErrors::InvalidDatabase(format!(
+                "{} has no matrices, it can't be linked or exported",
+                database.name()
+            ))),
+        }
+    }
+}
+
 pub trait Database {
     /// Name of the database
     fn name(&self) -> String;
@@ -12,6 +68,58 @@ pub trait Database {
 
     fn find_candidate(&self, id: &str) -> Option<&InventoryItem>;
 
+    /// Elementary flows of the intervention matrix.
+    fn list_elementaries(&self) -> Vec<&ElementaryItem>;
+
+    fn find_elementary(&self, id: &str) -> Option<&ElementaryItem>;
+
+    /// Elementary flows matching `name` in the given compartment.
+    /// Matching is case insensitive, and `subcompartment` is only checked if specified.
+    fn search_elementaries(
+        &self,
+        name: &str,
+        compartment: &str,
+        subcompartment: Option<&str>,
+    ) -> Vec<&ElementaryItem> {
+        self.list_elementaries()
+            .into_iter()
+            .filter(|e| {
+                e.name.eq_ignore_ascii_case(name)
+                    && e.compartment.eq_ignore_ascii_case(compartment)
+                    && subcompartment.is_none_or(|s| {
+                        e.subcompartment
+                            .as_ref()
+                            .is_some_and(|e| e.eq_ignore_ascii_case(s))
+                    })
+            })
+            .collect()
+    }
+
+    /// Hierarchy of the locations of the database, if known.
+    fn geographies(&self) -> Option<&Geographies> {
+        None
+    }
+
+    /// Technology matrix, for databases that can be linked with others.
+    fn technology(&self) -> Option<&MappedMatrix<String, String>> {
+        None
+    }
+
+    /// Intervention matrix, for databases that can be linked with others.
+    fn intervention(&self) -> Option<&MappedMatrix<String, String>> {
+        None
+    }
+
+    /// EF v3.1 characterization matrix, for databases that can be linked with others.
+    fn characterization(&self) -> Option<&MappedMatrix<ImpactCategory, String>> {
+        None
+    }
+
+    /// Exchanges of the activities of the database with activities of other databases.
+    fn links(&self) -> &[Link] {
+        &[]
+    }
+
     /// Performs the inventory for the items specified in the reference flow `f`.
     fn lci(&mut self, f: &MappedVector<String>) -> Result<MappedVector<String>>;
 
@@ -19,6 +127,7 @@ pub trait Database {
     fn lcia(&mut self, s: &MappedVector<String>) -> Result<MappedVector<ImpactCategory>>;
 
     fn empty_reference_flow(&self) -> MappedVector<String>;
+    fn empty_inventory(&self) -> MappedVector<String>;
     fn empty_impacts(&self) -> MappedVector<ImpactCategory>;
 
     /// Performs the life cycle assessment of the items specified in the reference flow `f`.
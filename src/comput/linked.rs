@@ -0,0 +1,308 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    comput::{
+        impacts::ImpactCategory,
+        lca::{Database, Matrices},
+    },
+    errors::{OdysseyErrors, Result},
+    utils::{
+        geographies::Geographies,
+        matrix::{MappedMatrix, MappedMatrixBuilder, MappedVector},
+        search::{ElementaryItem, InventoryItem},
+    },
+};
+
+/// Database whose activities are linked to activities of other databases, combined
+/// with them into a single system.
+///
+/// The technology matrix of the system is made of the blocks of each database on its
+/// diagonal, and of their links outside of it, so the supply of the linked databases
+/// is solved with the supply of the root one, loops between databases included.
+/// Activities of the root database keep their ids, the other ones are prefixed with
+/// the name of their database, e.g. `Ecoinvent_3.11:<id>`. Elementary flows are shared
+/// between databases, and characterized by the first database characterizing them.
+pub struct LinkedDatabase {
+    root: Box<dyn Database>,
+    /// Names of the databases of the system, the root first.
+    databases: Vec<String>,
+    technology: MappedMatrix<String, String>,
+    intervention: MappedMatrix<String, String>,
+    characterization: MappedMatrix<ImpactCategory, String>,
+    /// Activities of the other databases, by their prefixed id.
+    activities: HashMap<String, InventoryItem>,
+    /// Elementary flows of the other databases the root one doesn't know.
+    elementaries: HashMap<String, ElementaryItem>,
+}
+
+impl LinkedDatabase {
+    /// Combines the database `name` with the databases its links point to, transitively,
+    /// loaded by name and version with `load`.
+    pub fn new(
+        name: &str,
+        root: Box<dyn Database>,
+        mut load: impl FnMut(&str, &str) -> Result<Box<dyn Database>>,
+    ) -> Result<Self> {
+        let mut members: Vec<(String, Box<dyn Database>)> = vec![];
+        let mut names: HashSet<String> = HashSet::from([name.to_string()]);
+        let mut pending = root.links().to_vec();
+        while let Some(link) = pending.pop() {
+            if names.insert(link.database_name()) {
+                let database = load(&link.database, &link.version)?;
+                pending.extend(database.links().iter().cloned());
+                members.push((link.database_name(), database));
+            }
+        }
+        let key = |database: &str, id: &str| {
+            if database == name {
+                id.to_string()
+            } else {
+                format!("{database}:{id}")
+            }
+        };
+        let databases: Vec<(&str, &dyn Database)> = [(name, root.as_ref())]
+            .into_iter()
+            .chain(members.iter().map(|(n, d)| (n.as_str(), d.as_ref())))
+            .collect();
+        // Rows and columns in the same order, as reference flows are mapped to rows
+        let mut technology = MappedMatrixBuilder::new();
+        for (database, data) in databases.iter() {
+            let a = Matrices::new(*data)?.technology;
+            for i in 0..a.ncols() {
+                let id = key(database, a.icol(&i).unwrap());
+                technology.add_row(id.clone());
+                technology.add_col(id);
+            }
+        }
+        for (database, data) in databases.iter() {
+            let a = Matrices::new(*data)?.technology;
+            for (row, col, value) in a.triplets() {
+                technology.add_triplet(key(database, row), key(database, col), value);
+            }
+            for link in data.links() {
+                let product = key(&link.database_name(), &link.id);
+                if technology.row(&product).is_none() {
+                    return Err(OdysseyErrors::MissingId(format!(
+                        "Missing activity {:?} in {}, linked from {}",
+                        link.id,
+                        link.database_name(),
+                        database
+                    )));
+                }
+                technology.add_triplet(product, key(database, &link.activity), -link.amount);
+            }
+        }
+        let technology = technology.build();
+
+        let mut intervention = MappedMatrixBuilder::new();
+        intervention.copy_cols_into_cols(&technology);
+        for (database, data) in databases.iter() {
+            let b = Matrices::new(*data)?.intervention;
+            for (row, col, value) in b.triplets() {
+                intervention.add_triplet(row.clone(), key(database, col), value);
+            }
+        }
+        let intervention = intervention.build();
+
+        let mut characterization = MappedMatrixBuilder::new();
+        characterization.copy_rows_into_cols(&intervention);
+        characterization.copy_vec_into_rows(&ImpactCategory::get_empty_vector());
+        let mut characterized = HashSet::new();
+        for (_, data) in databases.iter() {
+            let c = Matrices::new(*data)?.characterization;
+            let flows: HashMap<&String, Vec<(&ImpactCategory, f64)>> =
+                c.triplets()
+                    .fold(HashMap::new(), |mut acc, (row, col, value)| {
+                        acc.entry(col).or_default().push((row, value));
+                        acc
+                    });
+            for (flow, factors) in flows {
+                if intervention.contains_row(flow) && characterized.insert(flow.clone()) {
+                    for (category, value) in factors {
+                        characterization.add_triplet(category.clone(), flow.clone(), value);
+                    }
+                }
+            }
+        }
+
+        let mut activities = HashMap::new();
+        let mut elementaries = HashMap::new();
+        for (database, data) in members.iter() {
+            for item in data.list_candidates() {
+                activities.insert(key(database, &item.id), item.clone());
+            }
+            for item in data.list_elementaries() {
+                if root.find_elementary(&item.id).is_none() {
+                    elementaries
+                        .entry(item.id.clone())
+                        .or_insert_with(|| item.clone());
+                }
+            }
+        }
+
+        Ok(LinkedDatabase {
+            databases: databases.iter().map(|(n, _)| n.to_string()).collect(),
+            root,
+            technology,
+            intervention,
+            characterization: characterization.build(),
+            activities,
+            elementaries,
+        })
+    }
+
+    /// Names of the databases of the system, the root first.
+    pub fn databases(&self) -> &[String] {
+        &self.databases
+    }
+}
+
+impl Database for LinkedDatabase {
+    fn name(&self) -> String {
+        self.root.name()
+    }
+
+    fn list_candidates(&self) -> Vec<&InventoryItem> {
+        self.root.list_candidates()
+    }
+
+    fn find_candidate(&self, id: &str) -> Option<&InventoryItem> {
+        self.root
+            .find_candidate(id)
+            .or_else(|| self.activities.get(id))
+    }
+
+    fn list_elementaries(&self) -> Vec<&ElementaryItem> {
+        let mut res = self.root.list_elementaries();
+        res.extend(self.elementaries.values());
+        res
+    }
+
+    fn find_elementary(&self, id: &str) -> Option<&ElementaryItem> {
+        self.root
+            .find_elementary(id)
+            .or_else(|| self.elementaries.get(id))
+    }
+
+    fn geographies(&self) -> Option<&Geographies> {
+        self.root.geographies()
+    }
+
+    fn technology(&self) -> Option<&MappedMatrix<String, String>> {
+        Some(&self.technology)
+    }
+
+    fn intervention(&self) -> Option<&MappedMatrix<String, String>> {
+        Some(&self.intervention)
+    }
+
+    fn characterization(&self) -> Option<&MappedMatrix<ImpactCategory, String>> {
+        Some(&self.characterization)
+    }
+
+    fn empty_reference_flow(&self) -> MappedVector<String> {
+        self.technology.zeros_like_rows()
+    }
+
+    fn empty_inventory(&self) -> MappedVector<String> {
+        self.intervention.zeros_like_rows()
+    }
+
+    fn empty_impacts(&self) -> MappedVector<ImpactCategory> {
+        self.characterization.zeros_like_rows()
+    }
+
+    fn lci(&mut self, f: &MappedVector<String>) -> Result<MappedVector<String>> {
+        let s = self.technology.solve(f);
+        Ok(self.intervention.dot(&s))
+    }
+
+    fn lcia(&mut self, g: &MappedVector<String>) -> Result<MappedVector<ImpactCategory>> {
+        Ok(self.characterization.dot(g))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        comput::{impacts::EF31, lca::Link},
+        parsers::{background::Background, build::Process},
+    };
+
+    fn process(id: &str, intermediates: Vec<(&str, f64)>, co2: f64) -> (String, Process) {
+        let flow = ElementaryItem {
+            id: "co2".to_string(),
+            name: "carbon dioxide".to_string(),
+            compartment: "air".to_string(),
+            subcompartment: None,
+            unit: "kg".to_string(),
+        };
+        let process = Process {
+            id: id.to_string(),
+            name: id.to_string(),
+            product: id.to_string(),
+            location: None,
+            unit: "kg".to_string(),
+            intermediates: intermediates
+                .into_iter()
+                .map(|(id, amount)| (id.to_string(), amount))
+                .collect(),
+            elementaries: vec![(flow, co2)],
+        };
+        (id.to_string(), process)
+    }
+
+    fn background(name: &str, processes: Vec<(String, Process)>) -> Background {
+        Background::build(name, processes.into_iter().collect(), |intervention, _| {
+            let mut mat = MappedMatrixBuilder::new();
+            mat.copy_rows_into_cols(intervention);
+            mat.copy_vec_into_rows(&ImpactCategory::get_empty_vector());
+            mat.add_triplet(ImpactCategory::EF31(EF31::Gwp100), "co2".to_string(), 1.);
+            Ok(mat.build())
+        })
+        .unwrap()
+    }
+
+    fn link(database: &str, id: &str, activity: &str, amount: f64) -> Link {
+        Link {
+            database: database.to_string(),
+            version: "1".to_string(),
+            id: id.to_string(),
+            activity: activity.to_string(),
+            amount,
+        }
+    }
+
+    /// `a` of `Custom_1` emits 1 co2 and consumes 2 `x` of `Supplier_1`, which emits
+    /// 1 co2 and consumes 0.1 `a` back (loop between databases).
+    ///
+    /// s_a = 1 + 0.1 s_x, s_x = 2 s_a  =>  s_a = 1.25, s_x = 2.5
+    #[test]
+    fn test_linked_databases() {
+        let mut custom = background("Custom_1", vec![process("a", vec![("a", 1.)], 1.)]);
+        custom.set_links(vec![link("Supplier", "x", "a", 2.)]);
+        let mut linked = LinkedDatabase::new("Custom_1", Box::new(custom), |name, _| {
+            assert_eq!(name, "Supplier");
+            let mut supplier = background("Supplier_1", vec![process("x", vec![("x", 1.)], 1.)]);
+            supplier.set_links(vec![link("Custom", "a", "x", 0.1)]);
+            Ok(Box::new(supplier))
+        })
+        .unwrap();
+        assert_eq!(linked.databases(), ["Custom_1", "Supplier_1"]);
+
+        let mut f = linked.empty_reference_flow();
+        f.set("a".to_string(), 1.);
+        let g = linked.lci(&f).unwrap();
+        assert!((g.values[*g.row(&"co2".to_string()).unwrap()] - 3.75).abs() < 1e-9);
+        let h = linked.lcia(&g).unwrap();
+        let gwp = ImpactCategory::EF31(EF31::Gwp100);
+        assert!((h.values[*h.row(&gwp).unwrap()] - 3.75).abs() < 1e-9);
+
+        // The whole system can be exported, activities of other databases included
+        let matrices = Matrices::new(&linked).unwrap();
+        assert_eq!(matrices.technology.ncols(), 2);
+        assert_eq!(linked.find_candidate("Supplier_1:x").unwrap().name, "x");
+        assert!(linked.find_elementary("co2").is_some());
+    }
+}
@@ -1,2 +1,6 @@
+pub mod comparison;
+pub mod foreground;
 pub mod impacts;
 pub mod lca;
+pub mod linked;
+pub mod results;
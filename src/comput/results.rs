@@ -0,0 +1,226 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    comput::{
+        foreground::{Foreground, FunctionalUnit},
+        impacts::ImpactCategory,
+        lca::Database,
+    },
+    errors::{OdysseyErrors, Result},
+    utils::matrix::MappedVector,
+};
+
+/// Impact category of [LcaResults].
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Category {
+    pub id: String,
+    pub name: String,
+    pub unit: String,
+    pub method: String,
+    pub description: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum RowKind {
+    /// Impacts of one exchange of the reference activity
+    Exchange,
+    /// Impacts of the whole system
+    Total,
+    /// Direct contribution of a foreground activity
+    Activity,
+    /// Impacts of a life cycle stage
+    Stage,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ResultRow {
+    pub kind: RowKind,
+    pub label: String,
+    /// One value per category, in the order of [LcaResults::categories].
+    pub values: Vec<f64>,
+}
+
+/// Results of the assessment of a project, in a form suited for export.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LcaResults {
+    pub project: String,
+    /// What the results are expressed for, if the project specifies it.
+    /// Otherwise, one unit of the reference activity.
+    pub functional_unit: Option<FunctionalUnit>,
+    /// Background databases linked to the project.
+    pub databases: Vec<String>,
+    pub categories: Vec<Category>,
+    pub rows: Vec<ResultRow>,
+    /// Remarks recorded while building the foreground.
+    pub notes: Vec<String>,
+}
+
+impl LcaResults {
+    pub fn new(project: String) -> Self {
+        let empty = ImpactCategory::get_empty_vector();
+        let categories = (0..empty.values.len())
+            .filter_map(|i| empty.mapping.get_by_right(&i))
+            .map(|c| {
+                let metadata = c.metadata();
+                Category {
+                    id: c.id(),
+                    name: metadata.name.to_string(),
+                    unit: metadata.unit.to_string(),
+                    method: metadata.method.to_string(),
+                    description: metadata.description.to_string(),
+                }
+            })
+            .collect();
+        Self {
+            project,
+            functional_unit: None,
+            databases: vec![],
+            categories,
+            rows: vec![],
+            notes: vec![],
+        }
+    }
+
+    /// Adds a row, whose values are taken in the order of the categories.
+    pub fn add_row(&mut self, kind: RowKind, label: String, res: &MappedVector<ImpactCategory>) {
+        let empty = ImpactCategory::get_empty_vector();
+        let values = (0..empty.values.len())
+            .map(|i| {
+                empty
+                    .mapping
+                    .get_by_right(&i)
+                    .and_then(|c| res.mapping.get_by_left(c))
+                    .map(|j| res.values[*j])
+                    .unwrap_or(0.)
+            })
+            .collect();
+        self.rows.push(ResultRow {
+            kind,
+            label,
+            values,
+        });
+    }
+
+    /// Assesses the `foreground`: one row per exchange of the reference activity,
+    /// the total, one row per life cycle stage if any and, if `contributions`,
+    /// the direct contribution of each activity.
+    pub fn compute(
+        project: String,
+        foreground: &mut Foreground,
+        databases: &mut HashMap<String, Box<dyn Database>>,
+        contributions: bool,
+    ) -> Result<Self> {
+        let mut res = Self::new(project);
+        res.notes = foreground.notes().to_vec();
+        res.databases = foreground.databases();
+        res.functional_unit = foreground.functional_unit().cloned();
+
+        let root = foreground.root().to_string();
+        let names: Vec<Option<String>> = foreground
+            .exchanges(&root)
+            .iter()
+            .map(|e| e.name.clone())
+            .collect();
+        for (index, name) in names.into_iter().enumerate() {
+            let impacts = foreground.lca_exchange(&root, index, databases)?;
+            res.add_row(
+                RowKind::Exchange,
+                name.unwrap_or("None".to_string()),
+                &impacts,
+            );
+        }
+
+        let f = foreground.reference_flow();
+        let impacts = foreground.lca(&f, databases)?;
+        res.add_row(RowKind::Total, "all".to_string(), &impacts);
+
+        if foreground.has_stages() {
+            for (stage, impacts) in foreground.stages(&f, databases)? {
+                res.add_row(RowKind::Stage, stage, &impacts);
+            }
+        }
+
+        if contributions {
+            for (activity, impacts) in foreground.contributions(&f, databases)? {
+                res.add_row(RowKind::Activity, activity, &impacts);
+            }
+        }
+        Ok(res)
+    }
+
+    /// Expresses the results per `amount` of the functional unit, instead of
+    /// its declared amount (1 if not specified). Both must be positive.
+    pub fn per(&mut self, amount: f64) -> Result<()> {
+        let declared = self
+            .functional_unit
+            .as_ref()
+            .map(|u| u.amount)
+            .unwrap_or(1.);
+        if amount <= 0. || amount.is_nan() {
+            return Err(OdysseyErrors::InvalidArgument(format!(
+                "results can't be expressed per {amount} functional unit"
+            )));
+        }
+        if declared == 0. {
+            return Err(OdysseyErrors::InvalidArgument(
+                "results can't be scaled, the functional unit has an amount of 0".to_string(),
+            ));
+        }
+        let factor = amount / declared;
+        for row in self.rows.iter_mut() {
+            row.values.iter_mut().for_each(|v| *v *= factor);
+        }
+        if let Some(functional_unit) = self.functional_unit.as_mut() {
+            functional_unit.amount = amount;
+        }
+        Ok(())
+    }
+
+    /// Values of the total row, if computed.
+    pub fn total(&self) -> Option<&[f64]> {
+        self.rows
+            .iter()
+            .find(|r| r.kind == RowKind::Total)
+            .map(|r| r.values.as_slice())
+    }
+
+    /// Methods of the categories, in order of first appearance.
+    pub fn methods(&self) -> Vec<&str> {
+        let mut res: Vec<&str> = vec![];
+        for category in self.categories.iter() {
+            if !res.contains(&category.method.as_str()) {
+                res.push(&category.method);
+            }
+        }
+        res
+    }
+
+    /// The `n` activities contributing the most to the `category`,
+    /// with their share of the total.
+    pub fn top_contributors(&self, category: usize, n: usize) -> Vec<(&ResultRow, f64)> {
+        let total = self.total().map(|t| t[category]).unwrap_or(0.);
+        let mut res: Vec<(&ResultRow, f64)> = self
+            .rows
+            .iter()
+            .filter(|r| r.kind == RowKind::Activity)
+            .map(|r| {
+                let share = if total == 0. {
+                    0.
+                } else {
+                    r.values[category] / total
+                };
+                (r, share)
+            })
+            .collect();
+        res.sort_by(|a, b| {
+            b.0.values[category]
+                .abs()
+                .total_cmp(&a.0.values[category].abs())
+        });
+        res.truncate(n);
+        res
+    }
+}
@@ -1,4 +1,4 @@
-use std::io;
+use std::{fmt, io, path::PathBuf};
 use thiserror::Error;
 pub type Result<T> = std::result::Result<T, OdysseyErrors>;
 
@@ -25,9 +25,73 @@ pub enum OdysseyErrors {
     #[error("{0:?}")]
     TantivyError(#[from] tantivy::TantivyError),
     #[error("{0:?}")]
+    ZipError(#[from] zip::result::ZipError),
+    #[error("{0:?}")]
     MissingId(String),
     #[error("{0:?}")]
     MissingDatabase(String),
     #[error("{0:?}")]
     NoCache(String),
+    #[error(
+        "{0} was imported with a previous version, import it again with `odyssey database import`"
+    )]
+    OutdatedCache(String),
+    #[error("invalid database: {0}")]
+    InvalidDatabase(String),
+    #[error("the search index was built by a previous version, import the databases again with `odyssey database import`")]
+    OutdatedSearchIndex,
+    #[error("invalid argument: {0}")]
+    InvalidArgument(String),
+    #[error("{0}: the foreground system can't be solved, check that each activity has a non zero net production and that loops between activities don't consume all they produce")]
+    SingularForeground(String),
+    #[error(
+        "{} invalid datasets, fix or skip them:\n{}",
+        .0.len(),
+        .0.iter().map(|e| e.to_string()).collect::<Vec<_>>().join("\n")
+    )]
+    InvalidDatasets(Vec<DatasetError>),
+    #[error("invalid expression {expression:?}: {reason}")]
+    InvalidExpression { expression: String, reason: String },
+    #[error("{file}: no match in {database} for {exchange}")]
+    UnresolvedExchange {
+        file: String,
+        database: String,
+        exchange: String,
+    },
+    #[error(
+        "{file}: multiple matches in {database} for {exchange}, add a location, a unit or an id:\n{}",
+        .candidates.join("\n")
+    )]
+    AmbiguousExchange {
+        file: String,
+        database: String,
+        exchange: String,
+        candidates: Vec<String>,
+    },
+    #[error("{file}: invalid exchange {exchange}: {reason}")]
+    InvalidExchange {
+        file: String,
+        exchange: String,
+        reason: String,
+    },
+    #[error("{file}: {exchange} can't be converted from {unit} to {expected}")]
+    IncompatibleUnits {
+        file: String,
+        exchange: String,
+        unit: String,
+        expected: String,
+    },
+}
+
+/// Dataset of a database which could not be read.
+#[derive(Debug, Clone)]
+pub struct DatasetError {
+    pub path: PathBuf,
+    pub reason: String,
+}
+
+impl fmt::Display for DatasetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.path.display(), self.reason)
+    }
 }
@@ -0,0 +1,232 @@
+use std::{
+    collections::{HashMap, HashSet},
+    path::Path,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    comput::{
+        impacts::ImpactCategory,
+        lca::{Database, Link},
+    },
+    errors::Result,
+    parsers::build::{build_candidates, build_elementaries, build_matrices, Process},
+    utils::{
+        cache::{read_cache, write_cache},
+        matrix::{MappedMatrix, MappedVector},
+        search::{ElementaryItem, InventoryItem},
+    },
+};
+
+/// Background database built from [Process]es, for formats without data of their own
+/// beyond the matrices.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Background {
+    /// Name under which the database is cached and indexed.
+    name: String,
+    technology: MappedMatrix<String, String>,
+    intervention: MappedMatrix<String, String>,
+    classifications: HashMap<String, MappedMatrix<ImpactCategory, String>>,
+    candidates: HashMap<String, InventoryItem>,
+    elementaries: HashMap<String, ElementaryItem>,
+    links: Vec<Link>,
+    /// Processes left out when building the database, with the reason.
+    #[serde(skip)]
+    left_out: Vec<String>,
+}
+
+impl Background {
+    /// Builds the database `name` from its `processes`, characterizing the elementary
+    /// flows of the intervention matrix with the EF v3.1 matrix built by `characterize`.
+    ///
+    /// Processes without a net production of their reference product would make the
+    /// technology matrix singular: they are left out, with the exchanges linked to them,
+    /// and listed by [Background::left_out].
+    pub fn build(
+        name: &str,
+        mut processes: HashMap<String, Process>,
+        characterize: impl FnOnce(
+            &MappedMatrix<String, String>,
+            &HashMap<String, ElementaryItem>,
+        ) -> Result<MappedMatrix<ImpactCategory, String>>,
+    ) -> Result<Self> {
+        let unproductive: HashSet<String> = processes
+            .values()
+            .filter(|p| production(p) == 0.)
+            .map(|p| p.id.clone())
+            .collect();
+        let mut left_out: Vec<String> = unproductive
+            .iter()
+            .filter_map(|id| processes.remove(id))
+            .map(|p| {
+                format!(
+                    "{} ({}): no net production of its reference product",
+                    p.name, p.id
+                )
+            })
+            .collect();
+        left_out.sort();
+        for process in processes.values_mut() {
+            process
+                .intermediates
+                .retain(|(provider, _)| !unproductive.contains(provider));
+        }
+
+        let candidates = build_candidates(&processes, name);
+        let mut elementaries = build_elementaries(&processes);
+        let (technology, intervention) = build_matrices(processes)?;
+        elementaries.retain(|id, _| intervention.contains_row(id));
+        let ef31 = characterize(&intervention, &elementaries)?;
+        let mut classifications = HashMap::new();
+        classifications.insert("ef31".to_string(), ef31);
+        Ok(Background {
+            name: name.to_string(),
+            technology,
+            intervention,
+            classifications,
+            candidates,
+            elementaries,
+            links: vec![],
+            left_out,
+        })
+    }
+
+    /// Processes left out when the database was built, with the reason. Empty for a
+    /// database loaded from its cache.
+    pub fn left_out(&self) -> &[String] {
+        &self.left_out
+    }
+
+    /// Links the activities of the database to activities of other databases.
+    pub fn set_links(&mut self, links: Vec<Link>) {
+        self.links = links;
+    }
+
+    /// Save the database data in a cache at the specified `path`.
+    pub fn cache(&self, cache: &Path) -> Result<()> {
+        write_cache(cache, self)
+    }
+
+    pub fn load_from_cache(path: &Path) -> Result<Self> {
+        read_cache(path)
+    }
+}
+
+/// Net amount of its reference product the `process` produces.
+fn production(process: &Process) -> f64 {
+    process
+        .intermediates
+        .iter()
+        .filter(|(provider, _)| *provider == process.id)
+        .map(|(_, amount)| amount)
+        .sum()
+}
+
+impl Database for Background {
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn empty_reference_flow(&self) -> MappedVector<String> {
+        self.technology.zeros_like_cols()
+    }
+
+    fn empty_inventory(&self) -> MappedVector<String> {
+        self.intervention.zeros_like_rows()
+    }
+
+    fn empty_impacts(&self) -> MappedVector<ImpactCategory> {
+        self.classifications.get("ef31").unwrap().zeros_like_rows()
+    }
+
+    fn list_candidates(&self) -> Vec<&InventoryItem> {
+        self.candidates.values().collect()
+    }
+
+    fn find_candidate(&self, id: &str) -> Option<&InventoryItem> {
+        self.candidates.get(id)
+    }
+
+    fn list_elementaries(&self) -> Vec<&ElementaryItem> {
+        self.elementaries.values().collect()
+    }
+
+    fn find_elementary(&self, id: &str) -> Option<&ElementaryItem> {
+        self.elementaries.get(id)
+    }
+
+    fn technology(&self) -> Option<&MappedMatrix<String, String>> {
+        Some(&self.technology)
+    }
+
+    fn intervention(&self) -> Option<&MappedMatrix<String, String>> {
+        Some(&self.intervention)
+    }
+
+    fn characterization(&self) -> Option<&MappedMatrix<ImpactCategory, String>> {
+        self.classifications.get("ef31")
+    }
+
+    fn links(&self) -> &[Link] {
+        &self.links
+    }
+
+    fn lci(&mut self, f: &MappedVector<String>) -> Result<MappedVector<String>> {
+        let s = self.technology.solve(f);
+        let g = self.intervention.dot(&s);
+        Ok(g)
+    }
+
+    fn lcia(&mut self, g: &MappedVector<String>) -> Result<MappedVector<ImpactCategory>> {
+        let ef = self.classifications.get_mut("ef31").unwrap();
+        let h = ef.dot(g);
+        Ok(h)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::matrix::MappedMatrixBuilder;
+
+    /// `steel` consumes `coal`, whose dataset has no reference product.
+    #[test]
+    fn test_left_out() {
+        let process = |id: &str, intermediates: Vec<(String, f64)>| Process {
+            id: id.to_string(),
+            name: format!("{id} production"),
+            product: id.to_string(),
+            location: None,
+            unit: "kg".to_string(),
+            intermediates,
+            elementaries: vec![],
+        };
+        let processes = HashMap::from([
+            (
+                "steel".to_string(),
+                process(
+                    "steel",
+                    vec![("steel".to_string(), 1.), ("coal".to_string(), -2.)],
+                ),
+            ),
+            ("coal".to_string(), process("coal", vec![])),
+        ]);
+        let mut database = Background::build("Custom_test", processes, |intervention, _| {
+            let mut mat = MappedMatrixBuilder::new();
+            mat.copy_rows_into_cols(intervention);
+            mat.copy_vec_into_rows(&ImpactCategory::get_empty_vector());
+            Ok(mat.build())
+        })
+        .unwrap();
+        assert_eq!(
+            database.left_out(),
+            ["coal production (coal): no net production of its reference product"]
+        );
+        assert!(database.find_candidate("coal").is_none());
+
+        let mut f = database.empty_reference_flow();
+        f.set("steel".to_string(), 1.);
+        assert!(database.lca(&f).is_ok());
+    }
+}
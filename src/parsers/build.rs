@@ -0,0 +1,107 @@
+use std::collections::HashMap;
+
+use crate::{
+    errors::{OdysseyErrors, Result},
+    utils::{
+        matrix::{MappedMatrix, MappedMatrixBuilder},
+        search::{ActivityMetadata, ElementaryItem, InventoryItem},
+    },
+};
+
+/// Process of a background database, independent of the format it was read from.
+#[derive(Debug)]
+pub struct Process {
+    pub id: String,
+    pub name: String,
+    /// Name of the reference product.
+    pub product: String,
+    pub location: Option<String>,
+    /// Unit of the reference product.
+    pub unit: String,
+    /// Products exchanged with other processes, by id of the process, inputs being negative.
+    /// The reference product is exchanged with the process itself.
+    pub intermediates: Vec<(String, f64)>,
+    /// Elementary flows exchanged with the environment.
+    pub elementaries: Vec<(ElementaryItem, f64)>,
+}
+
+/// Technology and intervention matrices of the `processes`.
+pub fn build_matrices(
+    mut processes: HashMap<String, Process>,
+) -> Result<(MappedMatrix<String, String>, MappedMatrix<String, String>)> {
+    let mut a = MappedMatrixBuilder::new();
+    let mut b = MappedMatrixBuilder::new();
+    let mut processes_fifo = vec![];
+    while !processes.is_empty() {
+        if processes_fifo.is_empty() {
+            let p = processes.iter().next().unwrap();
+            processes_fifo.push(p.0.clone());
+        }
+
+        let col_id = processes_fifo.remove(0);
+        if a.col(&col_id).is_some() {
+            continue;
+        }
+
+        a.add_col(col_id.clone());
+        a.add_row(col_id.clone());
+        b.add_col(col_id.clone());
+
+        let process = processes
+            .remove(&col_id)
+            .ok_or(OdysseyErrors::MissingId(format!(
+                "Missing process {col_id:?} while building the matrices"
+            )))?;
+
+        for (row_id, amount) in process.intermediates {
+            if amount != 0. && processes.contains_key(&row_id) {
+                processes_fifo.push(row_id.clone());
+            }
+            a.add_triplet(row_id, col_id.clone(), amount);
+        }
+
+        for (elementary, amount) in process.elementaries {
+            if amount == 0. {
+                continue;
+            }
+            b.add_triplet(elementary.id, col_id.clone(), amount);
+        }
+    }
+
+    Ok((a.build(), b.build()))
+}
+
+/// Reference products of the `processes`, indexed by id, as items of the `database`.
+pub fn build_candidates(
+    processes: &HashMap<String, Process>,
+    database: &str,
+) -> HashMap<String, InventoryItem> {
+    processes
+        .iter()
+        .map(|(id, process)| {
+            let item = InventoryItem {
+                id: id.clone(),
+                database: database.to_string(),
+                name: process.name.clone(),
+                alt_name: Some(process.product.clone()),
+                location: process.location.clone(),
+                unit: process.unit.clone(),
+                metadata: ActivityMetadata::default(),
+            };
+            (id.clone(), item)
+        })
+        .collect()
+}
+
+/// Elementary flows exchanged by the `processes`.
+pub fn build_elementaries(processes: &HashMap<String, Process>) -> HashMap<String, ElementaryItem> {
+    let mut res = HashMap::new();
+    for process in processes.values() {
+        for (elementary, _) in process.elementaries.iter() {
+            if !res.contains_key(&elementary.id) {
+                res.insert(elementary.id.clone(), elementary.clone());
+            }
+        }
+    }
+    res
+}
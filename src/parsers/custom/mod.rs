@@ -0,0 +1,315 @@
+use std::{
+    collections::{hash_map::Entry, HashMap, HashSet},
+    path::Path,
+};
+
+use crate::{
+    comput::{
+        impacts::ImpactCategory,
+        lca::{Database, Link},
+    },
+    errors::{OdysseyErrors, Result},
+    parsers::{
+        background::Background,
+        build::Process,
+        custom::parse::{parse_custom, Activity, Exchange, ExchangeKind},
+        load_database,
+    },
+    utils::{
+        cache::is_current,
+        matrix::{MappedMatrix, MappedMatrixBuilder, MappedVector},
+    },
+};
+
+mod parse;
+
+/// Processes of a custom database, with the impacts of one unit of the elementary
+/// flows of other databases they emit, by id, and their links to other databases.
+struct Tables {
+    processes: HashMap<String, Process>,
+    impacts: HashMap<String, MappedVector<ImpactCategory>>,
+    links: Vec<Link>,
+}
+
+/// Loads the custom database `name` from the `activities.csv` and `exchanges.csv`
+/// files of the folder at `path`, from the `cache` if it exists, and caches it
+/// otherwise.
+///
+/// Exchanges with activities and elementary flows of other databases load them from
+/// their cache, so they must be imported first. Activities of other databases are
+/// linked, and solved with the custom database when it is loaded, while elementary
+/// flows of other databases are characterized with their factors at import.
+pub fn load_custom(name: &str, path: &Path, cache: Option<&Path>) -> Result<Background> {
+    if let Some(cache) = cache {
+        if is_current(cache)? {
+            return Background::load_from_cache(cache);
+        }
+    }
+    let (activities, exchanges) = parse_custom(path)?;
+    let mut databases = HashMap::new();
+    for exchange in exchanges.iter() {
+        if let (Some(database), Some(version)) = (&exchange.database, &exchange.version) {
+            let key = format!("{}_{}", database, version);
+            if let Entry::Vacant(entry) = databases.entry(key) {
+                entry.insert(load_database(database, version)?);
+            }
+        }
+    }
+    let tables = build_processes(&activities, &exchanges, &mut databases)?;
+    let mut res = Background::build(name, tables.processes, |intervention, _| {
+        Ok(construct_impact_matrix(intervention, &tables.impacts))
+    })?;
+    res.set_links(tables.links);
+    if let Some(cache) = cache {
+        res.cache(cache)?;
+    }
+    Ok(res)
+}
+
+/// Converts the activities into processes, linked to the activities of other
+/// `databases` they consume.
+fn build_processes(
+    activities: &[Activity],
+    exchanges: &[Exchange],
+    databases: &mut HashMap<String, Box<dyn Database>>,
+) -> Result<Tables> {
+    let mut res: HashMap<String, Process> = HashMap::new();
+    for (line, activity) in activities.iter().enumerate() {
+        let process = Process {
+            id: activity.id.clone(),
+            name: activity.name.clone(),
+            product: activity.product.clone().unwrap_or(activity.name.clone()),
+            location: activity.location.clone(),
+            unit: activity.unit.clone(),
+            intermediates: vec![(activity.id.clone(), 1.)],
+            elementaries: vec![],
+        };
+        if res.insert(activity.id.clone(), process).is_some() {
+            // Line of the activity in the file, after the header
+            return Err(OdysseyErrors::InvalidDatabase(format!(
+                "activities.csv line {}: repeated activity {:?}",
+                line + 2,
+                activity.id
+            )));
+        }
+    }
+    let ids: HashSet<String> = res.keys().cloned().collect();
+
+    let mut impacts = HashMap::new();
+    let mut links = vec![];
+    for (line, exchange) in exchanges.iter().enumerate() {
+        // Line of the exchange in the file, after the header
+        let invalid = |reason: &str| {
+            OdysseyErrors::InvalidDatabase(format!("exchanges.csv line {}: {}", line + 2, reason))
+        };
+        if !ids.contains(&exchange.activity) {
+            return Err(invalid(&format!("no activity {:?}", exchange.activity)));
+        }
+        let Some(database) = &exchange.database else {
+            let id = match (exchange.kind, &exchange.id) {
+                (ExchangeKind::Technosphere, Some(id)) if ids.contains(id) => id,
+                (ExchangeKind::Technosphere, _) => {
+                    return Err(invalid("no activity of the database with this id"))
+                }
+                (ExchangeKind::Biosphere, _) => {
+                    return Err(invalid("elementary flows must belong to another database"))
+                }
+            };
+            let process = res.get_mut(&exchange.activity).unwrap();
+            process.intermediates.push((id.clone(), -exchange.amount));
+            continue;
+        };
+        let Some(version) = &exchange.version else {
+            return Err(invalid("the version of the database is required"));
+        };
+        let name = format!("{}_{}", database, version);
+        let data = databases
+            .get_mut(&name)
+            .ok_or(OdysseyErrors::MissingDatabase(name.clone()))?;
+        if exchange.kind == ExchangeKind::Technosphere {
+            let id = exchange.id.as_deref().unwrap_or_default();
+            if data.find_candidate(id).is_none() {
+                return Err(invalid(&format!("no activity {id:?} in {name}")));
+            }
+            links.push(Link {
+                database: database.clone(),
+                version: version.clone(),
+                id: id.to_string(),
+                activity: exchange.activity.clone(),
+                amount: exchange.amount,
+            });
+            continue;
+        }
+        let item = match (&exchange.id, &exchange.name) {
+            (Some(id), _) => data.find_elementary(id),
+            (None, Some(flow)) => {
+                let compartment = exchange.compartment.as_deref().unwrap_or_default();
+                let found =
+                    data.search_elementaries(flow, compartment, exchange.subcompartment.as_deref());
+                match found.as_slice() {
+                    [item] => Some(*item),
+                    [] => None,
+                    _ => return Err(invalid("several elementary flows match, add an id")),
+                }
+            }
+            (None, None) => return Err(invalid("an id or a name is required")),
+        };
+        let item = item
+            .cloned()
+            .ok_or_else(|| invalid(&format!("no elementary flow found in {name}")))?;
+        let mut g = data.empty_inventory();
+        g.set(item.id.clone(), 1.);
+        let unit_impacts = data.lcia(&g)?;
+        impacts.entry(item.id.clone()).or_insert(unit_impacts);
+        let process = res.get_mut(&exchange.activity).unwrap();
+        process.elementaries.push((item, exchange.amount));
+    }
+    Ok(Tables {
+        processes: res,
+        impacts,
+        links,
+    })
+}
+
+/// Characterization matrix of the flows of the `intervention` matrix, from the
+/// `impacts` of one unit of each of them.
+fn construct_impact_matrix(
+    intervention: &MappedMatrix<String, String>,
+    impacts: &HashMap<String, MappedVector<ImpactCategory>>,
+) -> MappedMatrix<ImpactCategory, String> {
+    let mut mat = MappedMatrixBuilder::new();
+    mat.copy_rows_into_cols(intervention);
+    mat.copy_vec_into_rows(&ImpactCategory::get_empty_vector());
+    for (id, impacts) in impacts.iter() {
+        for (category, index) in impacts.mapping.iter() {
+            let value = impacts.values[*index];
+            if value != 0. {
+                mat.add_triplet(category.clone(), id.clone(), value);
+            }
+        }
+    }
+    mat.build()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{comput::impacts::EF31, utils::search::ElementaryItem};
+
+    #[test]
+    fn test_build_processes() {
+        let activities = "id,name,product,location,unit
+recycling,plastic recycling,recycled plastic,FR,kg
+energy,factory energy,,FR,kWh
+";
+        let exchanges = "activity,type,database,version,id,name,compartment,subcompartment,amount
+recycling,technosphere,,,energy,,,,0.5
+recycling,technosphere,,,recycling,,,,-0.1
+";
+        let activities: Vec<Activity> = csv::Reader::from_reader(activities.as_bytes())
+            .deserialize()
+            .collect::<std::result::Result<_, _>>()
+            .unwrap();
+        let exchanges: Vec<Exchange> = csv::Reader::from_reader(exchanges.as_bytes())
+            .deserialize()
+            .collect::<std::result::Result<_, _>>()
+            .unwrap();
+        let tables = build_processes(&activities, &exchanges, &mut HashMap::new()).unwrap();
+        assert!(tables.impacts.is_empty() && tables.links.is_empty());
+        assert_eq!(tables.processes["energy"].product, "factory energy");
+        assert_eq!(
+            tables.processes["recycling"].intermediates,
+            vec![
+                ("recycling".to_string(), 1.),
+                ("energy".to_string(), -0.5),
+                ("recycling".to_string(), 0.1)
+            ]
+        );
+
+        // Elementary flows must come from another database
+        let exchanges = vec![Exchange {
+            activity: "energy".to_string(),
+            kind: ExchangeKind::Biosphere,
+            database: None,
+            version: None,
+            id: Some("co2".to_string()),
+            name: None,
+            compartment: None,
+            subcompartment: None,
+            amount: 1.,
+        }];
+        assert!(build_processes(&activities, &exchanges, &mut HashMap::new()).is_err());
+
+        // Activities can't be repeated
+        let repeated: Vec<Activity> = csv::Reader::from_reader(
+            "id,name,product,location,unit\nenergy,factory energy,,FR,kWh\nenergy,other energy,,DE,kWh\n"
+                .as_bytes(),
+        )
+        .deserialize()
+        .collect::<std::result::Result<_, _>>()
+        .unwrap();
+        let Err(error) = build_processes(&repeated, &[], &mut HashMap::new()) else {
+            panic!("repeated activities must fail the import");
+        };
+        assert!(error.to_string().contains("activities.csv line 3"));
+
+        // Emissions are positive
+        let co2 = ElementaryItem {
+            id: "co2".to_string(),
+            name: "Carbon dioxide".to_string(),
+            compartment: "air".to_string(),
+            subcompartment: None,
+            unit: "kg".to_string(),
+        };
+        let supplier = Process {
+            id: "x".to_string(),
+            name: "x".to_string(),
+            product: "x".to_string(),
+            location: None,
+            unit: "kg".to_string(),
+            intermediates: vec![("x".to_string(), 1.)],
+            elementaries: vec![(co2, 1.)],
+        };
+        let supplier = Background::build(
+            "Supplier_1",
+            HashMap::from([("x".to_string(), supplier)]),
+            |intervention, _| {
+                let mut mat = MappedMatrixBuilder::new();
+                mat.copy_rows_into_cols(intervention);
+                mat.copy_vec_into_rows(&ImpactCategory::get_empty_vector());
+                mat.add_triplet(ImpactCategory::EF31(EF31::Gwp100), "co2".to_string(), 1.);
+                Ok(mat.build())
+            },
+        )
+        .unwrap();
+        let mut databases: HashMap<String, Box<dyn Database>> = HashMap::from([(
+            "Supplier_1".to_string(),
+            Box::new(supplier) as Box<dyn Database>,
+        )]);
+        let exchanges = vec![Exchange {
+            activity: "energy".to_string(),
+            kind: ExchangeKind::Biosphere,
+            database: Some("Supplier".to_string()),
+            version: Some("1".to_string()),
+            id: Some("co2".to_string()),
+            name: None,
+            compartment: None,
+            subcompartment: None,
+            amount: 0.2,
+        }];
+        let mut tables = build_processes(&activities, &exchanges, &mut databases).unwrap();
+        let energy = tables.processes.remove("energy").unwrap();
+        assert_eq!(energy.elementaries[0].1, 0.2);
+        let mut background = Background::build(
+            "Custom_test",
+            HashMap::from([("energy".to_string(), energy)]),
+            |intervention, _| Ok(construct_impact_matrix(intervention, &tables.impacts)),
+        )
+        .unwrap();
+        let mut f = background.empty_reference_flow();
+        f.set("energy".to_string(), 1.);
+        let h = background.lca(&f).unwrap();
+        let gwp = h.values[*h.row(&ImpactCategory::EF31(EF31::Gwp100)).unwrap()];
+        assert!((gwp - 0.2).abs() < 1e-12);
+    }
+}
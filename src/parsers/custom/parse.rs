@@ -0,0 +1,70 @@
+use std::path::Path;
+
+use serde::Deserialize;
+
+use crate::errors::Result;
+
+/// Row of `activities.csv`. Each activity produces one unit of its product.
+#[derive(Debug, Deserialize)]
+pub struct Activity {
+    pub id: String,
+
+    pub name: String,
+
+    /// Name of the reference product, the name of the activity if not specified.
+    pub product: Option<String>,
+
+    pub location: Option<String>,
+
+    pub unit: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExchangeKind {
+    /// Product of an activity, of the custom database or of another imported one.
+    Technosphere,
+    /// Elementary flow of another imported database.
+    Biosphere,
+}
+
+/// Row of `exchanges.csv`.
+#[derive(Debug, Deserialize)]
+pub struct Exchange {
+    /// Id of the activity the exchange belongs to.
+    pub activity: String,
+
+    #[serde(rename = "type")]
+    pub kind: ExchangeKind,
+
+    /// Imported database of the target, the custom database itself if not specified.
+    pub database: Option<String>,
+
+    pub version: Option<String>,
+
+    /// Id of the target activity or elementary flow.
+    pub id: Option<String>,
+
+    /// Name, compartment and subcompartment of the elementary flow, if no id is specified.
+    pub name: Option<String>,
+
+    pub compartment: Option<String>,
+
+    pub subcompartment: Option<String>,
+
+    /// Amount exchanged to produce one unit of the activity. Technosphere outputs are
+    /// negative, while biosphere amounts are positive for both emissions and extractions.
+    pub amount: f64,
+}
+
+/// Activities and exchanges of the `activities.csv` and `exchanges.csv` files of the
+/// folder at `path`.
+pub fn parse_custom(path: &Path) -> Result<(Vec<Activity>, Vec<Exchange>)> {
+    let activities = csv::Reader::from_path(path.join("activities.csv"))?
+        .deserialize()
+        .collect::<std::result::Result<_, _>>()?;
+    let exchanges = csv::Reader::from_path(path.join("exchanges.csv"))?
+        .deserialize()
+        .collect::<std::result::Result<_, _>>()?;
+    Ok((activities, exchanges))
+}
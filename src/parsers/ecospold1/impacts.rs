@@ -0,0 +1,55 @@
+use std::{collections::HashMap, fs::File};
+
+use crate::{
+    comput::impacts::ImpactCategory,
+    errors::Result,
+    parsers::{
+        ecospold2::impacts::{
+            ef31::EcoinventEF31Impacts,
+            mapping::{flow_key, latest_version, mapping_path, FlowKey},
+        },
+        impacts::ef31::EF31Impacts,
+    },
+    utils::{
+        matrix::{MappedMatrix, MappedMatrixBuilder},
+        search::ElementaryItem,
+    },
+};
+
+/// Characterization matrix of the EF v3.1 method for the `elementaries` of the
+/// `intervention` matrix. EcoSpold1 flows have no ecoinvent 3 id, so they are matched
+/// by name, compartment and subcompartment, falling back to the unspecified
+/// subcompartment. Flows without match are not characterized.
+pub fn construct_impact_matrix(
+    intervention: &MappedMatrix<String, String>,
+    elementaries: &HashMap<String, ElementaryItem>,
+) -> Result<MappedMatrix<ImpactCategory, String>> {
+    let mut rdr = csv::Reader::from_reader(File::open(mapping_path(&latest_version()?))?);
+    let mut factors: HashMap<FlowKey, EF31Impacts> = HashMap::new();
+    for result in rdr.deserialize() {
+        let record: EcoinventEF31Impacts = result?;
+        let (Some(name), Some(compartment)) = (&record.name, &record.compartment) else {
+            continue;
+        };
+        let key = flow_key(
+            name,
+            compartment,
+            record.subcompartment.as_deref().unwrap_or("unspecified"),
+        );
+        factors.insert(key, EF31Impacts::from(record));
+    }
+
+    let mut mat = MappedMatrixBuilder::new();
+    mat.copy_rows_into_cols(intervention);
+    mat.copy_vec_into_rows(&ImpactCategory::get_empty_vector());
+    for (id, flow) in elementaries.iter() {
+        let subcompartment = flow.subcompartment.as_deref().unwrap_or("unspecified");
+        let impacts = factors
+            .get(&flow_key(&flow.name, &flow.compartment, subcompartment))
+            .or_else(|| factors.get(&flow_key(&flow.name, &flow.compartment, "unspecified")));
+        if let Some(impacts) = impacts {
+            impacts.add_triplets(&mut mat, id.clone());
+        }
+    }
+    Ok(mat.build())
+}
@@ -0,0 +1,199 @@
+use std::{collections::HashMap, path::Path};
+
+use uuid::Uuid;
+
+use crate::{
+    errors::{OdysseyErrors, Result},
+    parsers::{
+        background::Background,
+        build::Process,
+        ecospold1::{impacts::construct_impact_matrix, parse::parse_ecospold1},
+    },
+    utils::{cache::is_current, search::ElementaryItem},
+};
+
+mod impacts;
+mod parse;
+
+/// Input group of exchanges from the environment.
+const FROM_ENVIRONMENT: u8 = 4;
+/// Output group of the reference product.
+const REFERENCE_PRODUCT: u8 = 0;
+/// Output group of exchanges to the environment.
+const TO_ENVIRONMENT: u8 = 4;
+
+/// Loads the EcoSpold1 datasets of the folder at `path` as the database `name`,
+/// from the `cache` if it exists, and caches it otherwise.
+pub fn load_ecospold1(name: &str, path: &Path, cache: Option<&Path>) -> Result<Background> {
+    if let Some(cache) = cache {
+        if is_current(cache)? {
+            return Background::load_from_cache(cache);
+        }
+    }
+    let processes = build_processes(name, parse_ecospold1(path)?)?;
+    let res = Background::build(name, processes, construct_impact_matrix)?;
+    if let Some(cache) = cache {
+        res.cache(cache)?;
+    }
+    Ok(res)
+}
+
+/// Converts the datasets into processes of the database `name`.
+///
+/// EcoSpold1 has no ids linking the datasets: products are identified by their
+/// name, location and unit, and flows by their name, compartment and unit.
+/// Inputs of products no dataset of the database provides are left out, as well as
+/// byproducts, the datasets being expected to be allocated already. Datasets of the
+/// same product can't be told apart, and fail the loading.
+fn build_processes(name: &str, datasets: Vec<parse::Dataset>) -> Result<HashMap<String, Process>> {
+    let namespace = Uuid::new_v5(&Uuid::NAMESPACE_OID, name.as_bytes());
+    let product_id = |name: &str, location: &str, unit: &str| {
+        Uuid::new_v5(&namespace, format!("{name}|{location}|{unit}").as_bytes()).to_string()
+    };
+    let mut products: HashMap<String, &parse::Dataset> = HashMap::new();
+    let mut duplicates = vec![];
+    for dataset in datasets.iter() {
+        let information = &dataset.meta_information.process_information;
+        let reference = &information.reference_function;
+        let location = &information.geography.location;
+        let id = product_id(&reference.name, location, &reference.unit);
+        if products.insert(id, dataset).is_some() {
+            duplicates.push(format!(
+                "{} in {location} ({})",
+                reference.name, reference.unit
+            ));
+        }
+    }
+    if !duplicates.is_empty() {
+        duplicates.sort();
+        duplicates.dedup();
+        return Err(OdysseyErrors::InvalidDatabase(format!(
+            "several datasets provide each of these products, keep only one of them:\n{}",
+            duplicates.join("\n")
+        )));
+    }
+
+    let mut res = HashMap::new();
+    for (id, dataset) in products.iter() {
+        let information = &dataset.meta_information.process_information;
+        let reference = &information.reference_function;
+        let mut intermediates = vec![];
+        let mut elementaries = vec![];
+        for exchange in dataset.flows.exchanges.iter() {
+            match (exchange.input, exchange.output) {
+                (_, Some(REFERENCE_PRODUCT)) => intermediates.push((id.clone(), exchange.amount)),
+                (Some(FROM_ENVIRONMENT), _) | (_, Some(TO_ENVIRONMENT)) => {
+                    let flow = format!(
+                        "{}|{}|{}|{}",
+                        exchange.name,
+                        exchange.category.as_deref().unwrap_or_default(),
+                        exchange.subcategory.as_deref().unwrap_or_default(),
+                        exchange.unit
+                    );
+                    let item = ElementaryItem {
+                        id: Uuid::new_v5(&namespace, flow.as_bytes()).to_string(),
+                        name: exchange.name.clone(),
+                        compartment: exchange.category.clone().unwrap_or_default(),
+                        subcompartment: exchange.subcategory.clone(),
+                        unit: exchange.unit.clone(),
+                    };
+                    elementaries.push((item, exchange.amount));
+                }
+                (Some(_), _) => {
+                    let location = exchange
+                        .location
+                        .as_deref()
+                        .unwrap_or(&information.geography.location);
+                    let supplier = product_id(&exchange.name, location, &exchange.unit);
+                    if products.contains_key(&supplier) {
+                        intermediates.push((supplier, -exchange.amount));
+                    }
+                }
+                _ => {}
+            }
+        }
+        let process = Process {
+            id: id.clone(),
+            name: reference.name.clone(),
+            product: reference
+                .local_name
+                .clone()
+                .unwrap_or(reference.name.clone()),
+            location: Some(information.geography.location.clone()),
+            unit: reference.unit.clone(),
+            intermediates,
+            elementaries,
+        };
+        res.insert(id.clone(), process);
+    }
+    Ok(res)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_processes() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<ecoSpold xmlns="http://www.EcoInvent.org/EcoSpold01">
+  <dataset number="1">
+    <metaInformation>
+      <processInformation>
+        <referenceFunction name="steel" unit="kg"/>
+        <geography location="RER"/>
+      </processInformation>
+    </metaInformation>
+    <flowData>
+      <exchange number="1" name="steel" location="RER" unit="kg" meanValue="1"><outputGroup>0</outputGroup></exchange>
+      <exchange number="2" name="electricity" location="RER" unit="kWh" meanValue="2"><inputGroup>5</inputGroup></exchange>
+      <exchange number="3" name="water" location="RER" unit="kg" meanValue="3"><inputGroup>5</inputGroup></exchange>
+      <exchange number="4" name="Carbon dioxide, fossil" category="air" subCategory="low population density" unit="kg" meanValue="0.5"><outputGroup>4</outputGroup></exchange>
+    </flowData>
+  </dataset>
+  <dataset number="2">
+    <metaInformation>
+      <processInformation>
+        <referenceFunction name="electricity" localName="Strom" unit="kWh"/>
+        <geography location="RER"/>
+      </processInformation>
+    </metaInformation>
+    <flowData>
+      <exchange number="1" name="electricity" location="RER" unit="kWh" meanValue="1"><outputGroup>0</outputGroup></exchange>
+    </flowData>
+  </dataset>
+</ecoSpold>"#;
+        let data: parse::EcoSpold = quick_xml::de::from_str(xml).unwrap();
+        let processes = build_processes("EcoSpold1_test", data.datasets).unwrap();
+        assert_eq!(processes.len(), 2);
+
+        let steel = processes.values().find(|p| p.name == "steel").unwrap();
+        let electricity = processes
+            .values()
+            .find(|p| p.name == "electricity")
+            .unwrap();
+        assert_eq!(electricity.product, "Strom");
+        // Water is provided by no dataset
+        assert_eq!(
+            steel.intermediates,
+            vec![(steel.id.clone(), 1.), (electricity.id.clone(), -2.)]
+        );
+        assert_eq!(steel.elementaries.len(), 1);
+        assert_eq!(steel.elementaries[0].0.compartment, "air");
+        assert_eq!(steel.elementaries[0].1, 0.5);
+
+        // The same datasets twice can't be linked
+        let mut datasets = quick_xml::de::from_str::<parse::EcoSpold>(xml)
+            .unwrap()
+            .datasets;
+        datasets.extend(
+            quick_xml::de::from_str::<parse::EcoSpold>(xml)
+                .unwrap()
+                .datasets,
+        );
+        let error = build_processes("EcoSpold1_test", datasets).unwrap_err();
+        assert!(error
+            .to_string()
+            .ends_with("electricity in RER (kWh)\nsteel in RER (kg)"));
+    }
+}
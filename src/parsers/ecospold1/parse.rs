@@ -0,0 +1,110 @@
+use std::{
+    fs::{self, File},
+    io::BufReader,
+    path::Path,
+};
+
+use rayon::prelude::*;
+use serde::Deserialize;
+
+use crate::errors::Result;
+
+#[derive(Debug, Deserialize)]
+pub struct ReferenceFunction {
+    #[serde(rename = "@name")]
+    pub name: String,
+
+    #[serde(rename = "@localName")]
+    pub local_name: Option<String>,
+
+    #[serde(rename = "@unit")]
+    pub unit: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Geography {
+    #[serde(rename = "@location")]
+    pub location: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ProcessInformation {
+    #[serde(rename = "referenceFunction")]
+    pub reference_function: ReferenceFunction,
+
+    pub geography: Geography,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MetaInformation {
+    #[serde(rename = "processInformation")]
+    pub process_information: ProcessInformation,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Exchange {
+    #[serde(rename = "@name")]
+    pub name: String,
+
+    #[serde(rename = "@location")]
+    pub location: Option<String>,
+
+    #[serde(rename = "@unit")]
+    pub unit: String,
+
+    #[serde(rename = "@category")]
+    pub category: Option<String>,
+
+    #[serde(rename = "@subCategory")]
+    pub subcategory: Option<String>,
+
+    #[serde(rename = "@meanValue")]
+    pub amount: f64,
+
+    #[serde(rename = "inputGroup")]
+    pub input: Option<u8>,
+
+    #[serde(rename = "outputGroup")]
+    pub output: Option<u8>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FlowData {
+    #[serde(rename = "exchange", default)]
+    pub exchanges: Vec<Exchange>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Dataset {
+    #[serde(rename = "metaInformation")]
+    pub meta_information: MetaInformation,
+
+    #[serde(rename = "flowData")]
+    pub flows: FlowData,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct EcoSpold {
+    #[serde(rename = "dataset")]
+    pub datasets: Vec<Dataset>,
+}
+
+/// Datasets of the XML files of the `folder`, each file holding one or several of them.
+pub fn parse_ecospold1(folder: &Path) -> Result<Vec<Dataset>> {
+    let paths = fs::read_dir(folder)?
+        .map(|entry| Ok(entry?.path()))
+        .collect::<Result<Vec<_>>>()?;
+    let files = paths
+        .into_par_iter()
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|e| e.eq_ignore_ascii_case("xml"))
+        })
+        .map(|path| {
+            let reader = BufReader::new(File::open(&path)?);
+            let data: EcoSpold = quick_xml::de::from_reader(reader)?;
+            Ok(data.datasets)
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(files.into_iter().flatten().collect())
+}
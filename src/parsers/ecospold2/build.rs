@@ -1,108 +1,261 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use crate::{
-    errors::{OdysseyErrors, Result},
-    parsers::ecospold2::parse::{ids_from_str, EcoSpold},
-    utils::{
-        matrix::{MappedMatrix, MappedMatrixBuilder},
-        search::InventoryItem,
+    parsers::{
+        build::Process,
+        ecospold2::parse::{ids_from_str, Classification, EcoSpold},
     },
+    utils::search::{isic_section, ActivityMetadata, ActivityType, ElementaryItem},
 };
 
-pub fn build_matrices(
-    mut processes: HashMap<String, EcoSpold>,
-) -> Result<(MappedMatrix<String, String>, MappedMatrix<String, String>)> {
-    let mut a = MappedMatrixBuilder::new();
-    let mut b = MappedMatrixBuilder::new();
-    let mut processes_fifo = vec![];
-    while !processes.is_empty() {
-        if processes_fifo.is_empty() {
-            let p = processes.iter().next().unwrap();
-            processes_fifo.push(p.0.clone());
-        }
-
-        let col_id = processes_fifo.remove(0);
-        if a.col(&col_id).is_some() {
-            continue;
+/// Converts the parsed datasets into processes, keeping their ids.
+///
+/// Exchanges with the `skipped` datasets are left out. Exchanges with other activities
+/// missing from the datasets are left out as well, and returned to be reported.
+pub fn build_processes(
+    processes: HashMap<String, EcoSpold>,
+    skipped: &HashSet<String>,
+) -> (HashMap<String, Process>, Vec<String>) {
+    let ids: HashSet<String> = processes.keys().cloned().collect();
+    let mut unlinked = vec![];
+    let mut res = HashMap::new();
+    for (id, data) in processes {
+        let name = &data.activity.activity_description.activity.activity_name;
+        for exchange in data.activity.flows.intermediates.iter() {
+            let Some(process_id) = exchange.process_id else {
+                continue;
+            };
+            let provider = format!("{}_{}", process_id, exchange.product_id);
+            if !ids.contains(&provider) && !skipped.contains(&provider) {
+                unlinked.push(format!("{name}: {} from {provider}", exchange.name));
+            }
         }
+        let mut process = build_process(&id, data);
+        process
+            .intermediates
+            .retain(|(provider, _)| ids.contains(provider));
+        res.insert(id, process);
+    }
+    unlinked.sort();
+    (res, unlinked)
+}
 
-        a.add_col(col_id.clone());
-        a.add_row(col_id.clone());
-        b.add_col(col_id.clone());
-
-        let process = processes
-            .remove(&col_id)
-            .ok_or(OdysseyErrors::MissingId(format!(
-                "Missing process {col_id:?} while parsing ecoinvent"
-            )))?;
+fn build_process(id: &str, data: EcoSpold) -> Process {
+    let (_, product_id) = ids_from_str(id).unwrap();
+    let activity = data.activity;
+    let product = activity
+        .flows
+        .intermediates
+        .iter()
+        .find(|e| e.process_id.is_none() && e.product_id == product_id)
+        .unwrap_or_else(|| panic!("No product for process {:?}", id));
+    let (product, unit) = (product.name.clone(), product.unit.clone());
 
-        for exchange in process.activity.flows.intermediates {
-            let row_id = if let Some(process_id) = exchange.process_id {
-                let key = format!("{}_{}", process_id, exchange.product_id);
-                if exchange.amount != 0. && processes.contains_key(&key) {
-                    processes_fifo.push(key.clone());
-                }
-                key
-            } else {
-                col_id.clone()
+    let intermediates = activity
+        .flows
+        .intermediates
+        .into_iter()
+        .map(|exchange| {
+            let row_id = match exchange.process_id {
+                Some(process_id) => format!("{}_{}", process_id, exchange.product_id),
+                None => id.to_string(),
             };
-
             let multiplicator = if exchange.input.is_some() { -1. } else { 1. };
-            a.add_triplet(row_id, col_id.clone(), multiplicator * exchange.amount);
-        }
+            (row_id, multiplicator * exchange.amount)
+        })
+        .collect();
 
-        if let Some(elementaries) = process.activity.flows.elementaries {
-            for exchange in elementaries.iter() {
-                if exchange.amount == 0. {
-                    continue;
-                }
+    let elementaries = activity
+        .flows
+        .elementaries
+        .unwrap_or_default()
+        .into_iter()
+        .map(|exchange| {
+            let item = ElementaryItem {
+                id: exchange.product_id.to_string(),
+                name: exchange.name,
+                compartment: exchange.compartment.compartment,
+                subcompartment: exchange.compartment.subcompartment,
+                unit: exchange.unit,
+            };
+            (item, exchange.amount)
+        })
+        .collect();
 
-                let row_id = exchange.product_id.to_string();
-                b.add_triplet(row_id, col_id.clone(), exchange.amount);
-            }
-        }
+    Process {
+        id: id.to_string(),
+        name: activity.activity_description.activity.activity_name,
+        product,
+        location: Some(activity.activity_description.geography.shortname),
+        unit,
+        intermediates,
+        elementaries,
     }
+}
 
-    Ok((a.build(), b.build()))
+/// Classifications and descriptions of the datasets, by id of process.
+pub fn build_metadata(processes: &HashMap<String, EcoSpold>) -> HashMap<String, ActivityMetadata> {
+    processes
+        .iter()
+        .map(|(id, data)| (id.clone(), build_activity_metadata(id, data)))
+        .collect()
 }
 
-pub fn build_candidates(
-    processes: &mut HashMap<String, EcoSpold>,
-    version: &str,
-) -> HashMap<String, InventoryItem> {
-    let mut res = HashMap::new();
-    for (id, data) in processes.iter() {
-        let id = id.clone();
-        let (_, product_id) = ids_from_str(&id).unwrap();
-        let product = data
-            .activity
-            .flows
-            .intermediates
+fn build_activity_metadata(id: &str, data: &EcoSpold) -> ActivityMetadata {
+    let (_, product_id) = ids_from_str(id).unwrap();
+    let description = &data.activity.activity_description;
+    let isic = classification(&description.classifications, "ISIC");
+    let cpc = data
+        .activity
+        .flows
+        .intermediates
+        .iter()
+        .find(|e| e.process_id.is_none() && e.product_id == product_id)
+        .and_then(|e| classification(&e.classifications, "CPC"));
+    let activity_type = match description.activity.special_activity_type {
+        0 => ActivityType::Transformation,
+        1 => ActivityType::Market,
+        10 => ActivityType::MarketGroup,
+        _ => ActivityType::Other,
+    };
+    let technology_level = description
+        .technology
+        .as_ref()
+        .and_then(|t| match t.level? {
+            1 => Some("new"),
+            2 => Some("modern"),
+            3 => Some("current"),
+            4 => Some("old"),
+            5 => Some("outdated"),
+            _ => None,
+        });
+    let time_period = description.time_period.as_ref().map(|t| {
+        let year = |date: &str| date.get(..4).unwrap_or(date).to_string();
+        format!("{}-{}", year(&t.start), year(&t.end))
+    });
+    let comment = description.activity.general_comment.as_ref().map(|c| {
+        let mut texts: Vec<_> = c.texts.iter().collect();
+        texts.sort_by_key(|t| t.index);
+        texts
             .iter()
-            .find(|e| e.process_id.is_none() && e.product_id == product_id)
-            .unwrap_or_else(|| panic!("No product for process {:?}", id));
-        let location = Some(
-            data.activity
-                .activity_description
-                .geography
-                .shortname
-                .clone(),
+            .map(|t| t.value.trim())
+            .collect::<Vec<_>>()
+            .join("\n")
+    });
+    ActivityMetadata {
+        isic_section: isic.as_deref().and_then(isic_section).map(str::to_string),
+        isic,
+        cpc,
+        activity_type: Some(activity_type),
+        time_period,
+        technology_level: technology_level.map(str::to_string),
+        comment: comment.filter(|c| !c.is_empty()),
+    }
+}
+
+/// Value of the first classification whose system starts with `system`.
+fn classification(classifications: &[Classification], system: &str) -> Option<String> {
+    classifications
+        .iter()
+        .find(|c| c.system.starts_with(system))
+        .map(|c| c.value.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_activity_metadata() {
+        let dataset = r#"<ecoSpold>
+  <activityDataset>
+    <activityDescription>
+      <activity specialActivityType="1">
+        <activityName>market for steel, low-alloyed</activityName>
+        <generalComment>
+          <text xml:lang="en" index="2">Transport is included.</text>
+          <text xml:lang="en" index="1">Steel supplied in Europe.</text>
+        </generalComment>
+      </activity>
+      <classification classificationId="7ac1cbc6-1385-4a68-8647-ed7aa78db201">
+        <classificationSystem xml:lang="en">ISIC rev.4 ecoinvent</classificationSystem>
+        <classificationValue xml:lang="en">2410:Manufacture of basic iron and steel</classificationValue>
+      </classification>
+      <geography><shortname>RER</shortname></geography>
+      <technology technologyLevel="3"/>
+      <timePeriod startDate="2014-01-01" endDate="2023-12-31" isDataValidForEntirePeriod="true"/>
+    </activityDescription>
+    <flowData>
+      <intermediateExchange intermediateExchangeId="11111111-1111-1111-1111-111111111111" amount="1">
+        <name>steel, low-alloyed</name>
+        <unitName>kg</unitName>
+        <classification classificationId="39b0f0ab-1a06-4f42-9a40-1de56c6f5f8e">
+          <classificationSystem xml:lang="en">CPC</classificationSystem>
+          <classificationValue xml:lang="en">41122: Alloy steel in ingots</classificationValue>
+        </classification>
+        <outputGroup>0</outputGroup>
+      </intermediateExchange>
+    </flowData>
+  </activityDataset>
+</ecoSpold>"#;
+        let id = "55555555-5555-5555-5555-555555555555_11111111-1111-1111-1111-111111111111";
+        let dataset: EcoSpold = quick_xml::de::from_str(dataset).unwrap();
+        let metadata = build_activity_metadata(id, &dataset);
+        assert_eq!(metadata.isic_section.as_deref(), Some("C"));
+        assert_eq!(
+            metadata.cpc.as_deref(),
+            Some("41122: Alloy steel in ingots")
         );
-        let name = data
-            .activity
-            .activity_description
-            .activity
-            .activity_name
-            .clone();
-        let item = InventoryItem {
-            id: id.clone(),
-            database: format!("Ecoinvent_{}", version),
-            name,
-            alt_name: Some(product.name.clone()),
-            location,
-            unit: product.unit.clone(),
+        assert_eq!(metadata.activity_type, Some(ActivityType::Market));
+        assert_eq!(metadata.time_period.as_deref(), Some("2014-2023"));
+        assert_eq!(metadata.technology_level.as_deref(), Some("current"));
+        assert_eq!(
+            metadata.comment.as_deref(),
+            Some("Steel supplied in Europe.\nTransport is included.")
+        );
+    }
+
+    #[test]
+    fn test_build_processes() {
+        let steel = "22222222-2222-2222-2222-222222222222_11111111-1111-1111-1111-111111111111";
+        let dataset = r#"<ecoSpold><activityDataset>
+  <activityDescription>
+    <activity><activityName>steel production</activityName></activity>
+    <geography><shortname>GLO</shortname></geography>
+  </activityDescription>
+  <flowData>
+    <intermediateExchange intermediateExchangeId="11111111-1111-1111-1111-111111111111" amount="1">
+      <name>steel</name><unitName>kg</unitName><outputGroup>0</outputGroup>
+    </intermediateExchange>
+    <intermediateExchange intermediateExchangeId="44444444-4444-4444-4444-444444444444" activityLinkId="33333333-3333-3333-3333-333333333333" amount="2">
+      <name>coal</name><unitName>kg</unitName><inputGroup>5</inputGroup>
+    </intermediateExchange>
+    <intermediateExchange intermediateExchangeId="66666666-6666-6666-6666-666666666666" activityLinkId="55555555-5555-5555-5555-555555555555" amount="3">
+      <name>electricity</name><unitName>kWh</unitName><inputGroup>5</inputGroup>
+    </intermediateExchange>
+  </flowData>
+</activityDataset></ecoSpold>"#;
+        let datasets = || {
+            HashMap::from([(
+                steel.to_string(),
+                quick_xml::de::from_str::<EcoSpold>(dataset).unwrap(),
+            )])
         };
-        res.insert(id, item);
+        let coal = "33333333-3333-3333-3333-333333333333_44444444-4444-4444-4444-444444444444";
+
+        // Links to skipped datasets are left out silently
+        let skipped = HashSet::from([coal.to_string()]);
+        let (processes, unlinked) = build_processes(datasets(), &skipped);
+        assert_eq!(
+            processes[steel].intermediates,
+            vec![(steel.to_string(), 1.)]
+        );
+        assert_eq!(
+            unlinked,
+            vec!["steel production: electricity from 55555555-5555-5555-5555-555555555555_66666666-6666-6666-6666-666666666666"]
+        );
+
+        let (_, unlinked) = build_processes(datasets(), &HashSet::new());
+        assert_eq!(unlinked.len(), 2);
     }
-    res
 }
@@ -0,0 +1,268 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    errors::Result,
+    parsers::ecospold2::parse::{self, EcoSpold},
+    utils::expression::evaluate_all,
+};
+
+/// Quantity of a dataset which may be computed from the others, such as a parameter,
+/// the amount of an exchange or of one of its properties.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Variable {
+    pub id: String,
+    pub name: String,
+    pub unit: Option<String>,
+    pub amount: f64,
+    /// Name of the variable in mathematical relations.
+    pub variable: Option<String>,
+    /// Mathematical relation computing the amount.
+    pub formula: Option<String>,
+}
+
+/// Exchange of a dataset, with the properties of its flow.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Exchange {
+    /// Id of the exchanged product or elementary flow.
+    pub flow: String,
+    pub elementary: bool,
+    pub input: bool,
+    /// Amount of the exchange, as given in the dataset whether it is an input or an output.
+    pub amount: Variable,
+    /// Properties of one unit of the flow.
+    pub properties: Vec<Variable>,
+}
+
+/// Parameters, exchanges and properties of an EcoSpold2 dataset, with their
+/// mathematical relations.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct ProcessData {
+    pub parameters: Vec<Variable>,
+    pub exchanges: Vec<Exchange>,
+}
+
+impl ProcessData {
+    /// Properties of the flow `id` in the dataset.
+    pub fn properties(&self, id: &str) -> &[Variable] {
+        self.exchanges
+            .iter()
+            .find(|e| e.flow == id)
+            .map(|e| e.properties.as_slice())
+            .unwrap_or(&[])
+    }
+
+    /// Outputs minus inputs of the property named `name`, e.g. `dry mass` or
+    /// `carbon content, fossil`, which is zero for a balanced dataset. Exchanges without
+    /// this property are left out.
+    pub fn balance(&self, name: &str) -> f64 {
+        self.exchanges
+            .iter()
+            .flat_map(|e| {
+                e.properties
+                    .iter()
+                    .filter(|p| p.name.eq_ignore_ascii_case(name))
+                    .map(|p| if e.input { -1. } else { 1. } * e.amount.amount * p.amount)
+            })
+            .sum()
+    }
+
+    /// Dataset with the mathematical relations evaluated again with the `values` of
+    /// some variables, by name. Variables without relation keep their amount.
+    ///
+    /// Relations are evaluated with [evaluate_all], which only supports arithmetic:
+    /// relations using the other functions of ecoinvent, such as `Ref(...)`, `IF(...)`
+    /// or comparisons, fail the evaluation.
+    pub fn evaluate(&self, values: &HashMap<String, f64>) -> Result<ProcessData> {
+        let values: HashMap<String, f64> = values
+            .iter()
+            .map(|(name, value)| (name.to_lowercase(), *value))
+            .collect();
+        // Relations of amounts without variable name are evaluated under their position
+        let keys: Vec<String> = self
+            .variables()
+            .enumerate()
+            .map(|(i, variable)| match &variable.variable {
+                Some(name) => name.to_lowercase(),
+                None => format!("#{i}"),
+            })
+            .collect();
+        let mut variables = HashMap::new();
+        let mut formulas = vec![];
+        for (key, variable) in keys.iter().zip(self.variables()) {
+            match &variable.formula {
+                Some(formula) if !values.contains_key(key) => {
+                    formulas.push((key.clone(), formula.clone()))
+                }
+                _ => {
+                    variables.insert(key.clone(), variable.amount);
+                }
+            }
+        }
+        variables.extend(values);
+        let variables = evaluate_all(&formulas, variables)?;
+
+        let mut res = self.clone();
+        for (key, variable) in keys.iter().zip(res.variables_mut()) {
+            if let Some(value) = variables.get(key) {
+                variable.amount = *value;
+            }
+        }
+        Ok(res)
+    }
+
+    /// Parameters, amounts of the exchanges and properties of the dataset, in order.
+    fn variables(&self) -> impl Iterator<Item = &Variable> {
+        let exchanges = self.exchanges.iter().map(|e| &e.amount);
+        let properties = self.exchanges.iter().flat_map(|e| e.properties.iter());
+        self.parameters.iter().chain(exchanges).chain(properties)
+    }
+
+    fn variables_mut(&mut self) -> impl Iterator<Item = &mut Variable> {
+        let (exchanges, properties): (Vec<_>, Vec<_>) = self
+            .exchanges
+            .iter_mut()
+            .map(|e| (&mut e.amount, &mut e.properties))
+            .unzip();
+        let properties = properties.into_iter().flat_map(|p| p.iter_mut());
+        self.parameters
+            .iter_mut()
+            .chain(exchanges)
+            .chain(properties)
+    }
+}
+
+/// Parameters, exchanges and properties of the datasets, by id of process.
+pub fn build_data(processes: &HashMap<String, EcoSpold>) -> HashMap<String, ProcessData> {
+    processes
+        .iter()
+        .map(|(id, data)| (id.clone(), build_process_data(id, data)))
+        .collect()
+}
+
+fn build_process_data(id: &str, data: &EcoSpold) -> ProcessData {
+    let flows = &data.activity.flows;
+    let parameters = flows
+        .parameters
+        .iter()
+        .map(|p| Variable {
+            id: p.parameter_id.to_string(),
+            name: p.name.clone(),
+            unit: p.unit.clone(),
+            amount: p.amount,
+            variable: p.variable.clone(),
+            formula: p.formula.clone(),
+        })
+        .collect();
+    let intermediates = flows.intermediates.iter().map(|e| {
+        let flow = match e.process_id {
+            Some(process_id) => format!("{}_{}", process_id, e.product_id),
+            None => id.to_string(),
+        };
+        let amount = Variable {
+            id: e.product_id.to_string(),
+            name: e.name.clone(),
+            unit: Some(e.unit.clone()),
+            amount: e.amount,
+            variable: e.variable.clone(),
+            formula: e.formula.clone(),
+        };
+        Exchange {
+            flow,
+            elementary: false,
+            input: e.input.is_some(),
+            amount,
+            properties: e.properties.iter().map(property).collect(),
+        }
+    });
+    let elementaries = flows.elementaries.iter().flatten().map(|e| {
+        let amount = Variable {
+            id: e.product_id.to_string(),
+            name: e.name.clone(),
+            unit: Some(e.unit.clone()),
+            amount: e.amount,
+            variable: e.variable.clone(),
+            formula: e.formula.clone(),
+        };
+        Exchange {
+            flow: e.product_id.to_string(),
+            elementary: true,
+            input: e.input.is_some(),
+            amount,
+            properties: e.properties.iter().map(property).collect(),
+        }
+    });
+    ProcessData {
+        parameters,
+        exchanges: intermediates.chain(elementaries).collect(),
+    }
+}
+
+fn property(property: &parse::Property) -> Variable {
+    Variable {
+        id: property.property_id.to_string(),
+        name: property.name.clone(),
+        unit: property.unit.clone(),
+        amount: property.amount,
+        variable: property.variable.clone(),
+        formula: property.formula.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DATASET: &str = r#"<ecoSpold>
+  <activityDataset>
+    <activityDescription>
+      <activity><activityName>wood pellet production</activityName></activity>
+      <geography><shortname>GLO</shortname></geography>
+    </activityDescription>
+    <flowData>
+      <intermediateExchange intermediateExchangeId="11111111-1111-1111-1111-111111111111" amount="1" variableName="output">
+        <name>wood pellet</name>
+        <unitName>kg</unitName>
+        <property propertyId="aaaaaaaa-aaaa-aaaa-aaaa-aaaaaaaaaaaa" amount="0.9" variableName="pellet_dry_mass" mathematicalRelation="1 - moisture">
+          <name>dry mass</name>
+          <unitName>kg</unitName>
+        </property>
+        <outputGroup>0</outputGroup>
+      </intermediateExchange>
+      <intermediateExchange intermediateExchangeId="22222222-2222-2222-2222-222222222222" activityLinkId="33333333-3333-3333-3333-333333333333" amount="1.8" mathematicalRelation="output * pellet_dry_mass / 0.5">
+        <name>wood chips, wet</name>
+        <unitName>kg</unitName>
+        <property propertyId="aaaaaaaa-aaaa-aaaa-aaaa-aaaaaaaaaaaa" amount="0.5">
+          <name>dry mass</name>
+          <unitName>kg</unitName>
+        </property>
+        <inputGroup>5</inputGroup>
+      </intermediateExchange>
+      <parameter parameterId="44444444-4444-4444-4444-444444444444" amount="0.1" variableName="moisture">
+        <name>moisture of the pellets</name>
+      </parameter>
+    </flowData>
+  </activityDataset>
+</ecoSpold>"#;
+
+    #[test]
+    fn test_process_data() {
+        let id = "55555555-5555-5555-5555-555555555555_11111111-1111-1111-1111-111111111111";
+        let dataset: EcoSpold = quick_xml::de::from_str(DATASET).unwrap();
+        let data = build_process_data(id, &dataset);
+        assert_eq!(data.parameters[0].variable.as_deref(), Some("moisture"));
+        assert_eq!(data.properties(id)[0].name, "dry mass");
+        assert!(data.exchanges[1].input);
+        assert_eq!(data.exchanges[1].amount.amount, 1.8);
+        assert!(data.balance("Dry mass").abs() < 1e-9);
+
+        // Wetter pellets require less chips, and the dataset stays balanced
+        let wet = data
+            .evaluate(&HashMap::from([("Moisture".to_string(), 0.2)]))
+            .unwrap();
+        assert!((wet.properties(id)[0].amount - 0.8).abs() < 1e-9);
+        assert!((wet.exchanges[1].amount.amount - 1.6).abs() < 1e-9);
+        assert!(wet.balance("dry mass").abs() < 1e-9);
+    }
+}
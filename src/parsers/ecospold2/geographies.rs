@@ -0,0 +1,166 @@
+use std::{collections::HashSet, fs, fs::File, io::BufReader, path::Path};
+
+use serde::Deserialize;
+
+use crate::{errors::Result, utils::geographies::Geographies};
+
+#[derive(Debug, Deserialize)]
+struct Geography {
+    shortname: String,
+
+    /// UN M49 code of the continent
+    #[serde(rename = "@uNRegionCode")]
+    region: Option<String>,
+
+    /// UN M49 code of the sub-continent
+    #[serde(rename = "@uNSubregionCode")]
+    subregion: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ValidGeographies {
+    #[serde(rename = "geography", default)]
+    geographies: Vec<Geography>,
+}
+
+/// Ecoinvent regions derived from UN M49 codes.
+const REGIONS: &[&str] = &[
+    "Europe without Switzerland",
+    "RER",
+    "RAF",
+    "RME",
+    "RAS",
+    "RNA",
+    "RLA",
+    "OCE",
+];
+
+/// Ecoinvent regions containing the UN M49 `region` and `subregion`,
+/// from the most specific to the most general.
+///
+/// Regions carry the codes of their continent too: they are only given the regions
+/// containing them, e.g. `RER` for `Europe without Switzerland` but nothing for `RER`.
+fn ecoinvent_regions(shortname: &str, region: &str, subregion: Option<&str>) -> Vec<&'static str> {
+    let res = m49_regions(shortname, region, subregion);
+    if !REGIONS.contains(&shortname) {
+        return res;
+    }
+    match res.iter().position(|r| *r == shortname) {
+        Some(index) => res[index + 1..].to_vec(),
+        None => vec![],
+    }
+}
+
+fn m49_regions(shortname: &str, region: &str, subregion: Option<&str>) -> Vec<&'static str> {
+    match (region, subregion) {
+        ("150", _) if shortname == "CH" => vec!["RER"],
+        ("150", _) => vec!["Europe without Switzerland", "RER"],
+        ("002", _) => vec!["RAF"],
+        ("142", Some("145")) => vec!["RME"],
+        ("142", _) => vec!["RAS"],
+        ("019", Some("021")) => vec!["RNA"],
+        ("019", _) => vec!["RLA"],
+        ("009", _) => vec!["OCE"],
+        _ => vec![],
+    }
+}
+
+/// Parses the location hierarchy from the `MasterData/Geographies.xml` file
+/// of an ecospold2 database. Returns an empty hierarchy if the file is missing.
+pub fn parse_geographies(folder: &Path) -> Result<Geographies> {
+    let path = folder.join("MasterData").join("Geographies.xml");
+    let mut res = Geographies::new();
+    if !fs::exists(&path)? {
+        return Ok(res);
+    }
+
+    let reader = BufReader::new(File::open(path)?);
+    let data: ValidGeographies = quick_xml::de::from_reader(reader)?;
+    let known: HashSet<&str> = data
+        .geographies
+        .iter()
+        .map(|g| g.shortname.as_str())
+        .collect();
+    for geography in data.geographies.iter() {
+        let Some(region) = &geography.region else {
+            continue;
+        };
+        let parents: Vec<String> =
+            ecoinvent_regions(&geography.shortname, region, geography.subregion.as_deref())
+                .into_iter()
+                .filter(|p| known.contains(p) && *p != geography.shortname)
+                .map(|p| p.to_string())
+                .collect();
+        if !parents.is_empty() {
+            res.insert(geography.shortname.clone(), parents);
+        }
+    }
+    Ok(res)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ecoinvent_regions() {
+        assert_eq!(
+            ecoinvent_regions("FR", "150", Some("155")),
+            ["Europe without Switzerland", "RER"]
+        );
+        assert_eq!(ecoinvent_regions("CH", "150", Some("155")), ["RER"]);
+        assert_eq!(ecoinvent_regions("ZA", "002", Some("202")), ["RAF"]);
+        assert_eq!(ecoinvent_regions("SA", "142", Some("145")), ["RME"]);
+        assert_eq!(ecoinvent_regions("CN", "142", Some("030")), ["RAS"]);
+        assert_eq!(ecoinvent_regions("US", "019", Some("021")), ["RNA"]);
+        assert_eq!(ecoinvent_regions("BR", "019", Some("419")), ["RLA"]);
+        assert_eq!(ecoinvent_regions("AU", "009", None), ["OCE"]);
+        assert!(ecoinvent_regions("AQ", "010", None).is_empty());
+
+        // Regions are not their own sub-regions
+        assert!(ecoinvent_regions("RER", "150", None).is_empty());
+        assert_eq!(
+            ecoinvent_regions("Europe without Switzerland", "150", None),
+            ["RER"]
+        );
+        assert!(ecoinvent_regions("RNA", "019", None).is_empty());
+        assert!(ecoinvent_regions("RLA", "019", Some("419")).is_empty());
+    }
+
+    #[test]
+    fn test_parse_geographies() {
+        let folder =
+            std::env::temp_dir().join(format!("odyssey-geographies-{}", std::process::id()));
+        fs::create_dir_all(folder.join("MasterData")).unwrap();
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<validGeographies>
+  <geography uNRegionCode="150" uNSubregionCode="155"><shortname>FR</shortname></geography>
+  <geography uNRegionCode="150" uNSubregionCode="155"><shortname>CH</shortname></geography>
+  <geography uNRegionCode="142" uNSubregionCode="030"><shortname>CN</shortname></geography>
+  <geography uNRegionCode="150"><shortname>RER</shortname></geography>
+  <geography uNRegionCode="150"><shortname>Europe without Switzerland</shortname></geography>
+  <geography><shortname>GLO</shortname></geography>
+</validGeographies>"#;
+        fs::write(folder.join("MasterData").join("Geographies.xml"), xml).unwrap();
+        let geographies = parse_geographies(&folder).unwrap();
+        fs::remove_dir_all(&folder).unwrap();
+
+        assert_eq!(
+            geographies.parents("FR"),
+            ["Europe without Switzerland", "RER"]
+        );
+        assert_eq!(geographies.parents("CH"), ["RER"]);
+        // RAS is not a location of the database
+        assert!(geographies.parents("CN").is_empty());
+        assert_eq!(geographies.fallbacks("CH"), ["CH", "RER", "RoW", "GLO"]);
+        assert_eq!(
+            geographies.fallbacks("FR"),
+            ["FR", "Europe without Switzerland", "RER", "RoW", "GLO"]
+        );
+        assert_eq!(
+            geographies.fallbacks("Europe without Switzerland"),
+            ["Europe without Switzerland", "RER", "RoW", "GLO"]
+        );
+        assert_eq!(geographies.fallbacks("RER"), ["RER", "RoW", "GLO"]);
+    }
+}
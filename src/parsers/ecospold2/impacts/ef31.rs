@@ -3,11 +3,8 @@ use std::fs::File;
 use crate::{
     comput::impacts::ImpactCategory,
     errors::Result,
-    parsers::impacts::ef31::EF31Impacts,
-    utils::{
-        constants::DATABASES_PATH,
-        matrix::{MappedMatrix, MappedMatrixBuilder},
-    },
+    parsers::{ecospold2::impacts::mapping::mapping_path, impacts::ef31::EF31Impacts},
+    utils::matrix::{MappedMatrix, MappedMatrixBuilder},
 };
 
 #[rustfmt::skip]
@@ -15,6 +12,13 @@ use crate::{
 pub struct EcoinventEF31Impacts {
     #[serde(rename = "elementary_flow_id")]
     pub elementary_id: String,
+
+    /// Name, compartment and subcompartment of the flow, to match flows without ecoinvent id
+    pub name: Option<String>,
+
+    pub compartment: Option<String>,
+
+    pub subcompartment: Option<String>,
     
     #[serde(rename = "climate change|global warming potential (GWP100)")]
     pub gwp100: Option<f64>,
@@ -129,12 +133,7 @@ pub fn construct_impact_matrix(
     version: &str,
     intervention: &MappedMatrix<String, String>,
 ) -> Result<MappedMatrix<ImpactCategory, String>> {
-    let file = File::open(
-        DATABASES_PATH
-            .join("ecoinvent_lcia")
-            .join(format!("{}/methods_mapped", version))
-            .join(format!("EF v3.1_mapped_{}.csv", version)),
-    )?;
+    let file = File::open(mapping_path(version))?;
     let mut rdr = csv::Reader::from_reader(file);
     let mut mat = MappedMatrixBuilder::new();
     mat.copy_rows_into_cols(intervention);
@@ -0,0 +1,96 @@
+use std::{collections::HashMap, fs::File, path::PathBuf};
+
+use crate::{
+    errors::{OdysseyErrors, Result},
+    parsers::ecospold2::{impacts::ef31::EcoinventEF31Impacts, upload_lcia_files},
+    utils::constants::DATABASES_PATH,
+};
+
+/// Subcompartments of ecoinvent 2 and SimaPro, and their names in ecoinvent 3.
+const SUBCOMPARTMENTS: [(&str, &str); 12] = [
+    (
+        "low population density",
+        "non-urban air or from high stacks",
+    ),
+    ("high population density", "urban air close to ground"),
+    ("low. pop.", "non-urban air or from high stacks"),
+    ("high. pop.", "urban air close to ground"),
+    ("low. pop., long-term", "low population density, long-term"),
+    (
+        "stratosphere + troposphere",
+        "lower stratosphere + upper troposphere",
+    ),
+    ("river", "surface water"),
+    ("lake", "surface water"),
+    ("groundwater", "ground water"),
+    ("groundwater, long-term", "ground water, long-term"),
+    ("", "unspecified"),
+    ("(unspecified)", "unspecified"),
+];
+
+/// Name, compartment and subcompartment of a flow, as named in ecoinvent 3.
+pub type FlowKey = (String, String, String);
+
+pub fn flow_key(name: &str, compartment: &str, subcompartment: &str) -> FlowKey {
+    let compartment = compartment.to_lowercase();
+    let compartment = match compartment.as_str() {
+        "resource" | "resources" | "raw" => "natural resource".to_string(),
+        _ => compartment,
+    };
+    let subcompartment = subcompartment.trim().to_lowercase();
+    let subcompartment = SUBCOMPARTMENTS
+        .iter()
+        .find(|(old, _)| *old == subcompartment)
+        .map(|(_, new)| new.to_string())
+        .unwrap_or(subcompartment);
+    (name.to_lowercase(), compartment, subcompartment)
+}
+
+/// Latest ecoinvent version of the LCIA mappings.
+pub fn latest_version() -> Result<String> {
+    upload_lcia_files()?;
+    let path = DATABASES_PATH.join("ecoinvent_lcia");
+    let mut versions: Vec<(Vec<u32>, String)> = std::fs::read_dir(&path)?
+        .filter_map(|entry| {
+            let version = entry.ok()?.file_name().to_string_lossy().to_string();
+            let numbers = version
+                .split('.')
+                .map(|n| n.parse().ok())
+                .collect::<Option<Vec<u32>>>()?;
+            Some((numbers, version))
+        })
+        .collect();
+    versions.sort();
+    let (_, version) = versions.pop().ok_or(OdysseyErrors::MissingId(format!(
+        "No EF v3.1 mapping in {}",
+        path.display()
+    )))?;
+    Ok(version)
+}
+
+/// Mapping of the EF v3.1 method to the flows of the ecoinvent `version`.
+pub fn mapping_path(version: &str) -> PathBuf {
+    DATABASES_PATH
+        .join("ecoinvent_lcia")
+        .join(format!("{}/methods_mapped", version))
+        .join(format!("EF v3.1_mapped_{}.csv", version))
+}
+
+/// Ids of the ecoinvent `version` elementary flows, by name, compartment and subcompartment.
+pub fn flow_ids(version: &str) -> Result<HashMap<FlowKey, String>> {
+    let mut rdr = csv::Reader::from_reader(File::open(mapping_path(version))?);
+    let mut res = HashMap::new();
+    for result in rdr.deserialize() {
+        let record: EcoinventEF31Impacts = result?;
+        let (Some(name), Some(compartment)) = (&record.name, &record.compartment) else {
+            continue;
+        };
+        let key = flow_key(
+            name,
+            compartment,
+            record.subcompartment.as_deref().unwrap_or("unspecified"),
+        );
+        res.insert(key, record.elementary_id);
+    }
+    Ok(res)
+}
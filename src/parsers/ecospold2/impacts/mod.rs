@@ -1 +1,2 @@
 pub mod ef31;
+pub mod mapping;
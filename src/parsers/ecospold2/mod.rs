@@ -1,25 +1,45 @@
 use git2::Repository;
 use serde::{Deserialize, Serialize};
 
-use std::fs::{self, File};
-use std::io::{BufReader, BufWriter};
-use std::{collections::HashMap, path::Path};
+use std::fs;
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+};
 
 use impacts::ef31::construct_impact_matrix;
 
 use crate::comput::impacts::ImpactCategory;
 use crate::comput::lca::Database;
-use crate::errors::Result;
-use crate::parsers::ecospold2::build::{build_candidates, build_matrices};
+use crate::errors::{DatasetError, Result};
+use crate::parsers::build::{build_candidates, build_elementaries, build_matrices};
+use crate::parsers::ecospold2::build::{build_metadata, build_processes};
+use crate::parsers::ecospold2::data::build_data;
+use crate::parsers::ecospold2::geographies::parse_geographies;
 use crate::parsers::ecospold2::parse::parse_ecospold2;
+use crate::utils::cache::{is_current, read_cache, write_cache};
 use crate::utils::constants::DATABASES_PATH;
+use crate::utils::geographies::Geographies;
 use crate::utils::matrix::{MappedMatrix, MappedVector};
-use crate::utils::search::InventoryItem;
+use crate::utils::search::{ElementaryItem, InventoryItem};
 
 mod build;
-mod impacts;
+mod data;
+mod geographies;
+pub(crate) mod impacts;
 mod parse;
 
+pub use data::{Exchange, ProcessData, Variable};
+
+/// Datasets and exchanges left out while loading a release.
+#[derive(Debug, Default)]
+pub struct LeftOut {
+    /// Datasets which can't be read, with the reason.
+    pub skipped: Vec<DatasetError>,
+    /// Exchanges with activities missing from the release.
+    pub unlinked: Vec<String>,
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Ecoinvent {
     version: String,
@@ -27,62 +47,93 @@ pub struct Ecoinvent {
     intervention: MappedMatrix<String, String>,
     classifications: HashMap<String, MappedMatrix<ImpactCategory, String>>,
     candidates: HashMap<String, InventoryItem>,
+    elementaries: HashMap<String, ElementaryItem>,
+    geographies: Geographies,
 }
 
 impl Ecoinvent {
     /// Save the database data in a cache at the specified `path`.
     fn cache(&self, cache: &Path) -> Result<()> {
-        let file = File::create(cache)?;
-        let writer = BufWriter::new(file);
-        bincode::serialize_into(writer, self).expect("Failed to serialize");
-        Ok(())
+        write_cache(cache, self)
     }
 
-    fn load_from_files(version: &str, path: &Path) -> Result<Self> {
-        let mut processes = parse_ecospold2(path)?;
-        let candidates = build_candidates(&mut processes, version);
+    /// Loads the release at `path`, with the parameters, exchanges and properties of its
+    /// datasets, by id.
+    fn load_from_files(
+        version: &str,
+        path: &Path,
+        skip_invalid: bool,
+    ) -> Result<(Self, HashMap<String, ProcessData>, LeftOut)> {
+        let (datasets, skipped) = parse_ecospold2(path, skip_invalid)?;
+        let data = build_data(&datasets);
+        let mut metadata = build_metadata(&datasets);
+        // Skipped datasets are named after their id, when they are valid otherwise
+        let skipped_ids: HashSet<String> = skipped
+            .iter()
+            .filter_map(|e| Some(e.path.file_stem()?.to_str()?.to_string()))
+            .collect();
+        let (processes, unlinked) = build_processes(datasets, &skipped_ids);
+        let geographies = parse_geographies(path)?;
+        let mut candidates = build_candidates(&processes, &format!("Ecoinvent_{}", version));
+        for (id, item) in candidates.iter_mut() {
+            item.metadata = metadata.remove(id).unwrap_or_default();
+        }
+        let mut elementaries = build_elementaries(&processes);
         let (technology, intervention) = build_matrices(processes)?;
+        elementaries.retain(|id, _| intervention.contains_row(id));
         upload_lcia_files()?;
         let ef31 = construct_impact_matrix(version, &intervention)?;
         let mut classifications = HashMap::new();
         classifications.insert("ef31".to_string(), ef31);
-        Ok(Ecoinvent {
+        let res = Ecoinvent {
             version: version.to_string(),
             technology,
             intervention,
             classifications,
             candidates,
-        })
+            elementaries,
+            geographies,
+        };
+        Ok((res, data, LeftOut { skipped, unlinked }))
     }
 
-    pub fn load(version: &str, path: &Path, cache: Option<&Path>) -> Result<impl Database> {
+    /// Parameters, exchanges and properties of the datasets of the release cached at
+    /// `cache`, by id, to query the properties of their flows, check their balances or
+    /// evaluate their mathematical relations again.
+    ///
+    /// They are cached apart from the database at import, so that they are only read
+    /// when needed.
+    pub fn load_process_data(cache: &Path) -> Result<HashMap<String, ProcessData>> {
+        read_cache(&data_cache(cache))
+    }
+
+    /// Loads the release at `path`, from the `cache` if it exists, and caches it otherwise.
+    ///
+    /// Invalid datasets fail the loading, unless `skip_invalid`: they are then left out,
+    /// with the exchanges of other datasets linked to them, and returned with the reason.
+    /// Exchanges with activities missing from the release are left out and returned.
+    pub fn load(
+        version: &str,
+        path: &Path,
+        cache: Option<&Path>,
+        skip_invalid: bool,
+    ) -> Result<(Self, LeftOut)> {
+        // Caches of a previous version are built again
         if let Some(cache) = cache {
-            if fs::exists(cache)? {
-                let file = File::open(cache)?;
-                let reader = BufReader::new(file);
-                let data = bincode::deserialize_from(reader)?;
-                return Ok(data);
+            if is_current(cache)? {
+                return Ok((read_cache(cache)?, LeftOut::default()));
             }
         }
-        let res = Self::load_from_files(version, path)?;
+        let (res, data, left_out) = Self::load_from_files(version, path, skip_invalid)?;
         if let Some(cache) = cache {
             res.cache(cache)?;
+            write_cache(&data_cache(cache), &data)?;
         }
-        Ok(res)
-    }
-
-    pub fn load_from_cache(version: &str, path: &Path) -> Result<impl Database> {
-        if fs::exists(path)? {
-            let file = File::open(path)?;
-            let reader = BufReader::new(file);
-            let data: Ecoinvent = bincode::deserialize_from(reader)?;
-            Ok(data)
-        } else {
-            Err(crate::errors::OdysseyErrors::NoCache(format!(
-                "Ecoinvent {} was not previously loaded",
-                version
-            )))
-        }
+        Ok((res, left_out))
+    }
+
+    pub fn load_from_cache(path: &Path) -> Result<Self> {
+        read_cache(path)
     }
 }
 impl Database for Ecoinvent {
@@ -94,6 +145,10 @@ impl Database for Ecoinvent {
         self.technology.zeros_like_cols()
     }
 
+    fn empty_inventory(&self) -> MappedVector<String> {
+        self.intervention.zeros_like_rows()
+    }
+
     fn empty_impacts(&self) -> MappedVector<ImpactCategory> {
         self.classifications.get("ef31").unwrap().zeros_like_rows()
     }
@@ -106,6 +161,30 @@ impl Database for Ecoinvent {
         self.candidates.get(id)
     }
 
+    fn list_elementaries(&self) -> Vec<&ElementaryItem> {
+        self.elementaries.values().collect()
+    }
+
+    fn find_elementary(&self, id: &str) -> Option<&ElementaryItem> {
+        self.elementaries.get(id)
+    }
+
+    fn geographies(&self) -> Option<&Geographies> {
+        Some(&self.geographies)
+    }
+
+    fn technology(&self) -> Option<&MappedMatrix<String, String>> {
+        Some(&self.technology)
+    }
+
+    fn intervention(&self) -> Option<&MappedMatrix<String, String>> {
+        Some(&self.intervention)
+    }
+
+    fn characterization(&self) -> Option<&MappedMatrix<ImpactCategory, String>> {
+        self.classifications.get("ef31")
+    }
+
     fn lci(&mut self, f: &MappedVector<String>) -> Result<MappedVector<String>> {
         // TODO: Verify columns matching in debug
         let s = self.technology.solve(f);
@@ -121,7 +200,14 @@ impl Database for Ecoinvent {
     }
 }
 
-fn upload_lcia_files() -> Result<()> {
+/// Cache of the data of the datasets, next to the `cache` of the database.
+pub fn data_cache(cache: &Path) -> PathBuf {
+    let mut path = cache.as_os_str().to_owned();
+    path.push(".data");
+    PathBuf::from(path)
+}
+
+pub(crate) fn upload_lcia_files() -> Result<()> {
     let path = DATABASES_PATH.join("ecoinvent_lcia");
     if !fs::exists(&path)? {
         Repository::clone("https://github.com/ecoinvent/lcia.git", path)?;
@@ -8,18 +8,75 @@ use uuid::Uuid;
 
 use rayon::prelude::*;
 
-use crate::errors::Result;
+use crate::errors::{DatasetError, OdysseyErrors, Result};
 
 #[derive(Debug, Deserialize)]
 pub struct Activity {
     #[serde(rename = "activityName")]
     pub activity_name: String,
+
+    /// 0 for ordinary transforming activities, 1 for markets, 10 for market groups.
+    #[serde(rename = "@specialActivityType", default)]
+    pub special_activity_type: u8,
+
+    #[serde(rename = "generalComment")]
+    pub general_comment: Option<TextAndImage>,
+}
+
+/// Text made of paragraphs, which may be interleaved with images.
+#[derive(Debug, Deserialize)]
+pub struct TextAndImage {
+    #[serde(rename = "text", default)]
+    pub texts: Vec<Text>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Text {
+    #[serde(rename = "@index", default)]
+    pub index: u32,
+
+    #[serde(rename = "$text", default)]
+    pub value: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Classification {
+    #[serde(rename = "classificationSystem")]
+    pub system: String,
+
+    #[serde(rename = "classificationValue")]
+    pub value: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Technology {
+    /// From 0 (undefined) to 5 (outdated).
+    #[serde(rename = "@technologyLevel")]
+    pub level: Option<u8>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TimePeriod {
+    #[serde(rename = "@startDate")]
+    pub start: String,
+
+    #[serde(rename = "@endDate")]
+    pub end: String,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct ActivityDescription {
     pub activity: Activity,
+
+    #[serde(rename = "classification", default)]
+    pub classifications: Vec<Classification>,
+
     pub geography: Geograpy,
+
+    pub technology: Option<Technology>,
+
+    #[serde(rename = "timePeriod")]
+    pub time_period: Option<TimePeriod>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -27,6 +84,47 @@ pub struct Geograpy {
     pub shortname: String,
 }
 
+/// Property of an exchange, per unit of its flow, e.g. its dry mass or carbon content.
+#[derive(Debug, Deserialize)]
+pub struct Property {
+    #[serde(rename = "@propertyId")]
+    pub property_id: Uuid,
+
+    #[serde(rename = "@amount")]
+    pub amount: f64,
+
+    #[serde(rename = "@variableName")]
+    pub variable: Option<String>,
+
+    #[serde(rename = "@mathematicalRelation")]
+    pub formula: Option<String>,
+
+    pub name: String,
+
+    #[serde(rename = "unitName")]
+    pub unit: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Parameter {
+    #[serde(rename = "@parameterId")]
+    pub parameter_id: Uuid,
+
+    #[serde(rename = "@amount")]
+    pub amount: f64,
+
+    #[serde(rename = "@variableName")]
+    pub variable: Option<String>,
+
+    #[serde(rename = "@mathematicalRelation")]
+    pub formula: Option<String>,
+
+    pub name: String,
+
+    #[serde(rename = "unitName")]
+    pub unit: Option<String>,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct IntermediateExchange {
     #[serde(rename = "@activityLinkId")]
@@ -45,6 +143,24 @@ pub struct IntermediateExchange {
 
     #[serde(rename = "inputGroup")]
     pub input: Option<u16>,
+
+    #[serde(rename = "@variableName")]
+    pub variable: Option<String>,
+
+    #[serde(rename = "@mathematicalRelation")]
+    pub formula: Option<String>,
+
+    #[serde(rename = "property", default)]
+    pub properties: Vec<Property>,
+
+    #[serde(rename = "classification", default)]
+    pub classifications: Vec<Classification>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Compartment {
+    pub compartment: String,
+    pub subcompartment: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -54,6 +170,25 @@ pub struct ElementaryExchange {
 
     #[serde(rename = "@amount")]
     pub amount: f64,
+
+    pub name: String,
+
+    #[serde(rename = "unitName")]
+    pub unit: String,
+
+    pub compartment: Compartment,
+
+    #[serde(rename = "inputGroup")]
+    pub input: Option<u16>,
+
+    #[serde(rename = "@variableName")]
+    pub variable: Option<String>,
+
+    #[serde(rename = "@mathematicalRelation")]
+    pub formula: Option<String>,
+
+    #[serde(rename = "property", default)]
+    pub properties: Vec<Property>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -63,6 +198,9 @@ pub struct Flows {
 
     #[serde(rename = "elementaryExchange")]
     pub elementaries: Option<Vec<ElementaryExchange>>,
+
+    #[serde(rename = "parameter", default)]
+    pub parameters: Vec<Parameter>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -81,30 +219,127 @@ pub struct EcoSpold {
 }
 
 pub fn ids_from_str(ids: &str) -> Result<(Uuid, Uuid)> {
-    let ids: Vec<&str> = ids.split("_").collect();
-    let process_id = Uuid::from_str(ids.first().unwrap())?;
-    let product_id = Uuid::from_str(ids.get(1).unwrap())?;
-    Ok((process_id, product_id))
-}
-
-
-pub fn parse_ecospold2(folder: &Path) -> Result<HashMap<String, EcoSpold>> {
-    let activities_paths = fs::read_dir(folder.join("datasets"))?;
-    let res: HashMap<String, EcoSpold> = activities_paths
-        .par_bridge()
-        .map(|activity_path| {
-            let path = activity_path.unwrap().path();
-            let (process_id, product_id) =
-                ids_from_str(path.file_stem().unwrap().to_str().unwrap()).unwrap();
-            let f = File::open(path.clone()).unwrap();
-            let reader = BufReader::new(f);
-            let process: EcoSpold = match quick_xml::de::from_reader(reader) {
-                Ok(data) => data,
-                Err(e) => panic!("Error {:?} {:?}", e, path),
-            };
-            (format!("{}_{}", process_id, product_id), process)
-        })
-        .collect();
-
-    Ok(res)
+    let (process_id, product_id) = ids.split_once('_').ok_or_else(|| {
+        OdysseyErrors::MissingId(format!(
+            "{ids:?} is not made of an activity and a product id"
+        ))
+    })?;
+    Ok((Uuid::from_str(process_id)?, Uuid::from_str(product_id)?))
+}
+
+/// Parses the `.spold` files of the `datasets` folder, by id of process.
+///
+/// Datasets which can't be read are returned with the reason, if `skip_invalid`, and
+/// fail the parsing otherwise, all at once. Other files of the folder are ignored.
+pub fn parse_ecospold2(
+    folder: &Path,
+    skip_invalid: bool,
+) -> Result<(HashMap<String, EcoSpold>, Vec<DatasetError>)> {
+    let datasets = folder.join("datasets");
+    let parsed: Vec<std::result::Result<(String, EcoSpold), DatasetError>> =
+        fs::read_dir(&datasets)?
+            .par_bridge()
+            .filter_map(|entry| {
+                let path = match entry {
+                    Ok(entry) => entry.path(),
+                    Err(e) => {
+                        return Some(Err(DatasetError {
+                            path: datasets.clone(),
+                            reason: e.to_string(),
+                        }))
+                    }
+                };
+                let spold = path
+                    .extension()
+                    .is_some_and(|e| e.eq_ignore_ascii_case("spold"));
+                spold.then(|| {
+                    parse_dataset(&path).map_err(|reason| DatasetError {
+                        path: path.clone(),
+                        reason,
+                    })
+                })
+            })
+            .collect();
+
+    let mut res = HashMap::new();
+    let mut errors = vec![];
+    for dataset in parsed {
+        match dataset {
+            Ok((id, process)) => {
+                res.insert(id, process);
+            }
+            Err(e) => errors.push(e),
+        }
+    }
+    errors.sort_by(|a, b| a.path.cmp(&b.path));
+    if !errors.is_empty() && !skip_invalid {
+        return Err(OdysseyErrors::InvalidDatasets(errors));
+    }
+    Ok((res, errors))
+}
+
+/// Id and content of the dataset at `path`, or the reason it is invalid.
+fn parse_dataset(path: &Path) -> std::result::Result<(String, EcoSpold), String> {
+    let stem = path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or_default();
+    let (process_id, product_id) = ids_from_str(stem)
+        .map_err(|_| "the file is not named after an activity and a product id".to_string())?;
+    let reader = BufReader::new(File::open(path).map_err(|e| e.to_string())?);
+    let process: EcoSpold = quick_xml::de::from_reader(reader).map_err(|e| e.to_string())?;
+    let has_product = process
+        .activity
+        .flows
+        .intermediates
+        .iter()
+        .any(|e| e.process_id.is_none() && e.product_id == product_id);
+    if !has_product {
+        return Err(format!("no reference product {product_id}"));
+    }
+    Ok((format!("{}_{}", process_id, product_id), process))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_ecospold2() {
+        let folder = std::env::temp_dir().join(format!("odyssey-ecospold2-{}", std::process::id()));
+        let datasets = folder.join("datasets");
+        fs::create_dir_all(&datasets).unwrap();
+        let dataset = r#"<ecoSpold><activityDataset>
+  <activityDescription>
+    <activity><activityName>steel production</activityName></activity>
+    <geography><shortname>GLO</shortname></geography>
+  </activityDescription>
+  <flowData>
+    <intermediateExchange intermediateExchangeId="11111111-1111-1111-1111-111111111111" amount="1">
+      <name>steel</name><unitName>kg</unitName><outputGroup>0</outputGroup>
+    </intermediateExchange>
+  </flowData>
+</activityDataset></ecoSpold>"#;
+        let valid = "22222222-2222-2222-2222-222222222222_11111111-1111-1111-1111-111111111111";
+        let other_product =
+            "33333333-3333-3333-3333-333333333333_44444444-4444-4444-4444-444444444444";
+        fs::write(datasets.join(format!("{valid}.spold")), dataset).unwrap();
+        fs::write(datasets.join(format!("{other_product}.spold")), dataset).unwrap();
+        fs::write(datasets.join("steel.spold"), dataset).unwrap();
+        let truncated = "55555555-5555-5555-5555-555555555555_66666666-6666-6666-6666-666666666666";
+        fs::write(datasets.join(format!("{truncated}.spold")), "<ecoSpold>").unwrap();
+        fs::write(datasets.join("README.txt"), "not a dataset").unwrap();
+
+        let Err(OdysseyErrors::InvalidDatasets(errors)) = parse_ecospold2(&folder, false) else {
+            panic!("invalid datasets must fail the parsing");
+        };
+        assert_eq!(errors.len(), 3);
+        assert!(errors[0].reason.contains("no reference product"));
+        assert!(errors[2].reason.contains("not named after"));
+
+        let (res, skipped) = parse_ecospold2(&folder, true).unwrap();
+        assert_eq!(res.keys().collect::<Vec<_>>(), vec![valid]);
+        assert_eq!(skipped.len(), 3);
+        fs::remove_dir_all(folder).unwrap();
+    }
 }
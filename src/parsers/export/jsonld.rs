@@ -0,0 +1,241 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashSet},
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+};
+
+use serde_json::{json, Value};
+use uuid::Uuid;
+use zip::{write::SimpleFileOptions, ZipWriter};
+
+use crate::{
+    comput::lca::{Database, Matrices},
+    errors::Result,
+    parsers::export::{col_ids, row_ids},
+};
+
+/// Writes the database as an openLCA JSON-LD zip archive at `path`, which can be
+/// imported in openLCA or back in Odyssey.
+///
+/// Each activity becomes a process producing one unit of its own product flow, linked
+/// to the processes it consumes as default providers, and each impact category of the
+/// database an LCIA category with its characterization factors. Activities with a
+/// negative production, such as waste treatments, get a waste flow as reference,
+/// treated as an input and supplied to the activities emitting the waste. Ids which
+/// are not UUIDs are replaced by UUIDs derived from them.
+pub fn export_jsonld(database: &dyn Database, path: &Path) -> Result<()> {
+    let matrices = Matrices::new(database)?;
+    let namespace = Uuid::new_v5(&Uuid::NAMESPACE_OID, database.name().as_bytes());
+    let uuid = |kind: &str, id: &str| match Uuid::parse_str(id) {
+        Ok(_) => id.to_string(),
+        Err(_) => Uuid::new_v5(&namespace, format!("{kind}|{id}").as_bytes()).to_string(),
+    };
+
+    let mut archive = Archive::default();
+    let mut units = BTreeSet::new();
+    let mut locations = BTreeSet::new();
+
+    let activities = col_ids(matrices.technology);
+    let flows = row_ids(matrices.intervention);
+    let wastes: HashSet<&String> = matrices
+        .technology
+        .triplets()
+        .filter(|(product, activity, amount)| product == activity && *amount < 0.)
+        .map(|(product, _, _)| product)
+        .collect();
+    for id in activities.iter() {
+        let item = database.find_candidate(id);
+        let name = item.map(|c| c.name.as_str()).unwrap_or(id);
+        let unit = item.map(|c| c.unit.as_str()).unwrap_or_default();
+        units.insert(unit.to_string());
+        archive.add(
+            "flows",
+            json!({
+                "@type": "Flow",
+                "@id": uuid("product", id),
+                "name": item.and_then(|c| c.alt_name.as_deref()).unwrap_or(name),
+                "flowType": if wastes.contains(id) { "WASTE_FLOW" } else { "PRODUCT_FLOW" },
+                "flowProperties": [flow_property_factor(&uuid("property", unit))],
+            }),
+        );
+    }
+    for id in flows.iter() {
+        let Some(item) = database.find_elementary(id) else {
+            continue;
+        };
+        units.insert(item.unit.clone());
+        let category = ["Elementary flows", &item.compartment]
+            .into_iter()
+            .chain(item.subcompartment.as_deref())
+            .collect::<Vec<_>>()
+            .join("/");
+        archive.add(
+            "flows",
+            json!({
+                "@type": "Flow",
+                "@id": uuid("flow", id),
+                "name": item.name,
+                "flowType": "ELEMENTARY_FLOW",
+                "category": category,
+                "flowProperties": [flow_property_factor(&uuid("property", &item.unit))],
+            }),
+        );
+    }
+
+    let mut exchanges: BTreeMap<&String, Vec<Value>> = BTreeMap::new();
+    for (product, activity, amount) in matrices.technology.triplets() {
+        let waste = wastes.contains(product);
+        let exchange = if product == activity {
+            json!({
+                "@type": "Exchange",
+                "amount": amount.abs(),
+                "isInput": waste,
+                "isQuantitativeReference": true,
+                "flow": {"@id": uuid("product", product)},
+            })
+        } else {
+            // Products are consumed as inputs (negative) and wastes sent to treatment as
+            // outputs (positive), the other way round they are avoided
+            let avoided = if waste { amount < 0. } else { amount > 0. };
+            json!({
+                "@type": "Exchange",
+                "amount": amount.abs(),
+                "isInput": !waste,
+                "isAvoidedProduct": avoided,
+                "flow": {"@id": uuid("product", product)},
+                "defaultProvider": {"@id": uuid("process", product)},
+            })
+        };
+        exchanges.entry(activity).or_default().push(exchange);
+    }
+    for (flow, activity, amount) in matrices.intervention.triplets() {
+        let input = database
+            .find_elementary(flow)
+            .is_some_and(|e| e.compartment.to_lowercase().contains("resource"));
+        exchanges.entry(activity).or_default().push(json!({
+            "@type": "Exchange",
+            "amount": amount,
+            "isInput": input,
+            "flow": {"@id": uuid("flow", flow)},
+        }));
+    }
+    for id in activities.iter() {
+        let item = database.find_candidate(id);
+        let location = item.and_then(|c| c.location.as_deref());
+        if let Some(location) = location {
+            locations.insert(location.to_string());
+        }
+        archive.add(
+            "processes",
+            json!({
+                "@type": "Process",
+                "@id": uuid("process", id),
+                "name": item.map(|c| c.name.as_str()).unwrap_or(id),
+                "processType": "UNIT_PROCESS",
+                "location": location.map(|l| json!({"@id": uuid("location", l), "name": l})),
+                "exchanges": exchanges.remove(id).unwrap_or_default(),
+            }),
+        );
+    }
+
+    for category in row_ids(matrices.characterization) {
+        let metadata = category.metadata();
+        let factors: Vec<Value> = matrices
+            .characterization
+            .triplets()
+            .filter(|(c, _, value)| *c == category && *value != 0.)
+            .map(|(_, flow, value)| {
+                json!({
+                    "@type": "ImpactFactor",
+                    "flow": {"@id": uuid("flow", flow)},
+                    "value": value,
+                })
+            })
+            .collect();
+        archive.add(
+            "lcia_categories",
+            json!({
+                "@type": "ImpactCategory",
+                "@id": uuid("category", &category.id()),
+                "name": metadata.name,
+                "refUnit": metadata.unit,
+                "impactFactors": factors,
+            }),
+        );
+    }
+
+    for unit in units.iter() {
+        let group = uuid("unit group", unit);
+        archive.add(
+            "unit_groups",
+            json!({
+                "@type": "UnitGroup",
+                "@id": group,
+                "name": format!("Units of {unit}"),
+                "units": [{
+                    "@type": "Unit",
+                    "@id": uuid("unit", unit),
+                    "name": unit,
+                    "conversionFactor": 1.0,
+                    "isRefUnit": true,
+                }],
+            }),
+        );
+        archive.add(
+            "flow_properties",
+            json!({
+                "@type": "FlowProperty",
+                "@id": uuid("property", unit),
+                "name": format!("Quantity in {unit}"),
+                "flowPropertyType": "PHYSICAL_QUANTITY",
+                "unitGroup": {"@id": group},
+            }),
+        );
+    }
+    for location in locations.iter() {
+        archive.add(
+            "locations",
+            json!({
+                "@type": "Location",
+                "@id": uuid("location", location),
+                "name": location,
+                "code": location,
+            }),
+        );
+    }
+
+    archive.write(path)
+}
+
+fn flow_property_factor(property: &str) -> Value {
+    json!({
+        "@type": "FlowPropertyFactor",
+        "conversionFactor": 1.0,
+        "isRefFlowProperty": true,
+        "flowProperty": {"@id": property},
+    })
+}
+
+/// Entities of the archive, by folder.
+#[derive(Default)]
+struct Archive {
+    entities: Vec<(&'static str, Value)>,
+}
+
+impl Archive {
+    fn add(&mut self, folder: &'static str, entity: Value) {
+        self.entities.push((folder, entity));
+    }
+
+    fn write(&self, path: &Path) -> Result<()> {
+        let mut zip = ZipWriter::new(BufWriter::new(File::create(path)?));
+        for (folder, entity) in self.entities.iter() {
+            let id = entity["@id"].as_str().unwrap_or_default();
+            zip.start_file(format!("{folder}/{id}.json"), SimpleFileOptions::default())?;
+            zip.write_all(serde_json::to_string_pretty(entity)?.as_bytes())?;
+        }
+        zip.finish()?.flush()?;
+        Ok(())
+    }
+}
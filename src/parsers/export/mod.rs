@@ -0,0 +1,281 @@
+use std::{
+    collections::HashMap,
+    fs::{self, File},
+    hash::Hash,
+    io::{BufWriter, Write},
+    path::Path,
+};
+
+use crate::{
+    comput::lca::{Database, Matrices},
+    errors::Result,
+    utils::matrix::MappedMatrix,
+};
+
+mod jsonld;
+
+pub use jsonld::export_jsonld;
+
+/// Ids of the rows of `matrix`, in order.
+pub(crate) fn row_ids<R, C>(matrix: &MappedMatrix<R, C>) -> Vec<&R>
+where
+    R: Eq + Hash + Clone,
+    C: Eq + Hash + Clone,
+{
+    (0..matrix.nrows())
+        .filter_map(|i| matrix.irow(&i))
+        .collect()
+}
+
+/// Ids of the columns of `matrix`, in order.
+pub(crate) fn col_ids<R, C>(matrix: &MappedMatrix<R, C>) -> Vec<&C>
+where
+    R: Eq + Hash + Clone,
+    C: Eq + Hash + Clone,
+{
+    (0..matrix.ncols())
+        .filter_map(|i| matrix.icol(&i))
+        .collect()
+}
+
+/// Writes the matrices of `database` in the `folder`, in the Matrix Market format,
+/// with CSV tables of the ids and descriptions of their rows and columns:
+///
+/// - `technology.mtx`: products of `products.csv` by activities of `activities.csv`
+/// - `intervention.mtx`: elementary flows of `flows.csv` by activities
+/// - `characterization.mtx`: impact categories of `categories.csv` by elementary flows
+///
+/// Indices of the matrices are the `index` columns of the tables, starting at 1.
+pub fn export_matrix_market(database: &dyn Database, folder: &Path) -> Result<()> {
+    let matrices = Matrices::new(database)?;
+    fs::create_dir_all(folder)?;
+
+    let products = row_ids(matrices.technology);
+    let mut writer = csv::Writer::from_path(folder.join("products.csv"))?;
+    writer.write_record(["index", "id", "name", "location", "unit"])?;
+    for (i, id) in products.iter().enumerate() {
+        let item = database.find_candidate(id);
+        writer.write_record([
+            &(i + 1).to_string(),
+            *id,
+            item.map(|c| c.alt_name.as_deref().unwrap_or(&c.name))
+                .unwrap_or_default(),
+            item.and_then(|c| c.location.as_deref()).unwrap_or_default(),
+            item.map(|c| c.unit.as_str()).unwrap_or_default(),
+        ])?;
+    }
+    writer.flush()?;
+
+    let activities = col_ids(matrices.technology);
+    let mut writer = csv::Writer::from_path(folder.join("activities.csv"))?;
+    writer.write_record(["index", "id", "name", "product", "location", "unit"])?;
+    for (i, id) in activities.iter().enumerate() {
+        let item = database.find_candidate(id);
+        writer.write_record([
+            &(i + 1).to_string(),
+            *id,
+            item.map(|c| c.name.as_str()).unwrap_or_default(),
+            item.and_then(|c| c.alt_name.as_deref()).unwrap_or_default(),
+            item.and_then(|c| c.location.as_deref()).unwrap_or_default(),
+            item.map(|c| c.unit.as_str()).unwrap_or_default(),
+        ])?;
+    }
+    writer.flush()?;
+
+    let flows = row_ids(matrices.intervention);
+    let mut writer = csv::Writer::from_path(folder.join("flows.csv"))?;
+    writer.write_record([
+        "index",
+        "id",
+        "name",
+        "compartment",
+        "subcompartment",
+        "unit",
+    ])?;
+    for (i, id) in flows.iter().enumerate() {
+        let item = database.find_elementary(id);
+        writer.write_record([
+            &(i + 1).to_string(),
+            *id,
+            item.map(|e| e.name.as_str()).unwrap_or_default(),
+            item.map(|e| e.compartment.as_str()).unwrap_or_default(),
+            item.and_then(|e| e.subcompartment.as_deref())
+                .unwrap_or_default(),
+            item.map(|e| e.unit.as_str()).unwrap_or_default(),
+        ])?;
+    }
+    writer.flush()?;
+
+    let categories = row_ids(matrices.characterization);
+    let mut writer = csv::Writer::from_path(folder.join("categories.csv"))?;
+    writer.write_record(["index", "id", "name", "unit"])?;
+    for (i, category) in categories.iter().enumerate() {
+        let metadata = category.metadata();
+        writer.write_record([
+            &(i + 1).to_string(),
+            &category.id(),
+            metadata.name,
+            metadata.unit,
+        ])?;
+    }
+    writer.flush()?;
+
+    write_matrix_market(
+        &folder.join("technology.mtx"),
+        matrices.technology,
+        &products,
+        &activities,
+    )?;
+    write_matrix_market(
+        &folder.join("intervention.mtx"),
+        matrices.intervention,
+        &flows,
+        &activities,
+    )?;
+    write_matrix_market(
+        &folder.join("characterization.mtx"),
+        matrices.characterization,
+        &categories,
+        &flows,
+    )
+}
+
+/// Writes the `matrix` at `path` as a Matrix Market coordinate matrix, whose rows and
+/// columns are numbered by their position in `rows` and `cols`.
+fn write_matrix_market<R, C>(
+    path: &Path,
+    matrix: &MappedMatrix<R, C>,
+    rows: &[&R],
+    cols: &[&C],
+) -> Result<()>
+where
+    R: Eq + Hash + Clone,
+    C: Eq + Hash + Clone,
+{
+    let rows: HashMap<&R, usize> = rows.iter().enumerate().map(|(i, r)| (*r, i + 1)).collect();
+    let cols: HashMap<&C, usize> = cols.iter().enumerate().map(|(i, c)| (*c, i + 1)).collect();
+    let entries: Vec<(usize, usize, f64)> = matrix
+        .triplets()
+        .filter(|(_, _, value)| *value != 0.)
+        .filter_map(|(row, col, value)| Some((*rows.get(row)?, *cols.get(col)?, value)))
+        .collect();
+    let mut writer = BufWriter::new(File::create(path)?);
+    writeln!(writer, "%%MatrixMarket matrix coordinate real general")?;
+    writeln!(writer, "{} {} {}", rows.len(), cols.len(), entries.len())?;
+    for (row, col, value) in entries {
+        writeln!(writer, "{row} {col} {value}")?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use super::*;
+    use crate::{
+        comput::impacts::{ImpactCategory, EF31},
+        parsers::{background::Background, build::Process, openlca::load_openlca},
+        utils::{matrix::MappedMatrixBuilder, search::ElementaryItem},
+    };
+
+    /// `steel` consumes 2 `coal` and sends 0.5 to `landfill`, and all three emit 1 co2.
+    fn background() -> Background {
+        let co2 = ElementaryItem {
+            id: "co2".to_string(),
+            name: "Carbon dioxide".to_string(),
+            compartment: "air".to_string(),
+            subcompartment: None,
+            unit: "kg".to_string(),
+        };
+        let process = |id: &str, intermediates: Vec<(String, f64)>| Process {
+            id: id.to_string(),
+            name: format!("{id} production"),
+            product: id.to_string(),
+            location: Some("GLO".to_string()),
+            unit: "kg".to_string(),
+            intermediates,
+            elementaries: vec![(co2.clone(), 1.)],
+        };
+        let processes = HashMap::from([
+            (
+                "steel".to_string(),
+                process(
+                    "steel",
+                    vec![
+                        ("steel".to_string(), 1.),
+                        ("coal".to_string(), -2.),
+                        ("landfill".to_string(), 0.5),
+                    ],
+                ),
+            ),
+            (
+                "landfill".to_string(),
+                process("landfill", vec![("landfill".to_string(), -1.)]),
+            ),
+            (
+                "coal".to_string(),
+                process("coal", vec![("coal".to_string(), 1.)]),
+            ),
+        ]);
+        Background::build("Custom_test", processes, |intervention, _| {
+            let mut mat = MappedMatrixBuilder::new();
+            mat.copy_rows_into_cols(intervention);
+            mat.copy_vec_into_rows(&ImpactCategory::get_empty_vector());
+            mat.add_triplet(ImpactCategory::EF31(EF31::Gwp100), "co2".to_string(), 1.);
+            Ok(mat.build())
+        })
+        .unwrap()
+    }
+
+    fn gwp(database: &mut dyn Database, id: &str) -> f64 {
+        let mut f = database.empty_reference_flow();
+        f.set(id.to_string(), 1.);
+        let h = database.lca(&f).unwrap();
+        h.values[*h.row(&ImpactCategory::EF31(EF31::Gwp100)).unwrap()]
+    }
+
+    #[test]
+    fn test_export() {
+        let mut database = background();
+        let folder = std::env::temp_dir().join(format!("odyssey-export-{}", std::process::id()));
+
+        export_matrix_market(&database, &folder).unwrap();
+        let technology = fs::read_to_string(folder.join("technology.mtx")).unwrap();
+        let mut lines = technology.lines();
+        assert_eq!(
+            lines.next(),
+            Some("%%MatrixMarket matrix coordinate real general")
+        );
+        assert_eq!(lines.next(), Some("3 3 5"));
+        let activities = fs::read_to_string(folder.join("activities.csv")).unwrap();
+        assert!(activities.contains("steel,steel production,steel,GLO,kg"));
+
+        // The JSON-LD archive is read back by the openLCA importer
+        let archive = folder.join("database.zip");
+        export_jsonld(&database, &archive).unwrap();
+        let mut zip = zip::ZipArchive::new(File::open(&archive).unwrap()).unwrap();
+        let wastes = (0..zip.len())
+            .filter(|i| {
+                let mut file = zip.by_index(*i).unwrap();
+                let mut content = String::new();
+                file.read_to_string(&mut content).unwrap();
+                file.name().starts_with("flows/") && content.contains("WASTE_FLOW")
+            })
+            .count();
+        assert_eq!(wastes, 1);
+        let (mut imported, unlinked) = load_openlca("OpenLca_test", &archive, None).unwrap();
+        assert!(unlinked.is_empty());
+        let steel = imported
+            .list_candidates()
+            .into_iter()
+            .find(|c| c.name == "steel production")
+            .unwrap()
+            .id
+            .clone();
+        assert!((gwp(&mut database, "steel") - 3.5).abs() < 1e-9);
+        assert!((gwp(&mut imported, &steel) - 3.5).abs() < 1e-9);
+        fs::remove_dir_all(folder).unwrap();
+    }
+}
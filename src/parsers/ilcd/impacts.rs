@@ -0,0 +1,124 @@
+use std::collections::HashMap;
+
+use crate::{
+    comput::impacts::ImpactCategory,
+    errors::{OdysseyErrors, Result},
+    parsers::{
+        ilcd::parse::{english, LciaMethodDataSet},
+        impacts::ef31::category_from_name,
+    },
+    utils::matrix::{MappedMatrix, MappedMatrixBuilder},
+};
+
+/// Characterization matrix of the EF v3.1 method for the elementary flows of the
+/// `intervention` matrix, from the LCIA method datasets. Methods of other names and
+/// regionalized factors are left out.
+///
+/// Only the latest version of a method dataset repeated in several reference packages
+/// (e.g. EF 3.0 and EF 3.1) is used, and distinct methods of the same name (e.g. those
+/// of another publisher) fail the import.
+pub fn construct_impact_matrix(
+    intervention: &MappedMatrix<String, String>,
+    methods: &[LciaMethodDataSet],
+) -> Result<MappedMatrix<ImpactCategory, String>> {
+    let mut latest: HashMap<&str, &LciaMethodDataSet> = HashMap::new();
+    for method in methods {
+        let uuid = method.information.data_set.uuid.trim();
+        match latest.get(uuid) {
+            Some(other) if other.version() >= method.version() => {}
+            _ => {
+                latest.insert(uuid, method);
+            }
+        }
+    }
+    let mut latest: Vec<(&str, &LciaMethodDataSet)> = latest.into_iter().collect();
+    latest.sort_by_key(|(uuid, _)| *uuid);
+
+    let mut mat = MappedMatrixBuilder::new();
+    mat.copy_rows_into_cols(intervention);
+    mat.copy_vec_into_rows(&ImpactCategory::get_empty_vector());
+    let mut uuids: HashMap<ImpactCategory, &str> = HashMap::new();
+    for (uuid, method) in latest {
+        let Some(category) =
+            english(&method.information.data_set.name).and_then(category_from_name)
+        else {
+            continue;
+        };
+        if let Some(other) = uuids.insert(category.clone(), uuid) {
+            return Err(OdysseyErrors::InvalidDatabase(format!(
+                "LCIA methods {} and {} both match {}, keep only the EF v3.1 reference package",
+                other,
+                uuid,
+                category.metadata().name
+            )));
+        }
+        for factor in method.factors.iter().flat_map(|f| f.factors.iter()) {
+            let regionalized = factor
+                .location
+                .as_deref()
+                .is_some_and(|l| !l.trim().is_empty() && l.trim() != "GLO");
+            if !regionalized && intervention.contains_row(&factor.flow.id) {
+                mat.add_triplet(category.clone(), factor.flow.id.clone(), factor.mean_value);
+            }
+        }
+    }
+    Ok(mat.build())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn method(uuid: &str, version: &str, value: f64) -> LciaMethodDataSet {
+        quick_xml::de::from_str(&format!(
+            r#"<LCIAMethodDataSet xmlns:common="http://lca.jrc.it/ILCD/Common">
+  <LCIAMethodInformation>
+    <dataSetInformation>
+      <common:UUID>{uuid}</common:UUID>
+      <common:name xml:lang="en">Climate change</common:name>
+    </dataSetInformation>
+  </LCIAMethodInformation>
+  <characterisationFactors>
+    <factor>
+      <referenceToFlowDataSet refObjectId="co2"/>
+      <meanValue>{value}</meanValue>
+    </factor>
+  </characterisationFactors>
+  <administrativeInformation>
+    <publicationAndOwnership>
+      <common:dataSetVersion>{version}</common:dataSetVersion>
+    </publicationAndOwnership>
+  </administrativeInformation>
+</LCIAMethodDataSet>"#
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_repeated_methods() {
+        let mut intervention = MappedMatrixBuilder::new();
+        intervention.add_row("co2".to_string());
+        intervention.add_col("p".to_string());
+        let intervention = intervention.build();
+        let gwp = category_from_name("Climate change").unwrap();
+
+        // The method of both reference packages is only counted once, in its latest version
+        let methods = [
+            method("gwp", "03.01.000", 1.),
+            method("gwp", "03.00.000", 2.),
+        ];
+        let characterization = construct_impact_matrix(&intervention, &methods).unwrap();
+        let values: Vec<f64> = characterization
+            .triplets()
+            .filter(|(c, _, _)| **c == gwp)
+            .map(|(_, _, value)| value)
+            .collect();
+        assert_eq!(values, vec![1.]);
+
+        let methods = [
+            method("gwp", "03.01.000", 1.),
+            method("other", "01.00.000", 2.),
+        ];
+        assert!(construct_impact_matrix(&intervention, &methods).is_err());
+    }
+}
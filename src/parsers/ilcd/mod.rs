@@ -0,0 +1,286 @@
+use std::{
+    collections::{HashMap, HashSet},
+    path::Path,
+};
+
+use crate::{
+    errors::{OdysseyErrors, Result},
+    parsers::{
+        background::Background,
+        build::Process,
+        ilcd::{
+            impacts::construct_impact_matrix,
+            parse::{english, parse_ilcd, Archive, FlowDataSet},
+        },
+    },
+    utils::{cache::is_current, search::ElementaryItem},
+};
+
+mod impacts;
+mod parse;
+
+/// Loads the ILCD zip archive at `path`, or the archives of the folder at `path`, as the
+/// database `name`, from the `cache` if it exists, and caches it otherwise.
+///
+/// The EF v3.1 characterization factors are read from the LCIA method datasets of the
+/// archives, such as the ones of the EF reference package.
+pub fn load_ilcd(name: &str, path: &Path, cache: Option<&Path>) -> Result<Background> {
+    if let Some(cache) = cache {
+        if is_current(cache)? {
+            return Background::load_from_cache(cache);
+        }
+    }
+    let archive = parse_ilcd(path)?;
+    if archive.methods.is_empty() {
+        return Err(OdysseyErrors::InvalidDatabase(format!(
+            "no LCIA method in {}, add the archive of the EF v3.1 reference package",
+            path.display()
+        )));
+    }
+    let processes = build_processes(&archive);
+    let res = Background::build(name, processes, |intervention, _| {
+        construct_impact_matrix(intervention, &archive.methods)
+    })?;
+    if let Some(cache) = cache {
+        res.cache(cache)?;
+    }
+    Ok(res)
+}
+
+/// Unit of the reference flow property of each flow.
+fn flow_units(archive: &Archive) -> HashMap<&str, &str> {
+    let unit_groups: HashMap<&str, &str> = archive
+        .unit_groups
+        .iter()
+        .filter_map(|g| Some((g.information.data_set.uuid.trim(), g.reference_unit()?)))
+        .collect();
+    let properties: HashMap<&str, &str> = archive
+        .flow_properties
+        .iter()
+        .filter_map(|p| {
+            let unit_group = p.information.reference.unit_group.id.as_str();
+            Some((
+                p.information.data_set.uuid.trim(),
+                *unit_groups.get(unit_group)?,
+            ))
+        })
+        .collect();
+    archive
+        .flows
+        .iter()
+        .filter_map(|f| {
+            let unit = properties.get(f.reference_property()?)?;
+            Some((f.information.data_set.uuid.trim(), *unit))
+        })
+        .collect()
+}
+
+fn flow_name(flow: &FlowDataSet) -> String {
+    english(&flow.information.data_set.name.base_name)
+        .unwrap_or_default()
+        .to_string()
+}
+
+/// Converts the process datasets into processes, keeping their ids.
+///
+/// ILCD exchanges refer to flows rather than processes: product and waste flows are
+/// provided by the first process having them as reference flow, and exchanges of flows
+/// no process provides are left out. Amounts are expressed in the reference unit of the
+/// reference flow property of the flows.
+fn build_processes(archive: &Archive) -> HashMap<String, Process> {
+    let units = flow_units(archive);
+    let flows: HashMap<&str, &FlowDataSet> = archive
+        .flows
+        .iter()
+        .map(|f| (f.information.data_set.uuid.trim(), f))
+        .collect();
+
+    let mut datasets: Vec<_> = archive.processes.iter().collect();
+    datasets.sort_by_key(|p| p.information.data_set.uuid.trim());
+    let mut providers: HashMap<&str, &str> = HashMap::new();
+    for dataset in datasets.iter() {
+        let references: HashSet<usize> = dataset
+            .information
+            .reference
+            .flows
+            .iter()
+            .copied()
+            .collect();
+        for exchange in dataset.exchanges.exchanges.iter() {
+            if references.contains(&exchange.internal_id) {
+                providers
+                    .entry(exchange.flow.id.as_str())
+                    .or_insert(dataset.information.data_set.uuid.trim());
+            }
+        }
+    }
+
+    let mut res = HashMap::new();
+    for dataset in datasets {
+        let id = dataset.information.data_set.uuid.trim().to_string();
+        let references = &dataset.information.reference.flows;
+        let mut product = None;
+        let mut intermediates = vec![];
+        let mut elementaries = vec![];
+        for exchange in dataset.exchanges.exchanges.iter() {
+            let Some(flow) = flows.get(exchange.flow.id.as_str()) else {
+                continue;
+            };
+            let unit = units
+                .get(exchange.flow.id.as_str())
+                .copied()
+                .unwrap_or_default();
+            if flow.is_elementary() {
+                let categories = flow.categories();
+                let (compartment, subcompartment) = match categories.as_slice() {
+                    [] => ("", None),
+                    [compartment] => (*compartment, None),
+                    [_, compartment] => (*compartment, None),
+                    [_, compartment, subcompartment, ..] => (*compartment, Some(*subcompartment)),
+                };
+                let item = ElementaryItem {
+                    id: exchange.flow.id.clone(),
+                    name: flow_name(flow),
+                    compartment: compartment.to_string(),
+                    subcompartment: subcompartment.map(|s| s.to_string()),
+                    unit: unit.to_string(),
+                };
+                elementaries.push((item, exchange.amount()));
+            } else if references.contains(&exchange.internal_id) {
+                product.get_or_insert((flow_name(flow), unit.to_string()));
+                intermediates.push((id.clone(), exchange.signed_amount()));
+            } else if let Some(provider) = providers.get(exchange.flow.id.as_str()) {
+                intermediates.push((provider.to_string(), exchange.signed_amount()));
+            }
+        }
+        let (product, unit) = product.unwrap_or_default();
+        let process = Process {
+            id: id.clone(),
+            name: dataset.information.data_set.name.full_name(),
+            product,
+            location: dataset
+                .information
+                .geography
+                .as_ref()
+                .and_then(|g| g.location.as_ref())
+                .and_then(|l| l.location.clone()),
+            unit,
+            intermediates,
+            elementaries,
+        };
+        res.insert(id, process);
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn process(uuid: &str, name: &str, exchanges: &str) -> String {
+        format!(
+            r#"<processDataSet xmlns="http://lca.jrc.it/ILCD/Process" xmlns:common="http://lca.jrc.it/ILCD/Common">
+  <processInformation>
+    <dataSetInformation>
+      <common:UUID>{uuid}</common:UUID>
+      <name><baseName xml:lang="de">Prozess</baseName><baseName xml:lang="en">{name}</baseName><treatmentStandardsRoutes xml:lang="en">mix</treatmentStandardsRoutes></name>
+    </dataSetInformation>
+    <quantitativeReference type="Reference flow(s)"><referenceToReferenceFlow>0</referenceToReferenceFlow></quantitativeReference>
+    <geography><locationOfOperationSupplyOrProduction location="DE"/></geography>
+  </processInformation>
+  <exchanges>{exchanges}</exchanges>
+</processDataSet>"#
+        )
+    }
+
+    fn exchange(id: usize, flow: &str, direction: &str, amount: f64) -> String {
+        format!(
+            r#"<exchange dataSetInternalID="{id}"><referenceToFlowDataSet type="flow data set" refObjectId="{flow}"/><exchangeDirection>{direction}</exchangeDirection><meanAmount>{amount}</meanAmount><resultingAmount>{amount}</resultingAmount></exchange>"#
+        )
+    }
+
+    fn flow(uuid: &str, name: &str, kind: &str, categories: &str) -> String {
+        format!(
+            r#"<flowDataSet xmlns="http://lca.jrc.it/ILCD/Flow" xmlns:common="http://lca.jrc.it/ILCD/Common">
+  <flowInformation>
+    <dataSetInformation>
+      <common:UUID>{uuid}</common:UUID>
+      <name><baseName xml:lang="en">{name}</baseName></name>
+      <classificationInformation><common:elementaryFlowCategorization>{categories}</common:elementaryFlowCategorization></classificationInformation>
+    </dataSetInformation>
+    <quantitativeReference><referenceToReferenceFlowProperty>0</referenceToReferenceFlowProperty></quantitativeReference>
+  </flowInformation>
+  <modellingAndValidation><LCIMethod><typeOfDataSet>{kind}</typeOfDataSet></LCIMethod></modellingAndValidation>
+  <flowProperties><flowProperty dataSetInternalID="0"><referenceToFlowPropertyDataSet refObjectId="mass"/><meanValue>1.0</meanValue></flowProperty></flowProperties>
+</flowDataSet>"#
+        )
+    }
+
+    #[test]
+    fn test_build_processes() {
+        let property = r#"<flowPropertyDataSet xmlns:common="http://lca.jrc.it/ILCD/Common">
+  <flowPropertiesInformation>
+    <dataSetInformation><common:UUID>mass</common:UUID><common:name xml:lang="en">Mass</common:name></dataSetInformation>
+    <quantitativeReference><referenceToReferenceUnitGroup refObjectId="units-of-mass"/></quantitativeReference>
+  </flowPropertiesInformation>
+</flowPropertyDataSet>"#;
+        let unit_group = r#"<unitGroupDataSet xmlns:common="http://lca.jrc.it/ILCD/Common">
+  <unitGroupInformation>
+    <dataSetInformation><common:UUID>units-of-mass</common:UUID></dataSetInformation>
+    <quantitativeReference><referenceToReferenceUnit>1</referenceToReferenceUnit></quantitativeReference>
+  </unitGroupInformation>
+  <units><unit dataSetInternalID="0"><name>g</name><meanValue>0.001</meanValue></unit><unit dataSetInternalID="1"><name>kg</name><meanValue>1.0</meanValue></unit></units>
+</unitGroupDataSet>"#;
+        let steel = process(
+            "p-steel",
+            "steel",
+            &[
+                exchange(0, "f-steel", "Output", 1.),
+                exchange(1, "f-coal", "Input", 2.),
+                exchange(2, "f-co2", "Output", 3.),
+                exchange(3, "f-unknown", "Input", 4.),
+            ]
+            .concat(),
+        );
+        let coal = process("p-coal", "coal", &exchange(0, "f-coal", "Output", 1.));
+        let categories = r#"<common:category level="0">Emissions</common:category><common:category level="2">Emissions to air, unspecified</common:category><common:category level="1">Emissions to air</common:category>"#;
+        let archive = Archive {
+            processes: vec![
+                quick_xml::de::from_str(&steel).unwrap(),
+                quick_xml::de::from_str(&coal).unwrap(),
+            ],
+            flows: vec![
+                quick_xml::de::from_str(&flow("f-steel", "steel", "Product flow", "")).unwrap(),
+                quick_xml::de::from_str(&flow("f-coal", "hard coal", "Product flow", "")).unwrap(),
+                quick_xml::de::from_str(&flow(
+                    "f-co2",
+                    "carbon dioxide",
+                    "Elementary flow",
+                    categories,
+                ))
+                .unwrap(),
+            ],
+            flow_properties: vec![quick_xml::de::from_str(property).unwrap()],
+            unit_groups: vec![quick_xml::de::from_str(unit_group).unwrap()],
+            methods: vec![],
+        };
+
+        let processes = build_processes(&archive);
+        let steel = &processes["p-steel"];
+        assert_eq!(steel.name, "steel; mix");
+        assert_eq!(steel.product, "steel");
+        assert_eq!(steel.unit, "kg");
+        assert_eq!(steel.location.as_deref(), Some("DE"));
+        assert_eq!(
+            steel.intermediates,
+            vec![("p-steel".to_string(), 1.), ("p-coal".to_string(), -2.)]
+        );
+        let (co2, amount) = &steel.elementaries[0];
+        assert_eq!(co2.compartment, "Emissions to air");
+        assert_eq!(
+            co2.subcompartment.as_deref(),
+            Some("Emissions to air, unspecified")
+        );
+        assert_eq!(*amount, 3.);
+    }
+}
@@ -0,0 +1,470 @@
+use std::{
+    fs::{self, File},
+    io::{BufReader, Read, Seek},
+    path::{Path, PathBuf},
+};
+
+use serde::Deserialize;
+use zip::ZipArchive;
+
+use crate::errors::Result;
+
+/// Text in a given language.
+#[derive(Debug, Deserialize)]
+pub struct LangString {
+    #[serde(rename = "@lang")]
+    pub lang: Option<String>,
+
+    #[serde(rename = "$text", default)]
+    pub text: String,
+}
+
+/// English text of a multilingual field, or the first one if there is none.
+pub fn english(values: &[LangString]) -> Option<&str> {
+    values
+        .iter()
+        .find(|v| v.lang.as_deref() == Some("en"))
+        .or(values.first())
+        .map(|v| v.text.trim())
+}
+
+/// Reference to another dataset.
+#[derive(Debug, Deserialize)]
+pub struct Reference {
+    #[serde(rename = "@refObjectId")]
+    pub id: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ProcessName {
+    #[serde(rename = "baseName", default)]
+    pub base_name: Vec<LangString>,
+
+    #[serde(rename = "treatmentStandardsRoutes", default)]
+    pub treatment: Vec<LangString>,
+
+    #[serde(rename = "mixAndLocationTypes", default)]
+    pub mix: Vec<LangString>,
+
+    #[serde(rename = "functionalUnitFlowProperties", default)]
+    pub properties: Vec<LangString>,
+}
+
+impl ProcessName {
+    /// Parts of the name joined as in ILCD tools.
+    pub fn full_name(&self) -> String {
+        [
+            &self.base_name,
+            &self.treatment,
+            &self.mix,
+            &self.properties,
+        ]
+        .into_iter()
+        .filter_map(|part| english(part))
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("; ")
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ProcessDataSetInformation {
+    #[serde(rename = "UUID")]
+    pub uuid: String,
+
+    pub name: ProcessName,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ProcessQuantitativeReference {
+    #[serde(rename = "referenceToReferenceFlow", default)]
+    pub flows: Vec<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Location {
+    #[serde(rename = "@location")]
+    pub location: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ProcessGeography {
+    #[serde(rename = "locationOfOperationSupplyOrProduction")]
+    pub location: Option<Location>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ProcessInformation {
+    #[serde(rename = "dataSetInformation")]
+    pub data_set: ProcessDataSetInformation,
+
+    #[serde(rename = "quantitativeReference")]
+    pub reference: ProcessQuantitativeReference,
+
+    pub geography: Option<ProcessGeography>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Exchange {
+    #[serde(rename = "@dataSetInternalID")]
+    pub internal_id: usize,
+
+    #[serde(rename = "referenceToFlowDataSet")]
+    pub flow: Reference,
+
+    #[serde(rename = "exchangeDirection")]
+    pub direction: String,
+
+    #[serde(rename = "meanAmount")]
+    pub mean_amount: f64,
+
+    #[serde(rename = "resultingAmount")]
+    pub resulting_amount: Option<f64>,
+}
+
+impl Exchange {
+    /// Amount in the reference flow property of the flow.
+    pub fn amount(&self) -> f64 {
+        self.resulting_amount.unwrap_or(self.mean_amount)
+    }
+
+    /// Amount of the exchange, inputs being negative.
+    pub fn signed_amount(&self) -> f64 {
+        if self.direction.eq_ignore_ascii_case("input") {
+            -self.amount()
+        } else {
+            self.amount()
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Exchanges {
+    #[serde(rename = "exchange", default)]
+    pub exchanges: Vec<Exchange>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ProcessDataSet {
+    #[serde(rename = "processInformation")]
+    pub information: ProcessInformation,
+
+    pub exchanges: Exchanges,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Category {
+    #[serde(rename = "@level")]
+    pub level: usize,
+
+    #[serde(rename = "$text", default)]
+    pub name: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Categorization {
+    #[serde(rename = "category", default)]
+    pub categories: Vec<Category>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ClassificationInformation {
+    #[serde(rename = "elementaryFlowCategorization")]
+    pub categorization: Option<Categorization>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FlowName {
+    #[serde(rename = "baseName", default)]
+    pub base_name: Vec<LangString>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FlowDataSetInformation {
+    #[serde(rename = "UUID")]
+    pub uuid: String,
+
+    pub name: FlowName,
+
+    #[serde(rename = "classificationInformation")]
+    pub classification: Option<ClassificationInformation>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FlowQuantitativeReference {
+    #[serde(rename = "referenceToReferenceFlowProperty")]
+    pub property: usize,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FlowInformation {
+    #[serde(rename = "dataSetInformation")]
+    pub data_set: FlowDataSetInformation,
+
+    #[serde(rename = "quantitativeReference")]
+    pub reference: FlowQuantitativeReference,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LciMethod {
+    #[serde(rename = "typeOfDataSet")]
+    pub kind: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FlowModelling {
+    #[serde(rename = "LCIMethod")]
+    pub method: Option<LciMethod>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FlowProperty {
+    #[serde(rename = "@dataSetInternalID")]
+    pub internal_id: usize,
+
+    #[serde(rename = "referenceToFlowPropertyDataSet")]
+    pub property: Reference,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FlowProperties {
+    #[serde(rename = "flowProperty", default)]
+    pub properties: Vec<FlowProperty>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FlowDataSet {
+    #[serde(rename = "flowInformation")]
+    pub information: FlowInformation,
+
+    #[serde(rename = "modellingAndValidation")]
+    pub modelling: Option<FlowModelling>,
+
+    #[serde(rename = "flowProperties")]
+    pub properties: FlowProperties,
+}
+
+impl FlowDataSet {
+    pub fn is_elementary(&self) -> bool {
+        self.modelling
+            .as_ref()
+            .and_then(|m| m.method.as_ref())
+            .is_some_and(|m| m.kind.eq_ignore_ascii_case("elementary flow"))
+    }
+
+    /// Flow property the amounts of the flow are expressed in.
+    pub fn reference_property(&self) -> Option<&str> {
+        self.properties
+            .properties
+            .iter()
+            .find(|p| p.internal_id == self.information.reference.property)
+            .map(|p| p.property.id.as_str())
+    }
+
+    /// Categories of the elementary flow, from the most general one.
+    pub fn categories(&self) -> Vec<&str> {
+        let mut categories: Vec<&Category> = self
+            .information
+            .data_set
+            .classification
+            .iter()
+            .flat_map(|c| c.categorization.iter())
+            .flat_map(|c| c.categories.iter())
+            .collect();
+        categories.sort_by_key(|c| c.level);
+        categories.into_iter().map(|c| c.name.trim()).collect()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DataSetInformation {
+    #[serde(rename = "UUID")]
+    pub uuid: String,
+
+    #[serde(rename = "name", default)]
+    pub name: Vec<LangString>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FlowPropertyQuantitativeReference {
+    #[serde(rename = "referenceToReferenceUnitGroup")]
+    pub unit_group: Reference,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FlowPropertiesInformation {
+    #[serde(rename = "dataSetInformation")]
+    pub data_set: DataSetInformation,
+
+    #[serde(rename = "quantitativeReference")]
+    pub reference: FlowPropertyQuantitativeReference,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FlowPropertyDataSet {
+    #[serde(rename = "flowPropertiesInformation")]
+    pub information: FlowPropertiesInformation,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UnitGroupQuantitativeReference {
+    #[serde(rename = "referenceToReferenceUnit")]
+    pub unit: usize,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UnitGroupInformation {
+    #[serde(rename = "dataSetInformation")]
+    pub data_set: DataSetInformation,
+
+    #[serde(rename = "quantitativeReference")]
+    pub reference: UnitGroupQuantitativeReference,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Unit {
+    #[serde(rename = "@dataSetInternalID")]
+    pub internal_id: usize,
+
+    pub name: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Units {
+    #[serde(rename = "unit", default)]
+    pub units: Vec<Unit>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UnitGroupDataSet {
+    #[serde(rename = "unitGroupInformation")]
+    pub information: UnitGroupInformation,
+
+    pub units: Units,
+}
+
+impl UnitGroupDataSet {
+    pub fn reference_unit(&self) -> Option<&str> {
+        self.units
+            .units
+            .iter()
+            .find(|u| u.internal_id == self.information.reference.unit)
+            .map(|u| u.name.trim())
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LciaMethodInformation {
+    #[serde(rename = "dataSetInformation")]
+    pub data_set: DataSetInformation,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Factor {
+    #[serde(rename = "referenceToFlowDataSet")]
+    pub flow: Reference,
+
+    #[serde(rename = "meanValue")]
+    pub mean_value: f64,
+
+    pub location: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Factors {
+    #[serde(rename = "factor", default)]
+    pub factors: Vec<Factor>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PublicationAndOwnership {
+    #[serde(rename = "dataSetVersion")]
+    pub version: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AdministrativeInformation {
+    #[serde(rename = "publicationAndOwnership")]
+    pub publication: Option<PublicationAndOwnership>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LciaMethodDataSet {
+    #[serde(rename = "LCIAMethodInformation")]
+    pub information: LciaMethodInformation,
+
+    #[serde(rename = "administrativeInformation")]
+    pub administrative: Option<AdministrativeInformation>,
+
+    #[serde(rename = "characterisationFactors")]
+    pub factors: Option<Factors>,
+}
+
+impl LciaMethodDataSet {
+    /// Numbers of the version of the dataset, e.g. `[3, 1, 0]` for `03.01.000`.
+    pub fn version(&self) -> Vec<u32> {
+        self.administrative
+            .as_ref()
+            .and_then(|a| a.publication.as_ref())
+            .and_then(|p| p.version.as_deref())
+            .map(|v| v.trim().split('.').filter_map(|n| n.parse().ok()).collect())
+            .unwrap_or_default()
+    }
+}
+
+/// Datasets of ILCD archives.
+#[derive(Debug, Default)]
+pub struct Archive {
+    pub processes: Vec<ProcessDataSet>,
+    pub flows: Vec<FlowDataSet>,
+    pub flow_properties: Vec<FlowPropertyDataSet>,
+    pub unit_groups: Vec<UnitGroupDataSet>,
+    pub methods: Vec<LciaMethodDataSet>,
+}
+
+impl Archive {
+    /// Adds the datasets of the zip archive read by `reader`, by folder of the archive.
+    fn read(&mut self, reader: impl Read + Seek) -> Result<()> {
+        let mut zip = ZipArchive::new(reader)?;
+        for i in 0..zip.len() {
+            let file = zip.by_index(i)?;
+            let name = file.name().to_lowercase();
+            if !name.ends_with(".xml") {
+                continue;
+            }
+            let folder = name.rsplit('/').nth(1).unwrap_or_default().to_string();
+            let reader = BufReader::new(file);
+            match folder.as_str() {
+                "processes" => self.processes.push(quick_xml::de::from_reader(reader)?),
+                "flows" => self.flows.push(quick_xml::de::from_reader(reader)?),
+                "flowproperties" => self
+                    .flow_properties
+                    .push(quick_xml::de::from_reader(reader)?),
+                "unitgroups" => self.unit_groups.push(quick_xml::de::from_reader(reader)?),
+                "lciamethods" => self.methods.push(quick_xml::de::from_reader(reader)?),
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Datasets of the ILCD zip archive at `path`, or of every zip archive of the folder at
+/// `path`, so that datasets can be completed by reference packages.
+pub fn parse_ilcd(path: &Path) -> Result<Archive> {
+    let archives: Vec<PathBuf> = if path.is_dir() {
+        let mut archives = fs::read_dir(path)?
+            .map(|entry| Ok(entry?.path()))
+            .collect::<Result<Vec<_>>>()?;
+        archives.retain(|p| p.extension().is_some_and(|e| e.eq_ignore_ascii_case("zip")));
+        archives.sort();
+        archives
+    } else {
+        vec![path.to_path_buf()]
+    };
+    let mut res = Archive::default();
+    for archive in archives {
+        res.read(BufReader::new(File::open(archive)?))?;
+    }
+    Ok(res)
+}
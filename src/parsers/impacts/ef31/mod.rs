@@ -65,3 +65,114 @@ impl EF31Impacts {
         a.add_triplet(ImpactCategory::EF31(EF31::WaterUse), col.clone(), self.water_use.unwrap_or(0.));
     }
 }
+
+/// Names of the impact categories of the EF v3.1 method, as in the EF reference package
+/// and openLCA, without punctuation.
+const METHODS: [(&str, EF31); 25] = [
+    ("climatechange", EF31::Gwp100),
+    ("climatechangebiogenic", EF31::BiogenicGwp100),
+    ("climatechangefossil", EF31::FossilGwp100),
+    (
+        "climatechangelanduseandlandusechange",
+        EF31::ClimateChangeLandUse,
+    ),
+    ("acidification", EF31::Acidification),
+    ("particulatematter", EF31::ParticulMatter),
+    ("ecotoxicityfreshwater", EF31::EcotoxicityFreshwater),
+    (
+        "ecotoxicityfreshwaterinorganics",
+        EF31::EcotoxicityFreshwaterInorganics,
+    ),
+    (
+        "ecotoxicityfreshwaterorganics",
+        EF31::EcotoxicityFreshwaterOrganics,
+    ),
+    ("eutrophicationmarine", EF31::EutrophicationMarine),
+    ("eutrophicationfreshwater", EF31::EutrophicationFreshwater),
+    ("eutrophicationterrestrial", EF31::EutrophicationTerrestrial),
+    ("humantoxicitycancer", EF31::HumanToxicityCarcinogenic),
+    (
+        "humantoxicitycancerinorganics",
+        EF31::HumanToxicityCarcinogenicInorganics,
+    ),
+    (
+        "humantoxicitycancerorganics",
+        EF31::HumanToxicityCarcinogenicOrganics,
+    ),
+    ("humantoxicitynoncancer", EF31::HumanToxicityNonCacrinogenic),
+    (
+        "humantoxicitynoncancerinorganics",
+        EF31::HumanToxicityNonCacinogenicInorganics,
+    ),
+    (
+        "humantoxicitynoncancerorganics",
+        EF31::HumanToxicityNonCacinogenicOrganics,
+    ),
+    ("ionisingradiationhumanhealth", EF31::IonisingRadiation),
+    ("landuse", EF31::LandUse),
+    ("ozonedepletion", EF31::OzoneDepletion),
+    (
+        "photochemicalozoneformationhumanhealth",
+        EF31::PhotochemicalOxidant,
+    ),
+    ("resourceusefossils", EF31::EnergyResourcesNonRenewable),
+    (
+        "resourceusemineralsandmetals",
+        EF31::EnergyResourcesMetalsMinerals,
+    ),
+    ("wateruse", EF31::WaterUse),
+];
+
+/// Impact category of the EF v3.1 method named `name`.
+pub fn category_from_name(name: &str) -> Option<ImpactCategory> {
+    let name: String = name
+        .chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(|c| c.to_lowercase())
+        .collect();
+    METHODS
+        .iter()
+        .find(|(method, _)| *method == name)
+        .map(|(_, category)| ImpactCategory::EF31(category.clone()))
+}
+
+/// Test weither `name` is the name of the EF v3.1 method, e.g. `EF v3.1` or
+/// `Environmental Footprint 3.1`, and not of one of its variants.
+pub fn is_method_name(name: &str) -> bool {
+    let name: String = name
+        .chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(|c| c.to_lowercase())
+        .collect();
+    [
+        "ef31",
+        "efv31",
+        "environmentalfootprint31",
+        "environmentalfootprintv31",
+    ]
+    .contains(&name.as_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_category_from_name() {
+        assert_eq!(
+            category_from_name("Climate change"),
+            Some(ImpactCategory::EF31(EF31::Gwp100))
+        );
+        assert_eq!(
+            category_from_name("Climate change-Fossil"),
+            Some(ImpactCategory::EF31(EF31::FossilGwp100))
+        );
+        assert_eq!(
+            category_from_name("Human toxicity, non-cancer - organics"),
+            Some(ImpactCategory::EF31(
+                EF31::HumanToxicityNonCacinogenicOrganics
+            ))
+        );
+        assert_eq!(category_from_name("Resource use, fossils (ADP)"), None);
+    }
+}
@@ -1,20 +1,46 @@
+pub mod background;
+pub mod build;
+pub mod custom;
+pub mod ecospold1;
 pub mod ecospold2;
+pub mod export;
+pub mod ilcd;
 pub mod impacts;
+pub mod openlca;
+pub mod project;
+pub mod simapro;
 
 use crate::{
-    comput::lca::Database,
+    comput::{lca::Database, linked::LinkedDatabase},
     errors::{OdysseyErrors, Result},
-    parsers::ecospold2::Ecoinvent,
+    parsers::{background::Background, ecospold2::Ecoinvent},
     utils::constants::DATABASES_PATH,
 };
 
+/// Loads the database `name` of `version` from its cache. A database linked to others
+/// is combined with them into a [LinkedDatabase].
 pub fn load_database(name: &str, version: &str) -> Result<Box<dyn Database>> {
+    let database = load_cached_database(name, version)?;
+    if database.links().is_empty() {
+        return Ok(database);
+    }
+    let database_name = format!("{}_{}", name, version);
+    Ok(Box::new(LinkedDatabase::new(
+        &database_name,
+        database,
+        load_cached_database,
+    )?))
+}
+
+fn load_cached_database(name: &str, version: &str) -> Result<Box<dyn Database>> {
     let database_name = format!("{}_{}", name, version);
     match name.to_lowercase().as_str() {
         "ecoinvent" => Ok(Box::new(Ecoinvent::load_from_cache(
-            version,
             &DATABASES_PATH.join(database_name),
         )?)),
+        "custom" | "ecospold1" | "ilcd" | "openlca" | "simapro" => Ok(Box::new(
+            Background::load_from_cache(&DATABASES_PATH.join(database_name))?,
+        )),
         _ => Err(OdysseyErrors::MissingDatabase("haha".to_string())),
     }
 }
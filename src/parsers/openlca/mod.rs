@@ -0,0 +1,452 @@
+use std::{
+    collections::{HashMap, HashSet},
+    path::Path,
+};
+
+use serde_json::Value;
+
+use crate::{
+    comput::impacts::ImpactCategory,
+    errors::{OdysseyErrors, Result},
+    parsers::{
+        background::Background,
+        build::Process,
+        impacts::ef31::{category_from_name, is_method_name},
+        openlca::parse::{parse_openlca, Archive, Category, Flow, ImpactMethod, Ref},
+    },
+    utils::{
+        cache::is_current,
+        matrix::{MappedMatrix, MappedMatrixBuilder},
+        search::ElementaryItem,
+    },
+};
+
+mod parse;
+
+/// Loads the openLCA JSON-LD zip archive at `path` as the database `name`, from the
+/// `cache` if it exists, and caches it otherwise.
+///
+/// Returns the database, and the exchanges of the archive that could not be linked to
+/// a provider, which are left out of the technology matrix.
+pub fn load_openlca(
+    name: &str,
+    path: &Path,
+    cache: Option<&Path>,
+) -> Result<(Background, Vec<String>)> {
+    if let Some(cache) = cache {
+        if is_current(cache)? {
+            return Ok((Background::load_from_cache(cache)?, vec![]));
+        }
+    }
+    let archive = parse_openlca(path)?;
+    if archive.impact_categories.is_empty() {
+        return Err(OdysseyErrors::InvalidDatabase(format!(
+            "no LCIA category in {}, export the EF v3.1 method with the processes",
+            path.display()
+        )));
+    }
+    let units = Units::new(&archive);
+    let (processes, unlinked) = build_processes(&archive, &units);
+    let res = Background::build(name, processes, |intervention, _| {
+        construct_impact_matrix(intervention, &archive, &units)
+    })?;
+    if let Some(cache) = cache {
+        res.cache(cache)?;
+    }
+    Ok((res, unlinked))
+}
+
+/// Units of the archive, to express amounts in the reference unit of the flows.
+struct Units<'a> {
+    /// Factor of each unit, relative to the reference unit of its group.
+    factors: HashMap<&'a str, f64>,
+    /// Reference unit of the reference flow property of each flow.
+    flow_units: HashMap<&'a str, &'a str>,
+    flows: HashMap<&'a str, &'a Flow>,
+}
+
+impl<'a> Units<'a> {
+    fn new(archive: &'a Archive) -> Self {
+        let factors = archive
+            .unit_groups
+            .iter()
+            .flat_map(|g| g.units.iter())
+            .map(|u| (u.id.as_str(), u.conversion_factor))
+            .collect();
+        let group_units: HashMap<&str, &str> = archive
+            .unit_groups
+            .iter()
+            .filter_map(|g| {
+                let unit = g.units.iter().find(|u| u.reference)?;
+                Some((g.id.as_str(), unit.name.as_str()))
+            })
+            .collect();
+        let property_units: HashMap<&str, &str> = archive
+            .flow_properties
+            .iter()
+            .filter_map(|p| Some((p.id.as_str(), *group_units.get(p.unit_group.id.as_str())?)))
+            .collect();
+        let flow_units = archive
+            .flows
+            .iter()
+            .filter_map(|f| {
+                let property = f.flow_properties.iter().find(|p| p.reference)?;
+                let unit = property_units.get(property.flow_property.id.as_str())?;
+                Some((f.id.as_str(), *unit))
+            })
+            .collect();
+        let flows = archive.flows.iter().map(|f| (f.id.as_str(), f)).collect();
+        Units {
+            factors,
+            flow_units,
+            flows,
+        }
+    }
+
+    /// Factor converting quantities of `flow` in `unit` of `property` to its reference unit.
+    fn factor(&self, flow: &Flow, unit: Option<&Ref>, property: Option<&Ref>) -> f64 {
+        let unit_factor = unit
+            .and_then(|u| self.factors.get(u.id.as_str()))
+            .copied()
+            .unwrap_or(1.);
+        unit_factor / flow.property_factor(property.map(|p| p.id.as_str()))
+    }
+}
+
+/// Path of a category, from its `value` in a flow.
+fn category_path(value: &Value, categories: &HashMap<&str, &Category>) -> Vec<String> {
+    if let Some(path) = value.as_str() {
+        return path.split('/').map(|c| c.to_string()).collect();
+    }
+    let mut res = vec![];
+    let mut current = value["@id"].as_str();
+    while let Some(category) = current.and_then(|id| categories.get(id)) {
+        if res.len() > categories.len() {
+            break;
+        }
+        res.insert(0, category.name.clone());
+        current = category.category.as_ref().and_then(|c| c["@id"].as_str());
+    }
+    res
+}
+
+fn elementary_item(
+    flow: &Flow,
+    units: &Units,
+    categories: &HashMap<&str, &Category>,
+) -> ElementaryItem {
+    let mut path = flow
+        .category
+        .as_ref()
+        .map(|c| category_path(c, categories))
+        .unwrap_or_default();
+    if path.first().is_some_and(|c| c == "Elementary flows") {
+        path.remove(0);
+    }
+    let mut path = path.into_iter();
+    ElementaryItem {
+        id: flow.id.clone(),
+        name: flow.name.clone(),
+        compartment: path.next().unwrap_or_default(),
+        subcompartment: path.next(),
+        unit: units
+            .flow_units
+            .get(flow.id.as_str())
+            .unwrap_or(&"")
+            .to_string(),
+    }
+}
+
+/// Converts the processes of the archive, keeping their ids.
+///
+/// Product inputs, waste outputs and avoided products are linked to their default
+/// provider, or else to the process having the flow as quantitative reference. The
+/// exchanges without provider are left out and returned, as well as other products.
+fn build_processes(archive: &Archive, units: &Units) -> (HashMap<String, Process>, Vec<String>) {
+    let categories: HashMap<&str, &Category> = archive
+        .categories
+        .iter()
+        .map(|c| (c.id.as_str(), c))
+        .collect();
+    let locations: HashMap<&str, &str> = archive
+        .locations
+        .iter()
+        .filter_map(|l| Some((l.id.as_str(), l.code.as_deref()?)))
+        .collect();
+
+    let mut processes: Vec<_> = archive.processes.iter().collect();
+    processes.sort_by_key(|p| p.id.as_str());
+    let ids: HashSet<&str> = processes.iter().map(|p| p.id.as_str()).collect();
+    let mut providers: HashMap<&str, &str> = HashMap::new();
+    for process in processes.iter() {
+        for exchange in process.exchanges.iter().filter(|e| e.reference) {
+            providers
+                .entry(exchange.flow.id.as_str())
+                .or_insert(process.id.as_str());
+        }
+    }
+
+    let mut res = HashMap::new();
+    let mut unlinked = vec![];
+    for process in processes.iter() {
+        let mut product = None;
+        let mut intermediates = vec![];
+        let mut elementaries = vec![];
+        for exchange in process.exchanges.iter() {
+            let Some(flow) = units.flows.get(exchange.flow.id.as_str()) else {
+                unlinked.push(format!(
+                    "{}: unknown flow {}",
+                    process.name, exchange.flow.id
+                ));
+                continue;
+            };
+            let amount = exchange.amount
+                * units.factor(
+                    flow,
+                    exchange.unit.as_ref(),
+                    exchange.flow_property.as_ref(),
+                );
+            // Avoided products are stored as inputs, and count as negative inputs
+            let sign = if exchange.input != exchange.avoided {
+                -1.
+            } else {
+                1.
+            };
+            if flow.is_elementary() {
+                elementaries.push((elementary_item(flow, units, &categories), amount));
+            } else if exchange.reference {
+                let unit = units.flow_units.get(flow.id.as_str()).unwrap_or(&"");
+                product.get_or_insert((flow.name.clone(), unit.to_string()));
+                intermediates.push((process.id.clone(), sign * amount));
+            } else if exchange.input != flow.is_waste() || exchange.avoided {
+                let provider = exchange
+                    .default_provider
+                    .as_ref()
+                    .map(|p| p.id.as_str())
+                    .filter(|p| ids.contains(p))
+                    .or(providers.get(flow.id.as_str()).copied());
+                match provider {
+                    Some(provider) => intermediates.push((provider.to_string(), sign * amount)),
+                    None => unlinked.push(format!("{}: {}", process.name, flow.name)),
+                }
+            }
+        }
+        let (product, unit) = product.unwrap_or_default();
+        let location = process.location.as_ref().and_then(|l| {
+            locations
+                .get(l.id.as_str())
+                .map(|c| c.to_string())
+                .or(l.name.clone())
+        });
+        let converted = Process {
+            id: process.id.clone(),
+            name: process.name.clone(),
+            product,
+            location,
+            unit,
+            intermediates,
+            elementaries,
+        };
+        res.insert(process.id.clone(), converted);
+    }
+    (res, unlinked)
+}
+
+/// Characterization matrix of the EF v3.1 method for the elementary flows of the
+/// `intervention` matrix, from the impact categories of the archive, their factors
+/// being converted to the reference units of the flows. Categories of other names and
+/// regionalized factors are left out.
+///
+/// Archives often carry several methods sharing the names of the EF categories (EF 3.0,
+/// ILCD 2011...): only the categories of the EF v3.1 method are then used, and two
+/// categories of the same name fail the import.
+fn construct_impact_matrix(
+    intervention: &MappedMatrix<String, String>,
+    archive: &Archive,
+    units: &Units,
+) -> Result<MappedMatrix<ImpactCategory, String>> {
+    let mut mat = MappedMatrixBuilder::new();
+    mat.copy_rows_into_cols(intervention);
+    mat.copy_vec_into_rows(&ImpactCategory::get_empty_vector());
+    let mut names: HashMap<ImpactCategory, &str> = HashMap::new();
+    for impact_category in ef31_categories(archive)? {
+        let Some(category) = category_from_name(&impact_category.name) else {
+            continue;
+        };
+        if let Some(name) = names.insert(category.clone(), &impact_category.name) {
+            return Err(OdysseyErrors::InvalidDatabase(format!(
+                "LCIA categories {:?} and {:?} both match {}, export only the EF v3.1 method",
+                name,
+                impact_category.name,
+                category.metadata().name
+            )));
+        }
+        for factor in impact_category.factors.iter() {
+            let Some(flow) = units.flows.get(factor.flow.id.as_str()) else {
+                continue;
+            };
+            if factor.location.is_some() || !intervention.contains_row(&flow.id) {
+                continue;
+            }
+            let value = factor.value
+                / units.factor(flow, factor.unit.as_ref(), factor.flow_property.as_ref());
+            mat.add_triplet(category.clone(), flow.id.clone(), value);
+        }
+    }
+    Ok(mat.build())
+}
+
+/// Impact categories of the EF v3.1 method of the archive, or all of them if the
+/// archive has no LCIA method.
+fn ef31_categories(archive: &Archive) -> Result<Vec<&parse::ImpactCategory>> {
+    if archive.impact_methods.is_empty() {
+        return Ok(archive.impact_categories.iter().collect());
+    }
+    let methods: Vec<&ImpactMethod> = archive
+        .impact_methods
+        .iter()
+        .filter(|m| is_method_name(&m.name))
+        .collect();
+    let [method] = methods.as_slice() else {
+        return Err(OdysseyErrors::InvalidDatabase(format!(
+            "{} EF v3.1 LCIA methods in the archive instead of one, among: {}",
+            methods.len(),
+            archive
+                .impact_methods
+                .iter()
+                .map(|m| m.name.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        )));
+    };
+    let ids: HashSet<&str> = method.categories.iter().map(|c| c.id.as_str()).collect();
+    Ok(archive
+        .impact_categories
+        .iter()
+        .filter(|c| ids.contains(c.id.as_str()))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn from_value<T: serde::de::DeserializeOwned>(value: Value) -> T {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn test_build_processes() {
+        let mass = json!({"@id": "mass", "unitGroup": {"@id": "units-of-mass"}});
+        let units = json!({"@id": "units-of-mass", "units": [
+            {"@id": "kg", "name": "kg", "conversionFactor": 1.0, "isRefUnit": true},
+            {"@id": "g", "name": "g", "conversionFactor": 0.001},
+        ]});
+        let flow = |id: &str, flow_type: &str, category: &str| {
+            json!({
+                "@id": id, "name": id, "flowType": flow_type, "category": category,
+                "flowProperties": [{"conversionFactor": 1.0, "isRefFlowProperty": true, "flowProperty": {"@id": "mass"}}],
+            })
+        };
+        let steel = json!({"@id": "p-steel", "name": "steel production", "location": {"@id": "de", "name": "Germany"}, "exchanges": [
+            {"amount": 1.0, "isQuantitativeReference": true, "flow": {"@id": "steel"}, "unit": {"@id": "kg"}},
+            {"amount": 500.0, "isInput": true, "flow": {"@id": "coal"}, "unit": {"@id": "g"}, "defaultProvider": {"@id": "p-coal-2"}},
+            {"amount": 2.0, "isInput": true, "flow": {"@id": "water"}, "unit": {"@id": "kg"}},
+            {"amount": 0.1, "isInput": true, "isAvoidedProduct": true, "flow": {"@id": "coal"}, "defaultProvider": {"@id": "p-coal-1"}},
+            {"amount": 3.0, "flow": {"@id": "co2"}, "unit": {"@id": "kg"}},
+        ]});
+        let coal = |id: &str| {
+            json!({"@id": id, "name": id, "exchanges": [
+                {"amount": 1.0, "isQuantitativeReference": true, "flow": {"@id": "coal"}},
+            ]})
+        };
+        let archive = Archive {
+            processes: vec![
+                from_value(steel),
+                from_value(coal("p-coal-1")),
+                from_value(coal("p-coal-2")),
+            ],
+            flows: vec![
+                from_value(flow("steel", "PRODUCT_FLOW", "Products")),
+                from_value(flow("coal", "PRODUCT_FLOW", "Products")),
+                from_value(flow("water", "PRODUCT_FLOW", "Products")),
+                from_value(flow(
+                    "co2",
+                    "ELEMENTARY_FLOW",
+                    "Elementary flows/Emission to air/unspecified",
+                )),
+            ],
+            flow_properties: vec![from_value(mass)],
+            unit_groups: vec![from_value(units)],
+            locations: vec![from_value(json!({"@id": "de", "code": "DE"}))],
+            categories: vec![],
+            impact_categories: vec![],
+            impact_methods: vec![],
+        };
+
+        let units = Units::new(&archive);
+        let (processes, unlinked) = build_processes(&archive, &units);
+        let steel = &processes["p-steel"];
+        assert_eq!(steel.location.as_deref(), Some("DE"));
+        assert_eq!(steel.unit, "kg");
+        assert_eq!(
+            steel.intermediates,
+            vec![
+                ("p-steel".to_string(), 1.),
+                ("p-coal-2".to_string(), -0.5),
+                ("p-coal-1".to_string(), 0.1)
+            ]
+        );
+        assert_eq!(steel.elementaries[0].0.compartment, "Emission to air");
+        assert_eq!(
+            steel.elementaries[0].0.subcompartment.as_deref(),
+            Some("unspecified")
+        );
+        assert_eq!(unlinked, vec!["steel production: water".to_string()]);
+    }
+
+    #[test]
+    fn test_impact_methods() {
+        let co2 = json!({
+            "@id": "co2", "name": "co2", "flowType": "ELEMENTARY_FLOW",
+            "category": "Elementary flows/Emission to air/unspecified",
+        });
+        let category = |id: &str, value: f64| {
+            json!({"@id": id, "name": "Climate change", "impactFactors": [
+                {"flow": {"@id": "co2"}, "value": value},
+            ]})
+        };
+        let method = |name: &str, category: &str| json!({"name": name, "impactCategories": [{"@id": category}]});
+        let mut archive = Archive {
+            flows: vec![from_value(co2)],
+            impact_categories: vec![
+                from_value(category("gwp-30", 2.)),
+                from_value(category("gwp-31", 1.)),
+            ],
+            ..Default::default()
+        };
+        let mut intervention = MappedMatrixBuilder::new();
+        intervention.add_row("co2".to_string());
+        intervention.add_col("p".to_string());
+        let intervention = intervention.build();
+
+        // Without method, categories of the same name can't be told apart
+        let units = Units::new(&archive);
+        assert!(construct_impact_matrix(&intervention, &archive, &units).is_err());
+
+        archive.impact_methods = vec![
+            from_value(method("EF v3.0", "gwp-30")),
+            from_value(method("EF v3.1", "gwp-31")),
+        ];
+        let units = Units::new(&archive);
+        let characterization = construct_impact_matrix(&intervention, &archive, &units).unwrap();
+        let gwp: Vec<f64> = characterization
+            .triplets()
+            .filter(|(c, _, _)| **c == category_from_name("Climate change").unwrap())
+            .map(|(_, _, value)| value)
+            .collect();
+        assert_eq!(gwp, vec![1.]);
+    }
+}
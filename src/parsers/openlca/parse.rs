@@ -0,0 +1,245 @@
+use std::{
+    fs::File,
+    io::{BufReader, Read},
+    path::Path,
+};
+
+use serde::Deserialize;
+use serde_json::Value;
+use zip::ZipArchive;
+
+use crate::errors::Result;
+
+fn one() -> f64 {
+    1.
+}
+
+/// Reference to another entity.
+#[derive(Debug, Deserialize)]
+pub struct Ref {
+    #[serde(rename = "@id")]
+    pub id: String,
+
+    pub name: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Unit {
+    #[serde(rename = "@id")]
+    pub id: String,
+
+    pub name: String,
+
+    #[serde(rename = "conversionFactor", default = "one")]
+    pub conversion_factor: f64,
+
+    #[serde(rename = "isRefUnit", alias = "referenceUnit", default)]
+    pub reference: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UnitGroup {
+    #[serde(rename = "@id")]
+    pub id: String,
+
+    #[serde(default)]
+    pub units: Vec<Unit>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FlowProperty {
+    #[serde(rename = "@id")]
+    pub id: String,
+
+    #[serde(rename = "unitGroup")]
+    pub unit_group: Ref,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FlowPropertyFactor {
+    #[serde(rename = "conversionFactor", default = "one")]
+    pub conversion_factor: f64,
+
+    #[serde(rename = "isRefFlowProperty", alias = "referenceFlowProperty", default)]
+    pub reference: bool,
+
+    #[serde(rename = "flowProperty")]
+    pub flow_property: Ref,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Flow {
+    #[serde(rename = "@id")]
+    pub id: String,
+
+    pub name: String,
+
+    #[serde(rename = "flowType")]
+    pub flow_type: String,
+
+    /// Path of the category in openLCA 2, reference to it in openLCA 1.
+    pub category: Option<Value>,
+
+    #[serde(rename = "flowProperties", default)]
+    pub flow_properties: Vec<FlowPropertyFactor>,
+}
+
+impl Flow {
+    pub fn is_elementary(&self) -> bool {
+        self.flow_type == "ELEMENTARY_FLOW"
+    }
+
+    pub fn is_waste(&self) -> bool {
+        self.flow_type == "WASTE_FLOW"
+    }
+
+    /// Factor of the flow property `id`, relative to the reference flow property.
+    pub fn property_factor(&self, id: Option<&str>) -> f64 {
+        self.flow_properties
+            .iter()
+            .find(|f| match id {
+                Some(id) => f.flow_property.id == id,
+                None => f.reference,
+            })
+            .map(|f| f.conversion_factor)
+            .unwrap_or(1.)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Location {
+    #[serde(rename = "@id")]
+    pub id: String,
+
+    pub code: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Category {
+    #[serde(rename = "@id")]
+    pub id: String,
+
+    pub name: String,
+
+    /// Parent category.
+    pub category: Option<Value>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Exchange {
+    #[serde(default)]
+    pub amount: f64,
+
+    #[serde(rename = "isInput", alias = "input", default)]
+    pub input: bool,
+
+    #[serde(
+        rename = "isQuantitativeReference",
+        alias = "quantitativeReference",
+        default
+    )]
+    pub reference: bool,
+
+    #[serde(rename = "isAvoidedProduct", alias = "avoidedProduct", default)]
+    pub avoided: bool,
+
+    pub flow: Ref,
+
+    pub unit: Option<Ref>,
+
+    #[serde(rename = "flowProperty")]
+    pub flow_property: Option<Ref>,
+
+    #[serde(rename = "defaultProvider")]
+    pub default_provider: Option<Ref>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Process {
+    #[serde(rename = "@id")]
+    pub id: String,
+
+    pub name: String,
+
+    pub location: Option<Ref>,
+
+    #[serde(default)]
+    pub exchanges: Vec<Exchange>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ImpactFactor {
+    pub flow: Ref,
+
+    pub value: f64,
+
+    pub unit: Option<Ref>,
+
+    #[serde(rename = "flowProperty")]
+    pub flow_property: Option<Ref>,
+
+    pub location: Option<Ref>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ImpactCategory {
+    #[serde(rename = "@id")]
+    pub id: String,
+
+    pub name: String,
+
+    #[serde(rename = "impactFactors", default)]
+    pub factors: Vec<ImpactFactor>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ImpactMethod {
+    pub name: String,
+
+    #[serde(rename = "impactCategories", default)]
+    pub categories: Vec<Ref>,
+}
+
+/// Entities of an openLCA JSON-LD archive.
+#[derive(Debug, Default)]
+pub struct Archive {
+    pub processes: Vec<Process>,
+    pub flows: Vec<Flow>,
+    pub flow_properties: Vec<FlowProperty>,
+    pub unit_groups: Vec<UnitGroup>,
+    pub locations: Vec<Location>,
+    pub categories: Vec<Category>,
+    pub impact_categories: Vec<ImpactCategory>,
+    pub impact_methods: Vec<ImpactMethod>,
+}
+
+/// Entities of the openLCA JSON-LD zip archive at `path`, by folder of the archive.
+pub fn parse_openlca(path: &Path) -> Result<Archive> {
+    let mut zip = ZipArchive::new(BufReader::new(File::open(path)?))?;
+    let mut res = Archive::default();
+    for i in 0..zip.len() {
+        let file = zip.by_index(i)?;
+        let name = file.name().to_string();
+        if !name.ends_with(".json") {
+            continue;
+        }
+        let folder = name.rsplit('/').nth(1).unwrap_or_default().to_string();
+        let reader = BufReader::new(file);
+        match folder.as_str() {
+            "processes" => res.processes.push(from_reader(reader)?),
+            "flows" => res.flows.push(from_reader(reader)?),
+            "flow_properties" => res.flow_properties.push(from_reader(reader)?),
+            "unit_groups" => res.unit_groups.push(from_reader(reader)?),
+            "locations" => res.locations.push(from_reader(reader)?),
+            "categories" => res.categories.push(from_reader(reader)?),
+            "lcia_categories" => res.impact_categories.push(from_reader(reader)?),
+            "lcia_methods" => res.impact_methods.push(from_reader(reader)?),
+            _ => {}
+        }
+    }
+    Ok(res)
+}
+
+fn from_reader<T: serde::de::DeserializeOwned>(reader: impl Read) -> Result<T> {
+    Ok(serde_json::from_reader(reader)?)
+}
@@ -0,0 +1,94 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    comput::{
+        foreground::{Foreground, ForegroundBuilder, ForegroundExchange, ForegroundLink},
+        lca::Database,
+    },
+    errors::{OdysseyErrors, Result},
+    parsers::project::{
+        resolve::{resolve_biosphere_exchange, resolve_database_exchange},
+        Activity, ExchangeLink,
+    },
+    utils::{geographies::LocationPolicy, search::Search},
+};
+
+/// Builds the foreground system whose reference activity is described by the file at `path`.
+/// Every background database referenced by the project is loaded into `databases`.
+/// Locations substituted according to `policy` are reported in the notes of the foreground.
+pub fn build_foreground(
+    path: &Path,
+    search: &Search,
+    databases: &mut HashMap<String, Box<dyn Database>>,
+    policy: LocationPolicy,
+) -> Result<Foreground> {
+    let root = activity_id(path)?;
+    let mut builder = ForegroundBuilder::new(root.clone());
+    let mut files_fifo: Vec<PathBuf> = vec![path.to_path_buf()];
+    while !files_fifo.is_empty() {
+        let path = files_fifo.remove(0);
+        let id = activity_id(&path)?;
+        if builder.contains(&id) {
+            continue;
+        }
+        builder.add_activity(id.clone(), path.display().to_string());
+
+        let activity = Activity::from_file(&path)?;
+        if let Some(functional_unit) = activity.functional_unit.as_ref().filter(|_| id == root) {
+            builder.set_functional_unit(functional_unit.clone());
+        }
+        if let Some(stage) = &activity.stage {
+            builder.set_stage(&id, stage.clone());
+        }
+        for exchange in activity.exchanges {
+            let (link, factor) = match &exchange.link {
+                ExchangeLink::File { file } => {
+                    let file = Path::new(file);
+                    let child = activity_id(file).map_err(|e| OdysseyErrors::InvalidExchange {
+                        file: path.display().to_string(),
+                        exchange: exchange.describe(),
+                        reason: format!("can't open {}: {e}", file.display()),
+                    })?;
+                    files_fifo.push(file.to_path_buf());
+                    (ForegroundLink::Activity(child), 1.)
+                }
+                ExchangeLink::Database { database } => {
+                    let resolved = resolve_database_exchange(
+                        &path, database, &exchange, search, databases, policy,
+                    )?;
+                    if let Some(note) = resolved.note(&path, &exchange) {
+                        builder.add_note(note);
+                    }
+                    (resolved.link, resolved.factor)
+                }
+                ExchangeLink::Biosphere { biosphere } => {
+                    let resolved =
+                        resolve_biosphere_exchange(&path, biosphere, &exchange, databases)?;
+                    (resolved.link, resolved.factor)
+                }
+            };
+            // Biosphere exchanges can't be outputs
+            let multiplicator = if exchange.output { -1. } else { 1. };
+            builder.add_exchange(
+                &id,
+                ForegroundExchange {
+                    name: exchange.name.clone(),
+                    link,
+                    amount: multiplicator * factor * exchange.amount,
+                    stage: exchange.stage.clone(),
+                },
+            );
+        }
+    }
+    Ok(builder.build())
+}
+
+/// Foreground activities are identified by the canonical path of their file,
+/// so that a file included several times (or in a loop) is a single activity.
+pub fn activity_id(path: &Path) -> Result<String> {
+    Ok(fs::canonicalize(path)?.display().to_string())
+}
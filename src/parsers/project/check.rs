@@ -0,0 +1,220 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt, fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    comput::lca::Database,
+    errors::{OdysseyErrors, Result},
+    parsers::project::{
+        build::activity_id,
+        resolve::{resolve_biosphere_exchange, resolve_database_exchange},
+        Activity, ExchangeLink,
+    },
+    utils::{geographies::LocationPolicy, search::Search},
+};
+
+/// Problem found in a project file by [check_project].
+#[derive(Debug, Clone)]
+pub struct Problem {
+    pub file: PathBuf,
+    /// Line of the problem, starting at 1, if known.
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+impl Problem {
+    fn new(file: &Path, line: Option<usize>, error: impl fmt::Display) -> Self {
+        // Errors of the resolution are already prefixed by the file
+        let message = error.to_string();
+        let message = message
+            .strip_prefix(&format!("{}: ", file.display()))
+            .map(|m| m.to_string())
+            .unwrap_or(message);
+        Self {
+            file: file.to_path_buf(),
+            line,
+            message,
+        }
+    }
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "{}:{}: {}", self.file.display(), line, self.message),
+            None => write!(f, "{}: {}", self.file.display(), self.message),
+        }
+    }
+}
+
+/// Checks the project described by the file at `path`, and every file it includes,
+/// without computing anything: the structure of each file, the amounts, and the
+/// resolution of every file, database and biosphere reference, units included.
+///
+/// Returns all the problems found, and the number of files checked.
+pub fn check_project(
+    path: &Path,
+    search: &Search,
+    databases: &mut HashMap<String, Box<dyn Database>>,
+    policy: LocationPolicy,
+) -> Result<(Vec<Problem>, usize)> {
+    let mut problems = vec![];
+    let mut visited = HashSet::new();
+    let mut files_fifo: Vec<PathBuf> = vec![path.to_path_buf()];
+    while !files_fifo.is_empty() {
+        let path = files_fifo.remove(0);
+        let content = match activity_id(&path).and_then(|id| {
+            let content = fs::read_to_string(&path)?;
+            Ok((id, content))
+        }) {
+            Ok((id, content)) => {
+                if !visited.insert(id) {
+                    continue;
+                }
+                content
+            }
+            Err(e) => {
+                problems.push(Problem::new(&path, None, e));
+                continue;
+            }
+        };
+        let (file_problems, included) =
+            check_activity(&path, Path::new(""), &content, search, databases, policy);
+        problems.extend(file_problems);
+        files_fifo.extend(included);
+    }
+    Ok((problems, visited.len()))
+}
+
+/// Checks the `content` of the project file at `path`, without following its includes.
+/// Included files are looked for relatively to the `root` of the project.
+///
+/// Returns the problems found, and the files it includes that exist.
+pub fn check_activity(
+    path: &Path,
+    root: &Path,
+    content: &str,
+    search: &Search,
+    databases: &mut HashMap<String, Box<dyn Database>>,
+    policy: LocationPolicy,
+) -> (Vec<Problem>, Vec<PathBuf>) {
+    let mut problems = vec![];
+    let mut included = vec![];
+    let activity: Activity = match serde_yaml::from_str(content) {
+        Ok(activity) => activity,
+        Err(e) => {
+            let line = e.location().map(|l| l.line());
+            return (vec![Problem::new(path, line, e)], included);
+        }
+    };
+    if let Some(functional_unit) = &activity.functional_unit {
+        if !(functional_unit.amount.is_finite() && functional_unit.amount > 0.) {
+            problems.push(Problem::new(
+                path,
+                find_line(content, "functional_unit"),
+                "the amount of the functional unit must be positive",
+            ));
+        }
+    }
+
+    let lines = exchange_lines(content);
+    for (index, exchange) in activity.exchanges.iter().enumerate() {
+        let line = lines.get(index).copied();
+        if !exchange.amount.is_finite() {
+            problems.push(Problem::new(
+                path,
+                line,
+                OdysseyErrors::InvalidExchange {
+                    file: path.display().to_string(),
+                    exchange: exchange.describe(),
+                    reason: "the amount must be a finite number".to_string(),
+                },
+            ));
+        }
+        let resolution = match &exchange.link {
+            ExchangeLink::File { file } => {
+                let included_path = root.join(file);
+                match activity_id(&included_path) {
+                    Ok(_) => {
+                        included.push(included_path);
+                        Ok(())
+                    }
+                    Err(e) => Err(OdysseyErrors::InvalidExchange {
+                        file: path.display().to_string(),
+                        exchange: exchange.describe(),
+                        reason: format!("can't open {file}: {e}"),
+                    }),
+                }
+            }
+            ExchangeLink::Database { database } => {
+                resolve_database_exchange(path, database, exchange, search, databases, policy)
+                    .map(|_| ())
+            }
+            ExchangeLink::Biosphere { biosphere } => {
+                resolve_biosphere_exchange(path, biosphere, exchange, databases).map(|_| ())
+            }
+        };
+        if let Err(e) = resolution {
+            problems.push(Problem::new(path, line, e));
+        }
+    }
+    (problems, included)
+}
+
+/// Line, starting at 1, of the first line of `content` starting with `key:`.
+fn find_line(content: &str, key: &str) -> Option<usize> {
+    content
+        .lines()
+        .position(|l| l.trim_start().starts_with(&format!("{key}:")))
+        .map(|i| i + 1)
+}
+
+/// Lines, starting at 1, where each entry of the `exchanges` sequence starts.
+pub fn exchange_lines(content: &str) -> Vec<usize> {
+    let Some(start) = find_line(content, "exchanges") else {
+        return vec![];
+    };
+    let mut res = vec![];
+    let mut indent = None;
+    for (i, line) in content.lines().enumerate().skip(start) {
+        let trimmed = line.trim_start();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        let line_indent = line.len() - trimmed.len();
+        if !trimmed.starts_with('-') {
+            // A key at the indentation of the entries closes the sequence
+            match indent {
+                Some(indent) if line_indent <= indent => break,
+                None => break,
+                _ => continue,
+            }
+        }
+        match indent {
+            None => indent = Some(line_indent),
+            Some(indent) if line_indent < indent => break,
+            Some(indent) if line_indent > indent => continue,
+            _ => {}
+        }
+        res.push(i + 1);
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exchange_lines() {
+        let content = "functional_unit:\n  name: panel\n  amount: 1\n  unit: m2\nexchanges:\n- file: a.yaml\n  amount: 1\n\n# comment\n- database:\n    name: ecoinvent\n    version: '3.11'\n  amount: 2\nstage: use\n";
+        assert_eq!(exchange_lines(content), vec![6, 10]);
+        assert_eq!(find_line(content, "functional_unit"), Some(1));
+
+        let indented =
+            "exchanges:\n  - file: a.yaml\n    amount: 1\n  - file: b.yaml\n    amount: 2\n";
+        assert_eq!(exchange_lines(indented), vec![2, 4]);
+    }
+}
@@ -0,0 +1,128 @@
+use std::{fs::File, io::BufReader, path::Path};
+
+use schemars::{JsonSchema, Schema};
+use serde::{Deserialize, Serialize};
+
+use crate::{comput::foreground::FunctionalUnit, errors::Result};
+
+mod build;
+mod check;
+mod resolve;
+
+pub use build::build_foreground;
+pub use check::{check_activity, check_project, exchange_lines, Problem};
+pub use resolve::{
+    pin_ids, resolve_biosphere_exchange, resolve_database_exchange, Pinned, Resolved,
+};
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+pub struct DatabaseInfos {
+    pub name: String,
+    pub version: String,
+}
+
+impl DatabaseInfos {
+    /// Name under which the database is cached and indexed.
+    pub fn full_name(&self) -> String {
+        format!("{}_{}", self.name, self.version)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+#[serde(untagged)]
+pub enum ExchangeLink {
+    /// Reference product of another project file
+    File { file: String },
+    /// Activity of a background database
+    Database { database: DatabaseInfos },
+    /// Elementary flow of a background database
+    Biosphere { biosphere: DatabaseInfos },
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct Exchange {
+    #[serde(flatten)]
+    pub link: ExchangeLink,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub location: Option<String>,
+    /// Unit of `amount`, converted into the unit of the linked activity or flow.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unit: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    pub amount: f64,
+
+    /// Id of the linked activity (`activityId_productId` for ecoinvent) or
+    /// elementary flow. When set, the name is not searched.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    /// Compartment of the linked elementary flow (e.g. `air`), if not linked by id.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub compartment: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub subcompartment: Option<String>,
+
+    /// The exchange is produced by the activity (co-product, avoided product)
+    /// instead of being consumed. Not allowed on biosphere exchanges, whose amounts
+    /// are positive for both emissions and extractions.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub output: bool,
+
+    /// Life cycle stage of the exchange (e.g. `materials`, `manufacturing`,
+    /// `transport`, `use`, `end-of-life`). For a `file` exchange, it overrides
+    /// the stage of the included file.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stage: Option<String>,
+}
+
+impl Exchange {
+    /// Short description of the exchange, for messages.
+    pub fn describe(&self) -> String {
+        let mut res = match (&self.name, &self.id) {
+            (Some(name), _) => format!("{name:?}"),
+            (None, Some(id)) => id.clone(),
+            (None, None) => match &self.link {
+                ExchangeLink::File { file } => file.clone(),
+                _ => "unnamed exchange".to_string(),
+            },
+        };
+        if let Some(location) = &self.location {
+            res += &format!(" in {location}");
+        }
+        if let Some(compartment) = &self.compartment {
+            res += &format!(" to {compartment}");
+            if let Some(subcompartment) = &self.subcompartment {
+                res += &format!("/{subcompartment}");
+            }
+        }
+        if let Some(unit) = &self.unit {
+            res += &format!(" [{unit}]");
+        }
+        res
+    }
+}
+
+/// Project file, describing a foreground activity.
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct Activity {
+    /// What the exchanges of the file deliver. Only used for the root file of a project.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub functional_unit: Option<FunctionalUnit>,
+    /// Life cycle stage of the whole file, unless the exchange including it specifies one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stage: Option<String>,
+    pub exchanges: Vec<Exchange>,
+}
+
+impl Activity {
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let file = File::open(path)?;
+        let reader = BufReader::new(&file);
+        Ok(serde_yaml::from_reader(reader)?)
+    }
+}
+
+/// JSON Schema of project files, for editors and YAML language servers.
+pub fn project_schema() -> Schema {
+    schemars::schema_for!(Activity)
+}
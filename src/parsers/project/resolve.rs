@@ -0,0 +1,432 @@
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use uuid::Uuid;
+
+use crate::{
+    comput::{foreground::ForegroundLink, lca::Database},
+    errors::{OdysseyErrors, Result},
+    parsers::{
+        load_database,
+        project::{
+            build::activity_id, check::exchange_lines, Activity, DatabaseInfos, Exchange,
+            ExchangeLink,
+        },
+    },
+    utils::{
+        geographies::{Geographies, LocationPolicy},
+        search::{InventoryItem, Search},
+        units,
+    },
+};
+
+/// Background activity or elementary flow matched by an exchange.
+#[derive(Debug)]
+pub struct Resolved {
+    pub link: ForegroundLink,
+    /// Converts the amount of the exchange into the unit of the linked flow.
+    pub factor: f64,
+    /// Location of the activity, when it differs from the requested one.
+    pub fallback_location: Option<String>,
+}
+
+impl Resolved {
+    /// Note reporting the substituted location, if any.
+    pub fn note(&self, file: &Path, exchange: &Exchange) -> Option<String> {
+        self.fallback_location.as_ref().map(|location| {
+            format!(
+                "{}: {} resolved in {}",
+                file.display(),
+                exchange.describe(),
+                location
+            )
+        })
+    }
+}
+
+/// Outcome of [pin_ids].
+#[derive(Debug, Default)]
+pub struct Pinned {
+    /// Number of references pinned in each modified file.
+    pub files: Vec<(PathBuf, usize)>,
+    /// Locations substituted while resolving the references.
+    pub notes: Vec<String>,
+}
+
+/// Pins the background references of the project described by the file at `path`,
+/// and of every file it includes: each database and biosphere exchange without `id`
+/// is resolved, and its `id` written back to the file.
+///
+/// Locations substituted according to `policy` are reported in the notes.
+pub fn pin_ids(
+    path: &Path,
+    search: &Search,
+    databases: &mut HashMap<String, Box<dyn Database>>,
+    policy: LocationPolicy,
+) -> Result<Pinned> {
+    let mut res = Pinned::default();
+    let mut visited = HashSet::new();
+    let mut files_fifo: Vec<PathBuf> = vec![path.to_path_buf()];
+    while !files_fifo.is_empty() {
+        let path = files_fifo.remove(0);
+        if !visited.insert(activity_id(&path)?) {
+            continue;
+        }
+
+        let activity = Activity::from_file(&path)?;
+        let mut ids = vec![];
+        for (index, exchange) in activity.exchanges.iter().enumerate() {
+            let link = match &exchange.link {
+                ExchangeLink::File { file } => {
+                    files_fifo.push(PathBuf::from(file));
+                    continue;
+                }
+                _ if exchange.id.is_some() => continue,
+                ExchangeLink::Database { database } => {
+                    let resolved = resolve_database_exchange(
+                        &path, database, exchange, search, databases, policy,
+                    )?;
+                    res.notes.extend(resolved.note(&path, exchange));
+                    resolved.link
+                }
+                ExchangeLink::Biosphere { biosphere } => {
+                    resolve_biosphere_exchange(&path, biosphere, exchange, databases)?.link
+                }
+            };
+            if let ForegroundLink::Database { id, .. } | ForegroundLink::Biosphere { id, .. } = link
+            {
+                ids.push((index, id));
+            }
+        }
+
+        if !ids.is_empty() {
+            let content = std::fs::read_to_string(&path)?;
+            let patched = insert_ids(&content, &ids).ok_or_else(|| {
+                OdysseyErrors::InvalidArgument(format!(
+                    "{}: the ids can't be written in place, write each exchange as a block mapping",
+                    path.display()
+                ))
+            })?;
+            std::fs::write(&path, patched)?;
+            res.files.push((path, ids.len()));
+        }
+    }
+    Ok(res)
+}
+
+/// Writes an `id` key at the beginning of the exchanges of `content` given by their index,
+/// leaving the rest of the file, comments included, untouched. Returns `None` if an
+/// exchange isn't a block mapping, or if the patched file doesn't hold the ids.
+fn insert_ids(content: &str, ids: &[(usize, String)]) -> Option<String> {
+    let starts = exchange_lines(content);
+    let mut lines: Vec<String> = content.lines().map(String::from).collect();
+    // From the last exchange, so that the lines of the previous ones don't move
+    let mut sorted: Vec<&(usize, String)> = ids.iter().collect();
+    sorted.sort_by_key(|(index, _)| std::cmp::Reverse(*index));
+    for (index, id) in sorted {
+        let line = starts.get(*index)? - 1;
+        let value = serde_yaml::to_string(id).ok()?;
+        let value = value.trim_end();
+        let first = &lines[line];
+        let rest = first.trim_start()[1..].trim_start();
+        if rest.is_empty() {
+            // `-` alone, the keys starting on the next lines
+            let next = lines[line + 1..]
+                .iter()
+                .find(|l| !l.trim().is_empty() && !l.trim_start().starts_with('#'))?;
+            let indent = next.len() - next.trim_start().len();
+            lines.insert(line + 1, format!("{}id: {value}", " ".repeat(indent)));
+        } else {
+            if rest.starts_with('{') || rest.starts_with('#') || !rest.contains(':') {
+                return None;
+            }
+            // `- key: ...` becomes `- id: ...` followed by the key, aligned with it
+            let prefix = &first[..first.len() - rest.len()];
+            let key = format!("{}{rest}", " ".repeat(prefix.len()));
+            lines[line] = format!("{prefix}id: {value}");
+            lines.insert(line + 1, key);
+        }
+    }
+    let mut res = lines.join("\n");
+    if content.ends_with('\n') {
+        res.push('\n');
+    }
+
+    let activity: Activity = serde_yaml::from_str(&res).ok()?;
+    ids.iter()
+        .all(|(index, id)| {
+            activity
+                .exchanges
+                .get(*index)
+                .is_some_and(|e| e.id.as_ref() == Some(id))
+        })
+        .then_some(res)
+}
+
+/// Resolves a background activity of the `file`, either by `id` or by searching its name.
+/// With [LocationPolicy::Fallback], the parent regions of the requested location
+/// are searched in turn. If no activity has the requested unit, activities whose
+/// unit can be converted are accepted.
+pub fn resolve_database_exchange(
+    file: &Path,
+    database_infos: &DatabaseInfos,
+    exchange: &Exchange,
+    search: &Search,
+    databases: &mut HashMap<String, Box<dyn Database>>,
+    policy: LocationPolicy,
+) -> Result<Resolved> {
+    let database_name = database_infos.full_name();
+    let unresolved = || OdysseyErrors::UnresolvedExchange {
+        file: file.display().to_string(),
+        database: database_name.clone(),
+        exchange: exchange.describe(),
+    };
+
+    if let Some(id) = &exchange.id {
+        let database = load_into(database_infos, databases)?;
+        let item = database.find_candidate(id).ok_or_else(unresolved)?;
+        return Ok(Resolved {
+            factor: unit_factor(file, exchange, &item.unit)?,
+            link: ForegroundLink::Database {
+                database: database_name,
+                id: id.clone(),
+            },
+            fallback_location: None,
+        });
+    }
+
+    let Some(exchange_name) = &exchange.name else {
+        return Err(OdysseyErrors::InvalidExchange {
+            file: file.display().to_string(),
+            exchange: exchange.describe(),
+            reason: "database exchanges require a name or an id".to_string(),
+        });
+    };
+    let locations = match (&exchange.location, policy) {
+        (Some(location), LocationPolicy::Fallback) => {
+            let database = load_into(database_infos, databases)?;
+            Some(
+                database
+                    .geographies()
+                    .map(|g| g.fallbacks(location))
+                    .unwrap_or_else(|| Geographies::new().fallbacks(location)),
+            )
+        }
+        (Some(location), LocationPolicy::Exact) => Some(vec![location.clone()]),
+        (None, _) => None,
+    };
+    let find = |unit: Option<&str>| match &locations {
+        Some(locations) => {
+            search.search_for_items_in(exchange_name, Some(&database_name), locations, unit)
+        }
+        None => search
+            .search_for_items(exchange_name, Some(&database_name), None, unit)
+            .map(|items| (items, None)),
+    };
+
+    let (mut items, mut location) = find(exchange.unit.as_deref())?;
+    if items.is_empty() {
+        if let Some(unit) = &exchange.unit {
+            // Accept other units of the same dimension
+            let (others, other_location) = find(None)?;
+            if !others.is_empty() {
+                let requested = units::dimension(unit);
+                let convertible: Vec<InventoryItem> = others
+                    .iter()
+                    .filter(|item| requested.is_some() && units::dimension(&item.unit) == requested)
+                    .cloned()
+                    .collect();
+                if convertible.is_empty() {
+                    return Err(OdysseyErrors::IncompatibleUnits {
+                        file: file.display().to_string(),
+                        exchange: exchange.describe(),
+                        unit: unit.clone(),
+                        expected: others
+                            .iter()
+                            .map(|item| item.unit.clone())
+                            .collect::<Vec<_>>()
+                            .join(", "),
+                    });
+                }
+                items = convertible;
+                location = other_location;
+            }
+        }
+    }
+    match &items[..] {
+        [] => Err(unresolved()),
+        [item] => {
+            load_into(database_infos, databases)?;
+            Ok(Resolved {
+                factor: unit_factor(file, exchange, &item.unit)?,
+                link: ForegroundLink::Database {
+                    database: database_name,
+                    id: item.id.clone(),
+                },
+                fallback_location: location.filter(|l| exchange.location.as_ref() != Some(l)),
+            })
+        }
+        _ => Err(OdysseyErrors::AmbiguousExchange {
+            file: file.display().to_string(),
+            database: database_name.clone(),
+            exchange: exchange.describe(),
+            candidates: items
+                .iter()
+                .map(|item| {
+                    format!(
+                        "  {} ({}) {} {} [id: {}]",
+                        item.name,
+                        item.alt_name.as_deref().unwrap_or("-"),
+                        item.location.as_deref().unwrap_or("-"),
+                        item.unit,
+                        item.id
+                    )
+                })
+                .collect(),
+        }),
+    }
+}
+
+/// Resolves an elementary flow of the `file`, either by `id` or by name and compartment.
+pub fn resolve_biosphere_exchange(
+    file: &Path,
+    database_infos: &DatabaseInfos,
+    exchange: &Exchange,
+    databases: &mut HashMap<String, Box<dyn Database>>,
+) -> Result<Resolved> {
+    if exchange.output {
+        return Err(OdysseyErrors::InvalidExchange {
+            file: file.display().to_string(),
+            exchange: exchange.describe(),
+            reason: "elementary flows can't be outputs, their amounts are positive for both emissions and extractions".to_string(),
+        });
+    }
+    let database = load_into(database_infos, databases)?;
+    let unresolved = || OdysseyErrors::UnresolvedExchange {
+        file: file.display().to_string(),
+        database: database_infos.full_name(),
+        exchange: exchange.describe(),
+    };
+
+    let elementary = if let Some(id) = &exchange.id {
+        // Accept any casing of the UUID
+        let id = Uuid::from_str(id)
+            .map(|u| u.to_string())
+            .unwrap_or(id.clone());
+        database.find_elementary(&id).ok_or_else(unresolved)?
+    } else {
+        let (Some(name), Some(compartment)) = (&exchange.name, &exchange.compartment) else {
+            return Err(OdysseyErrors::InvalidExchange {
+                file: file.display().to_string(),
+                exchange: exchange.describe(),
+                reason: "biosphere exchanges require an id, or a name and a compartment"
+                    .to_string(),
+            });
+        };
+        let candidates =
+            database.search_elementaries(name, compartment, exchange.subcompartment.as_deref());
+        match &candidates[..] {
+            [] => return Err(unresolved()),
+            [e] => *e,
+            // Without subcompartment, default to the unspecified one
+            _ => candidates
+                .iter()
+                .find(|e| e.subcompartment.as_deref() == Some("unspecified"))
+                .copied()
+                .ok_or_else(|| OdysseyErrors::AmbiguousExchange {
+                    file: file.display().to_string(),
+                    database: database_infos.full_name(),
+                    exchange: exchange.describe(),
+                    candidates: candidates
+                        .iter()
+                        .map(|e| {
+                            format!(
+                                "  {} ({}/{}) {} [id: {}]",
+                                e.name,
+                                e.compartment,
+                                e.subcompartment.as_deref().unwrap_or("-"),
+                                e.unit,
+                                e.id
+                            )
+                        })
+                        .collect(),
+                })?,
+        }
+    };
+    Ok(Resolved {
+        factor: unit_factor(file, exchange, &elementary.unit)?,
+        link: ForegroundLink::Biosphere {
+            database: database_infos.full_name(),
+            id: elementary.id.clone(),
+        },
+        fallback_location: None,
+    })
+}
+
+/// Factor converting the amount of `exchange` into `unit`, the unit of the linked flow.
+fn unit_factor(file: &Path, exchange: &Exchange, unit: &str) -> Result<f64> {
+    let Some(exchange_unit) = &exchange.unit else {
+        return Ok(1.);
+    };
+    units::conversion_factor(exchange_unit, unit).ok_or_else(|| OdysseyErrors::IncompatibleUnits {
+        file: file.display().to_string(),
+        exchange: exchange.describe(),
+        unit: exchange_unit.clone(),
+        expected: unit.to_string(),
+    })
+}
+
+/// Loads the database described by `database_infos` into `databases`, if not already loaded.
+fn load_into<'a>(
+    database_infos: &DatabaseInfos,
+    databases: &'a mut HashMap<String, Box<dyn Database>>,
+) -> Result<&'a mut Box<dyn Database>> {
+    let database_name = database_infos.full_name();
+    if !databases.contains_key(&database_name) {
+        let database = load_database(&database_infos.name, &database_infos.version)?;
+        databases.insert(database_name.clone(), database);
+    }
+    Ok(databases.get_mut(&database_name).unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_insert_ids() {
+        let content = "# yaml-language-server: $schema=odyssey.schema.json\nexchanges:\n  # steel frame\n  - database:\n      name: ecoinvent\n      version: '3.11'\n    name: steel\n    amount: 2 # kg\n  - file: glass.yaml\n    amount: 1\n  -\n    biosphere:\n      name: ecoinvent\n      version: '3.11'\n    name: Carbon dioxide, fossil\n    amount: 0.5\n";
+        let ids = vec![(0, "a_b".to_string()), (2, "123".to_string())];
+        let patched = insert_ids(content, &ids).unwrap();
+        assert_eq!(
+            patched,
+            "# yaml-language-server: $schema=odyssey.schema.json\nexchanges:\n  # steel frame\n  - id: a_b\n    database:\n      name: ecoinvent\n      version: '3.11'\n    name: steel\n    amount: 2 # kg\n  - file: glass.yaml\n    amount: 1\n  -\n    id: '123'\n    biosphere:\n      name: ecoinvent\n      version: '3.11'\n    name: Carbon dioxide, fossil\n    amount: 0.5\n"
+        );
+
+        // Flow mappings can't be patched
+        let flow = "exchanges:\n- {file: glass.yaml, amount: 1}\n";
+        assert_eq!(insert_ids(flow, &[(0, "a".to_string())]), None);
+    }
+
+    #[test]
+    fn test_biosphere_output() {
+        let exchange: Exchange = serde_yaml::from_str(
+            "biosphere:\n  name: ecoinvent\n  version: '3.11'\nname: Carbon dioxide, fossil\ncompartment: air\namount: 1\noutput: true\n",
+        )
+        .unwrap();
+        let ExchangeLink::Biosphere { biosphere } = &exchange.link else {
+            panic!("biosphere exchange expected");
+        };
+        let error = resolve_biosphere_exchange(
+            Path::new("a.yaml"),
+            biosphere,
+            &exchange,
+            &mut HashMap::new(),
+        )
+        .unwrap_err();
+        assert!(matches!(error, OdysseyErrors::InvalidExchange { .. }));
+    }
+}
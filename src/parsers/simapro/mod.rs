@@ -0,0 +1,380 @@
+use std::{collections::HashMap, path::Path};
+
+use uuid::Uuid;
+
+use crate::{
+    errors::{OdysseyErrors, Result},
+    parsers::{
+        background::Background,
+        build::Process,
+        ecospold2::impacts::{
+            ef31::construct_impact_matrix,
+            mapping::{flow_ids, flow_key, latest_version, FlowKey},
+        },
+        simapro::parse::{parse_simapro, Block, SimaPro},
+    },
+    utils::{
+        cache::is_current,
+        expression::{evaluate, evaluate_all},
+        search::ElementaryItem,
+        units,
+    },
+};
+
+mod parse;
+
+/// Sections of the elementary flows, with their compartment.
+const ELEMENTARY_SECTIONS: [(&str, &str); 4] = [
+    ("Resources", "natural resource"),
+    ("Emissions to air", "air"),
+    ("Emissions to water", "water"),
+    ("Emissions to soil", "soil"),
+];
+
+/// Sections of the inputs from other processes.
+const INPUT_SECTIONS: [&str; 3] = ["Materials/fuels", "Electricity/heat", "Waste to treatment"];
+
+/// Loads the SimaPro CSV export at `path` as the database `name`, from the `cache` if
+/// it exists, and caches it otherwise.
+///
+/// Elementary flows are mapped to the ecoinvent flows of the latest LCIA mapping, by
+/// name, compartment and subcompartment, to be characterized by the EF v3.1 method.
+/// Returns the database, and the exchanges of the export that could not be linked to
+/// a process, which are left out of the technology matrix.
+pub fn load_simapro(
+    name: &str,
+    path: &Path,
+    cache: Option<&Path>,
+) -> Result<(Background, Vec<String>)> {
+    if let Some(cache) = cache {
+        if is_current(cache)? {
+            return Ok((Background::load_from_cache(cache)?, vec![]));
+        }
+    }
+    let data = parse_simapro(path)?;
+    let version = latest_version()?;
+    let (processes, unlinked) = build_processes(name, &data, &flow_ids(&version)?)?;
+    let res = Background::build(name, processes, |intervention, _| {
+        construct_impact_matrix(&version, intervention)
+    })?;
+    if let Some(cache) = cache {
+        res.cache(cache)?;
+    }
+    Ok((res, unlinked))
+}
+
+/// Converts the process blocks into processes of the database `name`, one per
+/// product, the exchanges being allocated to the products by their allocation
+/// percentage. Processes are linked by product name, so two processes of the same
+/// product fail the loading. Elementary flows are given the ids of `flows` when they
+/// match one.
+///
+/// Returns the processes, and the inputs no process provides.
+fn build_processes(
+    name: &str,
+    data: &SimaPro,
+    flows: &HashMap<FlowKey, String>,
+) -> Result<(HashMap<String, Process>, Vec<String>)> {
+    let namespace = Uuid::new_v5(&Uuid::NAMESPACE_OID, name.as_bytes());
+    let product_id = |product: &str| Uuid::new_v5(&namespace, product.as_bytes()).to_string();
+    let blocks: Vec<&Block> = data.blocks("Process").collect();
+    let mut products: HashMap<&str, &str> = HashMap::new();
+    let mut duplicates = vec![];
+    for row in blocks.iter().flat_map(|b| {
+        b.section("Products")
+            .iter()
+            .chain(b.section("Waste treatment"))
+    }) {
+        if products.insert(field(row, 0), field(row, 1)).is_some() {
+            duplicates.push(field(row, 0));
+        }
+    }
+    if !duplicates.is_empty() {
+        duplicates.sort();
+        duplicates.dedup();
+        return Err(OdysseyErrors::InvalidDatabase(format!(
+            "several processes output each of these products, keep only one of them:\n{}",
+            duplicates.join("\n")
+        )));
+    }
+    let globals = parameters(
+        data,
+        HashMap::new(),
+        data.blocks("Database Input parameters")
+            .chain(data.blocks("Project Input parameters"))
+            .flat_map(|b| b.sections.values().flatten()),
+        data.blocks("Database Calculated parameters")
+            .chain(data.blocks("Project Calculated parameters"))
+            .flat_map(|b| b.sections.values().flatten()),
+    )?;
+
+    let mut res = HashMap::new();
+    let mut unlinked = vec![];
+    for block in blocks {
+        let variables = parameters(
+            data,
+            globals.clone(),
+            block.section("Input parameters").iter(),
+            block.section("Calculated parameters").iter(),
+        )?;
+        let value = |field: &str| amount(field, data.decimal, &variables);
+        let process_name = block
+            .section("Process name")
+            .first()
+            .map(|row| field(row, 0))
+            .filter(|name| !name.is_empty());
+        let label = process_name
+            .or(block.section("Products").first().map(|row| field(row, 0)))
+            .unwrap_or_default();
+
+        // Inputs are converted to the unit of their product, and avoided products are outputs
+        let mut intermediates = vec![];
+        let inputs = INPUT_SECTIONS
+            .iter()
+            .flat_map(|section| block.section(section).iter().map(|row| (row, -1.)));
+        let avoided = block
+            .section("Avoided products")
+            .iter()
+            .map(|row| (row, 1.));
+        for (row, sign) in inputs.chain(avoided) {
+            let supplier = field(row, 0);
+            let unit = field(row, 1);
+            let Some(expected) = products.get(supplier) else {
+                unlinked.push(format!("{label}: {supplier}"));
+                continue;
+            };
+            match units::conversion_factor(unit, expected) {
+                Some(factor) => intermediates
+                    .push((product_id(supplier), sign * factor * value(field(row, 2))?)),
+                None => unlinked.push(format!(
+                    "{label}: {supplier} in {unit}, which can't be converted to {expected}"
+                )),
+            }
+        }
+        let mut elementaries = vec![];
+        for (section, compartment) in ELEMENTARY_SECTIONS {
+            for row in block.section(section) {
+                let (item, factor) = elementary_item(
+                    &namespace,
+                    flows,
+                    field(row, 0),
+                    compartment,
+                    field(row, 1),
+                    field(row, 2),
+                );
+                elementaries.push((item, factor * value(field(row, 3))?));
+            }
+        }
+
+        // Waste treatments have no allocation column
+        let mut outputs = vec![];
+        for row in block.section("Products") {
+            let allocation = match field(row, 3) {
+                "" => 1.,
+                allocation => value(allocation.trim_end_matches('%'))? / 100.,
+            };
+            outputs.push((row, allocation));
+        }
+        outputs.extend(block.section("Waste treatment").iter().map(|row| (row, 1.)));
+        for (row, allocation) in outputs {
+            let product = field(row, 0);
+            let id = product_id(product);
+            let mut process_intermediates = vec![(id.clone(), value(field(row, 2))?)];
+            process_intermediates.extend(
+                intermediates
+                    .iter()
+                    .map(|(supplier, amount)| (supplier.clone(), amount * allocation)),
+            );
+            let process = Process {
+                id: id.clone(),
+                name: product.to_string(),
+                product: process_name.unwrap_or(product).to_string(),
+                location: location(product),
+                unit: field(row, 1).to_string(),
+                intermediates: process_intermediates,
+                elementaries: elementaries
+                    .iter()
+                    .map(|(item, amount)| (item.clone(), amount * allocation))
+                    .collect(),
+            };
+            res.insert(id, process);
+        }
+    }
+    Ok((res, unlinked))
+}
+
+/// Elementary flow of a SimaPro exchange, with the factor converting its amount into
+/// the unit of the flow. Flows are identified by their ecoinvent id if they match one
+/// of `flows`, falling back to the unspecified subcompartment, and expressed in the
+/// base unit of their dimension.
+fn elementary_item(
+    namespace: &Uuid,
+    flows: &HashMap<FlowKey, String>,
+    name: &str,
+    compartment: &str,
+    subcompartment: &str,
+    unit: &str,
+) -> (ElementaryItem, f64) {
+    let (unit, factor) = match units::lookup(unit) {
+        Some((dimension, factor)) => (dimension.base_unit(), factor),
+        None => (unit, 1.),
+    };
+    let key = flow_key(name, compartment, subcompartment);
+    let unspecified = flow_key(name, compartment, "unspecified");
+    let (id, subcompartment) = match (flows.get(&key), flows.get(&unspecified)) {
+        (Some(id), _) => (id.clone(), key.2),
+        (None, Some(id)) => (id.clone(), unspecified.2),
+        (None, None) => {
+            let flow = format!("{}|{}|{}|{}", key.0, key.1, key.2, unit);
+            (Uuid::new_v5(namespace, flow.as_bytes()).to_string(), key.2)
+        }
+    };
+    let item = ElementaryItem {
+        id,
+        name: name.to_string(),
+        compartment: compartment.to_string(),
+        subcompartment: Some(subcompartment),
+        unit: unit.to_string(),
+    };
+    (item, factor)
+}
+
+/// Values of the `inputs` and `calculated` parameter rows, added to the `variables`.
+fn parameters<'a>(
+    data: &SimaPro,
+    mut variables: HashMap<String, f64>,
+    inputs: impl Iterator<Item = &'a Vec<String>>,
+    calculated: impl Iterator<Item = &'a Vec<String>>,
+) -> Result<HashMap<String, f64>> {
+    for row in inputs {
+        let value = amount(field(row, 1), data.decimal, &variables)?;
+        variables.insert(field(row, 0).to_lowercase(), value);
+    }
+    let expressions: Vec<(String, String)> = calculated
+        .map(|row| {
+            let expression = field(row, 1);
+            (
+                field(row, 0).to_string(),
+                normalize(expression, data.decimal),
+            )
+        })
+        .collect();
+    evaluate_all(&expressions, variables)
+}
+
+/// Amount of a field, which may be an expression of the parameters.
+fn amount(field: &str, decimal: char, variables: &HashMap<String, f64>) -> Result<f64> {
+    evaluate(&normalize(field, decimal), variables)
+}
+
+/// Replaces the `decimal` separator of numbers by a point.
+fn normalize(expression: &str, decimal: char) -> String {
+    if decimal == '.' {
+        expression.to_string()
+    } else {
+        expression.replace(decimal, ".")
+    }
+}
+
+fn field(row: &[String], i: usize) -> &str {
+    row.get(i).map(String::as_str).unwrap_or_default()
+}
+
+/// Location of a product, between braces in the names of ecoinvent products.
+fn location(product: &str) -> Option<String> {
+    let start = product.find('{')?;
+    let end = start + product[start..].find('}')?;
+    Some(product[start + 1..end].to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_processes() {
+        let csv = "{SimaPro 9.5}
+{CSV separator: Semicolon}
+{Decimal separator: ,}
+
+Process
+
+Process name
+Steel production
+
+Products
+Steel {RER};kg;1;80;not defined;Metals;
+Slag {RER};kg;a*2;20;not defined;Metals;
+
+Materials/fuels
+Electricity {RER};kWh;2,5;Undefined;0;0;0;
+Electricity {RER};Wh;500;Undefined;0;0;0;
+Electricity {RER};kg;1;Undefined;0;0;0;
+Water;kg;3;Undefined;0;0;0;
+
+Emissions to air
+Carbon dioxide, fossil;high. pop.;g;500;Undefined;0;0;0;
+
+Input parameters
+a;0,5;Undefined;0;0;0;No;
+
+End
+
+Process
+
+Products
+Electricity {RER};kWh;1;100;not defined;Energy;
+
+End
+";
+        let data = parse::parse_str(csv);
+        let flows = HashMap::from([(
+            flow_key("Carbon dioxide, fossil", "air", "urban air close to ground"),
+            "co2".to_string(),
+        )]);
+        let (processes, unlinked) = build_processes("SimaPro_test", &data, &flows).unwrap();
+        assert_eq!(processes.len(), 3);
+        assert_eq!(
+            unlinked,
+            vec![
+                "Steel production: Electricity {RER} in kg, which can't be converted to kWh",
+                "Steel production: Water"
+            ]
+        );
+
+        let steel = processes
+            .values()
+            .find(|p| p.name == "Steel {RER}")
+            .unwrap();
+        let slag = processes.values().find(|p| p.name == "Slag {RER}").unwrap();
+        let electricity = processes
+            .values()
+            .find(|p| p.name == "Electricity {RER}")
+            .unwrap();
+        assert_eq!(steel.product, "Steel production");
+        assert_eq!(steel.location.as_deref(), Some("RER"));
+        assert_eq!(
+            steel.intermediates,
+            vec![
+                (steel.id.clone(), 1.),
+                (electricity.id.clone(), -2.),
+                (electricity.id.clone(), -0.4)
+            ]
+        );
+        assert_eq!(slag.intermediates[0], (slag.id.clone(), 1.));
+        assert_eq!(steel.elementaries[0].0.id, "co2");
+        assert_eq!(steel.elementaries[0].0.unit, "kg");
+        assert!((steel.elementaries[0].1 - 0.4).abs() < 1e-12);
+
+        // Two processes of the same product can't be told apart
+        let repeated = csv.replace(
+            "Energy;\n\nEnd",
+            "Energy;\nSlag {RER};kg;1;0;not defined;Metals;\n\nEnd",
+        );
+        let data = parse::parse_str(&repeated);
+        let error = build_processes("SimaPro_test", &data, &flows).unwrap_err();
+        assert!(error
+            .to_string()
+            .ends_with("keep only one of them:\nSlag {RER}"));
+    }
+}
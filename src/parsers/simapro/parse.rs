@@ -0,0 +1,151 @@
+use std::{collections::HashMap, fs, path::Path};
+
+use crate::errors::Result;
+
+/// Block of a SimaPro CSV export, between its name and `End`.
+#[derive(Debug, Default)]
+pub struct Block {
+    pub name: String,
+
+    /// Rows of each section of the block, by section name. Blocks without sections,
+    /// such as the parameter blocks, have a single section named after the block.
+    pub sections: HashMap<String, Vec<Vec<String>>>,
+}
+
+impl Block {
+    pub fn section(&self, name: &str) -> &[Vec<String>] {
+        self.sections.get(name).map(Vec::as_slice).unwrap_or(&[])
+    }
+}
+
+/// Content of a SimaPro CSV export.
+#[derive(Debug)]
+pub struct SimaPro {
+    /// Decimal separator of the numbers and expressions.
+    pub decimal: char,
+
+    pub blocks: Vec<Block>,
+}
+
+impl SimaPro {
+    /// Blocks named `name`.
+    pub fn blocks<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Block> {
+        self.blocks.iter().filter(move |b| b.name == name)
+    }
+}
+
+/// Parses the SimaPro CSV export at `path`.
+pub fn parse_simapro(path: &Path) -> Result<SimaPro> {
+    Ok(parse_str(&decode(fs::read(path)?)))
+}
+
+/// Characters of the bytes 0x80 to 0x9F in Windows-1252, the others being those of
+/// Latin-1. Unassigned bytes are kept as the control characters of the same code.
+const WINDOWS_1252: [char; 32] = [
+    '€', '\u{81}', '‚', 'ƒ', '„', '…', '†', '‡', 'ˆ', '‰', 'Š', '‹', 'Œ', '\u{8D}', 'Ž', '\u{8F}',
+    '\u{90}', '‘', '’', '“', '”', '•', '–', '—', '˜', '™', 'š', '›', 'œ', '\u{9D}', 'ž', 'Ÿ',
+];
+
+/// Text of an export, in UTF-8 if it is valid, and else in Windows-1252, the encoding
+/// of SimaPro.
+fn decode(bytes: Vec<u8>) -> String {
+    match String::from_utf8(bytes) {
+        Ok(text) => text.trim_start_matches('\u{FEFF}').to_string(),
+        Err(error) => error
+            .into_bytes()
+            .into_iter()
+            .map(|b| match b {
+                0x80..=0x9F => WINDOWS_1252[(b - 0x80) as usize],
+                _ => b as char,
+            })
+            .collect(),
+    }
+}
+
+pub(crate) fn parse_str(content: &str) -> SimaPro {
+    let mut separator = ';';
+    let mut decimal = '.';
+    let mut blocks = vec![];
+    let mut block: Option<Block> = None;
+    let mut section: Option<String> = None;
+    for line in content.lines() {
+        let line = line.trim_end_matches('\r');
+        if let Some(header) = line.strip_prefix('{') {
+            let header = header.trim_end_matches('}');
+            if let Some(value) = header.strip_prefix("CSV separator: ") {
+                separator = match value {
+                    "Comma" => ',',
+                    "Tab" => '\t',
+                    _ => ';',
+                };
+            } else if let Some(value) = header.strip_prefix("Decimal separator: ") {
+                decimal = value.chars().next().unwrap_or('.');
+            }
+            continue;
+        }
+        let row = split_row(line, separator);
+        let first = row.first().map(String::as_str).unwrap_or_default();
+        let blank = row.iter().all(|f| f.is_empty());
+        match block.as_mut() {
+            None if blank => {}
+            None => {
+                // Blocks of parameters have their rows right after their name
+                section = (first != "Process").then(|| first.to_string());
+                block = Some(Block {
+                    name: first.to_string(),
+                    sections: HashMap::new(),
+                });
+            }
+            Some(_) if first == "End" => {
+                blocks.extend(block.take());
+                section = None;
+            }
+            Some(_) if blank => section = None,
+            Some(current) => match &section {
+                None => {
+                    section = Some(first.to_string());
+                    current.sections.entry(first.to_string()).or_default();
+                }
+                Some(name) => current.sections.entry(name.clone()).or_default().push(row),
+            },
+        }
+    }
+    SimaPro { decimal, blocks }
+}
+
+/// Fields of the `line`, which may be quoted to contain the `separator`.
+fn split_row(line: &str, separator: char) -> Vec<String> {
+    let mut res = vec![];
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            c if c == separator && !quoted => {
+                res.push(std::mem::take(&mut field).trim().to_string())
+            }
+            c => field.push(c),
+        }
+    }
+    res.push(field.trim().to_string());
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode() {
+        // "Béton – 25 €" in Windows-1252, and in UTF-8 with a byte order mark
+        let windows = b"B\xe9ton \x96 25 \x80".to_vec();
+        assert_eq!(decode(windows), "Béton – 25 €");
+        let utf8 = "\u{FEFF}Béton – 25 €".as_bytes().to_vec();
+        assert_eq!(decode(utf8), "Béton – 25 €");
+    }
+}
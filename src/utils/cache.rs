@@ -0,0 +1,78 @@
+use std::{
+    fs::{self, File},
+    io::{BufReader, BufWriter, Read, Write},
+    path::Path,
+};
+
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::errors::{OdysseyErrors, Result};
+
+/// Version of the layout of the caches, to increase whenever a cached struct changes,
+/// so that the caches of a previous version are built again instead of misread.
+const CACHE_VERSION: u32 = 1;
+
+/// Start of the caches, before their version.
+const MAGIC: &[u8; 8] = b"ODYSSEY\0";
+
+/// Writes `value` in a cache at `path`.
+pub fn write_cache<T: Serialize>(path: &Path, value: &T) -> Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    writer.write_all(MAGIC)?;
+    writer.write_all(&CACHE_VERSION.to_le_bytes())?;
+    bincode::serialize_into(&mut writer, value)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Test weither a cache of the current version exists at `path`.
+pub fn is_current(path: &Path) -> Result<bool> {
+    if !fs::exists(path)? {
+        return Ok(false);
+    }
+    Ok(current_header(&mut File::open(path)?))
+}
+
+/// Reads the cache at `path`, which fails if it was written by a previous version.
+pub fn read_cache<T: DeserializeOwned>(path: &Path) -> Result<T> {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    if !fs::exists(path)? {
+        return Err(OdysseyErrors::NoCache(format!(
+            "{name} was not previously loaded"
+        )));
+    }
+    let mut reader = BufReader::new(File::open(path)?);
+    if !current_header(&mut reader) {
+        return Err(OdysseyErrors::OutdatedCache(name.to_string()));
+    }
+    Ok(bincode::deserialize_from(reader)?)
+}
+
+fn current_header(reader: &mut impl Read) -> bool {
+    let mut header = [0u8; 12];
+    reader.read_exact(&mut header).is_ok()
+        && header[..8] == MAGIC[..]
+        && header[8..] == CACHE_VERSION.to_le_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_outdated_cache() {
+        let path = std::env::temp_dir().join(format!("odyssey-cache-{}", std::process::id()));
+        write_cache(&path, &vec![1., 2.]).unwrap();
+        assert!(is_current(&path).unwrap());
+        assert_eq!(read_cache::<Vec<f64>>(&path).unwrap(), vec![1., 2.]);
+
+        // Caches written before the header are rejected instead of misread
+        bincode::serialize_into(File::create(&path).unwrap(), &vec![1., 2.]).unwrap();
+        assert!(!is_current(&path).unwrap());
+        assert!(matches!(
+            read_cache::<Vec<f64>>(&path),
+            Err(OdysseyErrors::OutdatedCache(_))
+        ));
+        fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,252 @@
+use std::collections::HashMap;
+
+use crate::errors::{OdysseyErrors, Result};
+
+/// Evaluates the arithmetic `expression`, whose variables are looked up in `variables`
+/// by lowercase name.
+///
+/// Supports numbers, variables, `+ - * / ^`, parentheses and the functions `abs`,
+/// `sqrt`, `exp`, `ln`, `log`, `min` and `max`, whose arguments are separated by `,` or `;`.
+pub fn evaluate(expression: &str, variables: &HashMap<String, f64>) -> Result<f64> {
+    let mut parser = Parser {
+        expression,
+        chars: expression.chars().collect(),
+        position: 0,
+        variables,
+    };
+    let res = parser.expression()?;
+    parser.skip_spaces();
+    match parser.peek() {
+        None => Ok(res),
+        Some(c) => Err(parser.error(format!("unexpected {c:?}"))),
+    }
+}
+
+/// Evaluates the `expressions`, by name, which may refer to each other in any order
+/// and to the `variables`. Returns the variables completed with the results.
+pub fn evaluate_all(
+    expressions: &[(String, String)],
+    mut variables: HashMap<String, f64>,
+) -> Result<HashMap<String, f64>> {
+    let mut remaining: Vec<&(String, String)> = expressions.iter().collect();
+    while !remaining.is_empty() {
+        let count = remaining.len();
+        let mut last_error = None;
+        remaining.retain(
+            |(name, expression)| match evaluate(expression, &variables) {
+                Ok(value) => {
+                    variables.insert(name.to_lowercase(), value);
+                    false
+                }
+                Err(e) => {
+                    last_error = Some(e);
+                    true
+                }
+            },
+        );
+        // No progress: the remaining expressions refer to unknown or cyclic variables
+        if remaining.len() == count {
+            return Err(last_error.unwrap());
+        }
+    }
+    Ok(variables)
+}
+
+struct Parser<'a> {
+    expression: &'a str,
+    chars: Vec<char>,
+    position: usize,
+    variables: &'a HashMap<String, f64>,
+}
+
+impl Parser<'_> {
+    fn error(&self, reason: String) -> OdysseyErrors {
+        OdysseyErrors::InvalidExpression {
+            expression: self.expression.to_string(),
+            reason,
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.position).copied()
+    }
+
+    fn skip_spaces(&mut self) {
+        while self.peek().is_some_and(|c| c.is_whitespace()) {
+            self.position += 1;
+        }
+    }
+
+    /// Consumes `c` if it is the next character.
+    fn eat(&mut self, c: char) -> bool {
+        self.skip_spaces();
+        if self.peek() == Some(c) {
+            self.position += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expression(&mut self) -> Result<f64> {
+        let mut res = self.term()?;
+        loop {
+            if self.eat('+') {
+                res += self.term()?;
+            } else if self.eat('-') {
+                res -= self.term()?;
+            } else {
+                return Ok(res);
+            }
+        }
+    }
+
+    fn term(&mut self) -> Result<f64> {
+        let mut res = self.unary()?;
+        loop {
+            if self.eat('*') {
+                res *= self.unary()?;
+            } else if self.eat('/') {
+                res /= self.unary()?;
+            } else {
+                return Ok(res);
+            }
+        }
+    }
+
+    fn unary(&mut self) -> Result<f64> {
+        if self.eat('-') {
+            Ok(-self.unary()?)
+        } else if self.eat('+') {
+            self.unary()
+        } else {
+            self.power()
+        }
+    }
+
+    /// Powers bind tighter than the unary minus, and are right associative.
+    fn power(&mut self) -> Result<f64> {
+        let base = self.primary()?;
+        if self.eat('^') {
+            Ok(base.powf(self.unary()?))
+        } else {
+            Ok(base)
+        }
+    }
+
+    fn primary(&mut self) -> Result<f64> {
+        self.skip_spaces();
+        if self.eat('(') {
+            let res = self.expression()?;
+            if !self.eat(')') {
+                return Err(self.error("missing )".to_string()));
+            }
+            return Ok(res);
+        }
+        match self.peek() {
+            Some(c) if c.is_ascii_digit() || c == '.' => self.number(),
+            Some(c) if c.is_alphabetic() || c == '_' => self.identifier(),
+            Some(c) => Err(self.error(format!("unexpected {c:?}"))),
+            None => Err(self.error("unexpected end".to_string())),
+        }
+    }
+
+    fn number(&mut self) -> Result<f64> {
+        let start = self.position;
+        while self.peek().is_some_and(|c| c.is_ascii_digit() || c == '.') {
+            self.position += 1;
+        }
+        if self.peek().is_some_and(|c| c == 'e' || c == 'E') {
+            let mantissa = self.position;
+            self.position += 1;
+            if self.peek().is_some_and(|c| c == '+' || c == '-') {
+                self.position += 1;
+            }
+            if self.peek().is_some_and(|c| c.is_ascii_digit()) {
+                while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+                    self.position += 1;
+                }
+            } else {
+                // Not an exponent, but a variable following the number
+                self.position = mantissa;
+            }
+        }
+        let number: String = self.chars[start..self.position].iter().collect();
+        number
+            .parse()
+            .map_err(|_| self.error(format!("invalid number {number:?}")))
+    }
+
+    fn identifier(&mut self) -> Result<f64> {
+        let start = self.position;
+        while self.peek().is_some_and(|c| c.is_alphanumeric() || c == '_') {
+            self.position += 1;
+        }
+        let name: String = self.chars[start..self.position]
+            .iter()
+            .collect::<String>()
+            .to_lowercase();
+        if !self.eat('(') {
+            return self
+                .variables
+                .get(&name)
+                .copied()
+                .ok_or_else(|| self.error(format!("unknown variable {name:?}")));
+        }
+        let mut arguments = vec![self.expression()?];
+        while self.eat(',') || self.eat(';') {
+            arguments.push(self.expression()?);
+        }
+        if !self.eat(')') {
+            return Err(self.error("missing )".to_string()));
+        }
+        match (name.as_str(), arguments.as_slice()) {
+            ("abs", [x]) => Ok(x.abs()),
+            ("sqrt", [x]) => Ok(x.sqrt()),
+            ("exp", [x]) => Ok(x.exp()),
+            ("ln", [x]) => Ok(x.ln()),
+            ("log", [x]) => Ok(x.log10()),
+            ("min", [_, ..]) => Ok(arguments.iter().copied().fold(f64::INFINITY, f64::min)),
+            ("max", [_, ..]) => Ok(arguments.iter().copied().fold(f64::NEG_INFINITY, f64::max)),
+            _ => Err(self.error(format!(
+                "unknown function {name:?} of {} arguments",
+                arguments.len()
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_evaluate() {
+        let variables = HashMap::from([("a".to_string(), 2.), ("b_2".to_string(), 3.)]);
+        assert_eq!(evaluate("1 + 2 * 3", &variables).unwrap(), 7.);
+        assert_eq!(evaluate("(1 + 2) * 3", &variables).unwrap(), 9.);
+        assert_eq!(evaluate("-A^2 + B_2", &variables).unwrap(), -1.);
+        assert_eq!(evaluate("2^3^2", &variables).unwrap(), 512.);
+        assert_eq!(evaluate("1.5e-3 * 2E3", &variables).unwrap(), 3.);
+        assert_eq!(evaluate("max(a; b_2, 1) / 2", &variables).unwrap(), 1.5);
+        assert!(evaluate("a +", &variables).is_err());
+        assert!(evaluate("c * 2", &variables).is_err());
+    }
+
+    #[test]
+    fn test_evaluate_all() {
+        let expressions = vec![
+            ("c".to_string(), "b * 2".to_string()),
+            ("b".to_string(), "a + 1".to_string()),
+        ];
+        let variables = HashMap::from([("a".to_string(), 1.)]);
+        let res = evaluate_all(&expressions, variables).unwrap();
+        assert_eq!(res["c"], 4.);
+
+        let cyclic = vec![
+            ("a".to_string(), "b".to_string()),
+            ("b".to_string(), "a".to_string()),
+        ];
+        assert!(evaluate_all(&cyclic, HashMap::new()).is_err());
+    }
+}
@@ -0,0 +1,81 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+/// How the location of an exchange is matched against the locations of a database.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum LocationPolicy {
+    /// Only the requested location is accepted.
+    #[default]
+    Exact,
+    /// The parent regions of the requested location are tried in order,
+    /// down to `RoW` and `GLO`.
+    Fallback,
+}
+
+/// Hierarchy of the locations of a database.
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+pub struct Geographies {
+    /// Parent regions of each location, from the most specific to the most general.
+    parents: HashMap<String, Vec<String>>,
+}
+
+impl Geographies {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the parent regions of `location`, from the most specific to the most general.
+    pub fn insert(&mut self, location: String, parents: Vec<String>) {
+        self.parents.insert(location, parents);
+    }
+
+    pub fn parents(&self, location: &str) -> &[String] {
+        self.parents
+            .get(location)
+            .map(|p| p.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// Locations to try in order when `location` is requested: the location itself,
+    /// its parent regions, then `RoW` and `GLO`.
+    pub fn fallbacks(&self, location: &str) -> Vec<String> {
+        let mut res = vec![location.to_string()];
+        for parent in self.parents(location).iter().map(|p| p.as_str()) {
+            if !res.iter().any(|l| l == parent) {
+                res.push(parent.to_string());
+            }
+        }
+        for global in ["RoW", "GLO"] {
+            if !res.iter().any(|l| l == global) {
+                res.push(global.to_string());
+            }
+        }
+        res
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fallbacks() {
+        let mut geographies = Geographies::new();
+        geographies.insert(
+            "FR".to_string(),
+            vec!["Europe without Switzerland".to_string(), "RER".to_string()],
+        );
+        geographies.insert("RoW".to_string(), vec!["GLO".to_string()]);
+        assert_eq!(
+            geographies.fallbacks("FR"),
+            ["FR", "Europe without Switzerland", "RER", "RoW", "GLO"]
+        );
+        // Locations are never tried twice
+        assert_eq!(geographies.fallbacks("RoW"), ["RoW", "GLO"]);
+        assert_eq!(geographies.fallbacks("GLO"), ["GLO", "RoW"]);
+        // Unknown locations only fall back to the global ones
+        assert_eq!(geographies.fallbacks("XX"), ["XX", "RoW", "GLO"]);
+        assert!(geographies.parents("XX").is_empty());
+    }
+}
@@ -53,6 +53,13 @@ where
         self.cols = (*copied.rows).clone();
     }
 
+    pub fn copy_cols_into_cols<T>(&mut self, copied: &MappedMatrix<T, C>)
+    where
+        T: std::cmp::Eq + Hash + Clone,
+    {
+        self.cols = (*copied.cols).clone();
+    }
+
     pub fn copy_vec_into_rows(&mut self, copied: &MappedVector<R>) {
         self.rows = (*copied.mapping).clone();
     }
@@ -247,6 +254,32 @@ where
         self.cols.contains_left(id)
     }
 
+    /// Test weither the matrix could be factorized, which [MappedMatrix::solve] requires.
+    pub fn is_invertible(&self) -> bool {
+        self.css.is_some() && self.csn.is_some()
+    }
+
+    /// Iterates over the non zero values of the matrix, with their row and column.
+    ///
+    /// # Example
+    /// ```
+    /// # use odyssey::{MM, utils::matrix::{MappedMatrixBuilder, MappedMatrix}};
+    /// let a: MappedMatrix<&str, &str> = MM!["a" => { "c" => 1.0 }, "b" => { "d" => 2.0 }];
+    /// let mut triplets: Vec<_> = a.triplets().collect();
+    /// triplets.sort_by(|x, y| x.2.total_cmp(&y.2));
+    /// assert_eq!(triplets, vec![(&"a", &"c", 1.0), (&"b", &"d", 2.0)]);
+    /// ```
+    pub fn triplets(&self) -> impl Iterator<Item = (&R, &C, f64)> + '_ {
+        (0..self.cs.n).flat_map(move |col| {
+            let start = self.cs.p[col] as usize;
+            let end = self.cs.p[col + 1] as usize;
+            (start..end).filter_map(move |k| {
+                let row = self.irow(&(self.cs.i[k] as usize))?;
+                Some((row, self.icol(&col)?, self.cs.x[k]))
+            })
+        })
+    }
+
     /// Solve the system `Ax = b`, where `A` is a `MappedMatrix` and `b` a known `Vec<f64>`.
     /// The returned value is `x`.
     ///
@@ -260,10 +293,7 @@ where
     /// assert!(x == MV!["c" => 6.25, "d" => 1.875]);
     /// ```
     pub fn solve(&mut self, rhs: &MappedVector<R>) -> MappedVector<C> {
-        assert!(
-            self.css.is_some() && self.csn.is_some(),
-            "Matrix is not invertible"
-        );
+        assert!(self.is_invertible(), "Matrix is not invertible");
         assert_eq!(
             rhs.values.len(),
             self.rows.len(),
@@ -311,7 +341,6 @@ where
     /// assert!(x == MV!["a" => 10.0, "b" => 5.]);
     /// ```
     pub fn dot(&mut self, rhs: &MappedVector<C>) -> MappedVector<R> {
-        println!("{} {} {}", self.cols.len(), self.rows.len(), rhs.nrows());
         let mut res = vec![0f64; self.rows.len()];
         unsafe {
             csparse_matvec(&self.cs.as_ffi(), rhs.values.as_ptr(), res.as_mut_ptr());
@@ -1,3 +1,7 @@
+pub mod cache;
 pub mod constants;
+pub mod expression;
+pub mod geographies;
 pub mod matrix;
 pub mod search;
+pub mod units;
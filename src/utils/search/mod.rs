@@ -1,15 +1,18 @@
+use std::str::FromStr;
+
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use tantivy::collector::{Count, TopDocs};
-use tantivy::query::{BooleanQuery, Occur, QueryParser, TermQuery};
+use tantivy::query::{BooleanQuery, Occur, QueryParser, RegexQuery, TermQuery};
 use tantivy::schema::document::CompactDocValue;
 use tantivy::{doc, DocAddress, Index, IndexReader, IndexWriter, ReloadPolicy};
 use tantivy::{schema::*, TantivyError};
 
 use crate::comput::lca::Database;
+use crate::errors::{self, OdysseyErrors};
 use crate::utils::constants::SEARCH_PATH;
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct InventoryItem {
     pub id: String,
     pub database: String,
@@ -17,6 +20,124 @@ pub struct InventoryItem {
     pub alt_name: Option<String>,
     pub location: Option<String>,
     pub unit: String,
+    #[serde(default)]
+    pub metadata: ActivityMetadata,
+}
+
+/// Kind of activity, as defined by EcoSpold2.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum ActivityType {
+    /// Activity transforming inputs into products.
+    Transformation,
+    /// Activity supplying a product from its producers in a geography.
+    Market,
+    /// Activity supplying a product from the markets of smaller geographies.
+    MarketGroup,
+    /// Other special activities, such as production mixes or corrections.
+    Other,
+}
+
+impl ActivityType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ActivityType::Transformation => "transformation",
+            ActivityType::Market => "market",
+            ActivityType::MarketGroup => "market-group",
+            ActivityType::Other => "other",
+        }
+    }
+}
+
+impl FromStr for ActivityType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        [
+            ActivityType::Transformation,
+            ActivityType::Market,
+            ActivityType::MarketGroup,
+            ActivityType::Other,
+        ]
+        .into_iter()
+        .find(|t| t.as_str() == s)
+        .ok_or(format!(
+            "unknown activity type {s:?}, expected transformation, market, market-group or other"
+        ))
+    }
+}
+
+/// Classification and description of an activity, when its database provides them.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct ActivityMetadata {
+    /// ISIC rev.4 class of the activity, e.g. `2410:Manufacture of basic iron and steel`.
+    pub isic: Option<String>,
+    /// ISIC rev.4 section of the activity, e.g. `C` for manufacturing.
+    pub isic_section: Option<String>,
+    /// CPC class of the reference product, e.g. `41121: Non-alloy steel in ingots`.
+    pub cpc: Option<String>,
+    pub activity_type: Option<ActivityType>,
+    /// Years the data is valid for, e.g. `2015-2023`.
+    pub time_period: Option<String>,
+    /// Technology level compared to the other activities of the same product, from
+    /// `new` to `outdated`.
+    pub technology_level: Option<String>,
+    pub comment: Option<String>,
+}
+
+/// Filters on the classification of activities, to browse them by sector.
+#[derive(Debug, Default)]
+pub struct ActivityFilters {
+    /// ISIC section, e.g. `C`, or beginning of the ISIC class code, e.g. `24`.
+    pub isic: Option<String>,
+    /// Beginning of the CPC code of the reference product, e.g. `41`.
+    pub cpc: Option<String>,
+    pub activity_type: Option<ActivityType>,
+}
+
+/// ISIC rev.4 section of the class `code`, from its division, the first two digits.
+pub fn isic_section(code: &str) -> Option<&'static str> {
+    let division: u32 = code.get(..2)?.parse().ok()?;
+    let section = match division {
+        1..=3 => "A",
+        5..=9 => "B",
+        10..=33 => "C",
+        35 => "D",
+        36..=39 => "E",
+        41..=43 => "F",
+        45..=47 => "G",
+        49..=53 => "H",
+        55..=56 => "I",
+        58..=63 => "J",
+        64..=66 => "K",
+        68 => "L",
+        69..=75 => "M",
+        77..=82 => "N",
+        84 => "O",
+        85 => "P",
+        86..=88 => "Q",
+        90..=93 => "R",
+        94..=96 => "S",
+        97..=98 => "T",
+        99 => "U",
+        _ => return None,
+    };
+    Some(section)
+}
+
+/// Code of a classification value such as `2410:Manufacture of basic iron and steel`.
+fn classification_code(value: &str) -> &str {
+    value.split(':').next().unwrap_or_default().trim()
+}
+
+/// Elementary flow exchanged between an activity and the environment.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ElementaryItem {
+    pub id: String,
+    pub name: String,
+    pub compartment: String,
+    pub subcompartment: Option<String>,
+    pub unit: String,
 }
 
 pub struct Search {
@@ -31,10 +152,27 @@ pub struct Search {
     pub database_field: Field,
     pub location_field: Field,
     pub unit_field: Field,
+    pub isic_field: Field,
+    pub isic_section_field: Field,
+    pub cpc_field: Field,
+    pub activity_type_field: Field,
+    pub metadata_field: Field,
 }
 
 impl Search {
-    pub fn new() -> tantivy::Result<Self> {
+    /// Opens the search index, which fails if it was built by a previous version.
+    pub fn new() -> errors::Result<Self> {
+        Ok(Self::open(false)?.0)
+    }
+
+    /// Opens the search index like [Search::new], but replaces an index built by a
+    /// previous version by an empty one, in which case the returned flag is set and
+    /// all the databases must be indexed again.
+    pub fn new_or_reset() -> errors::Result<(Self, bool)> {
+        Self::open(true)
+    }
+
+    fn open(reset: bool) -> errors::Result<(Self, bool)> {
         let mut schema_builder = Schema::builder();
         let id_field = schema_builder.add_text_field("id", STRING | STORED);
         let exact_name_field = schema_builder.add_text_field("exact_name", STRING | STORED);
@@ -43,20 +181,35 @@ impl Search {
         let database_field = schema_builder.add_text_field("database", STRING | STORED);
         let location_field = schema_builder.add_text_field("location", STRING | STORED);
         let unit_field = schema_builder.add_text_field("unit", STRING | STORED);
+        let isic_field = schema_builder.add_text_field("isic", STRING);
+        let isic_section_field = schema_builder.add_text_field("isic_section", STRING);
+        let cpc_field = schema_builder.add_text_field("cpc", STRING);
+        let activity_type_field = schema_builder.add_text_field("activity_type", STRING);
+        let metadata_field = schema_builder.add_text_field("metadata", STORED);
         let schema = schema_builder.build();
 
-        let index =
+        let mut index =
             Index::create_in_dir(&*SEARCH_PATH, schema.clone()).or_else(|error| match error {
                 TantivyError::IndexAlreadyExists => Ok(Index::open_in_dir(&*SEARCH_PATH)?),
                 _ => Err(error),
             })?;
+        // Index of a previous version, whose databases must be imported again
+        let outdated = index.schema() != schema;
+        if outdated {
+            if !reset {
+                return Err(OdysseyErrors::OutdatedSearchIndex);
+            }
+            std::fs::remove_dir_all(&*SEARCH_PATH)?;
+            std::fs::create_dir_all(&*SEARCH_PATH)?;
+            index = Index::create_in_dir(&*SEARCH_PATH, schema.clone())?;
+        }
 
         let reader = Index::open_in_dir(&*SEARCH_PATH)?
             .reader_builder()
             .reload_policy(ReloadPolicy::OnCommitWithDelay)
             .try_into()?;
 
-        Ok(Self {
+        let search = Self {
             schema,
             index,
             reader,
@@ -68,10 +221,16 @@ impl Search {
             location_field,
             database_field,
             unit_field,
-        })
+            isic_field,
+            isic_section_field,
+            cpc_field,
+            activity_type_field,
+            metadata_field,
+        };
+        Ok((search, outdated))
     }
 
-    pub fn index_database(&self, data: &impl Database) -> tantivy::Result<()> {
+    pub fn index_database(&self, data: &dyn Database) -> tantivy::Result<()> {
         let mut index_writer: IndexWriter = self.index.writer(50_000_000)?;
 
         for item in data.list_candidates() {
@@ -92,6 +251,24 @@ impl Search {
                 doc.add_text(self.location_field, loc);
             }
             doc.add_text(self.unit_field, item.unit.clone());
+
+            let metadata = &item.metadata;
+            if let Some(isic) = &metadata.isic {
+                doc.add_text(self.isic_field, classification_code(isic));
+            }
+            if let Some(section) = &metadata.isic_section {
+                doc.add_text(self.isic_section_field, section);
+            }
+            if let Some(cpc) = &metadata.cpc {
+                doc.add_text(self.cpc_field, classification_code(cpc));
+            }
+            if let Some(activity_type) = metadata.activity_type {
+                doc.add_text(self.activity_type_field, activity_type.as_str());
+            }
+            if *metadata != ActivityMetadata::default() {
+                let metadata = serde_json::to_string(metadata).expect("Failed to serialize");
+                doc.add_text(self.metadata_field, metadata);
+            }
             index_writer.add_document(doc)?;
         }
         index_writer.commit()?;
@@ -112,6 +289,7 @@ impl Search {
         database: Option<&str>,
         localisation: Option<&str>,
         unit: Option<&str>,
+        filters: &ActivityFilters,
         exact_name: bool,
     ) -> tantivy::Result<Vec<(f32, DocAddress)>> {
         let mut queries = vec![];
@@ -140,6 +318,24 @@ impl Search {
             let unit_filter = TermQuery::new(unit_term, IndexRecordOption::Basic);
             queries.push((Occur::Must, Box::new(unit_filter)));
         }
+        match filters.isic.as_deref() {
+            Some(section) if section.len() == 1 && section.chars().all(char::is_alphabetic) => {
+                let section_term =
+                    Term::from_field_text(self.isic_section_field, &section.to_uppercase());
+                let section_filter = TermQuery::new(section_term, IndexRecordOption::Basic);
+                queries.push((Occur::Must, Box::new(section_filter)));
+            }
+            Some(code) => queries.push((Occur::Must, prefix_query(self.isic_field, code)?)),
+            None => {}
+        }
+        if let Some(code) = filters.cpc.as_deref() {
+            queries.push((Occur::Must, prefix_query(self.cpc_field, code)?));
+        }
+        if let Some(activity_type) = filters.activity_type {
+            let type_term = Term::from_field_text(self.activity_type_field, activity_type.as_str());
+            let type_filter = TermQuery::new(type_term, IndexRecordOption::Basic);
+            queries.push((Occur::Must, Box::new(type_filter)));
+        }
         let searcher = self.reader.searcher();
         searcher.search(&BooleanQuery::from(queries), &TopDocs::with_limit(10))
     }
@@ -151,13 +347,70 @@ impl Search {
         localisation: Option<&str>,
         unit: Option<&str>,
     ) -> tantivy::Result<Vec<String>> {
+        let res = self.search_for_items(query, database, localisation, unit)?;
+        Ok(res.into_iter().map(|item| item.id).collect())
+    }
+
+    /// Items whose name is exactly `query`.
+    pub fn search_for_items(
+        &self,
+        query: &str,
+        database: Option<&str>,
+        localisation: Option<&str>,
+        unit: Option<&str>,
+    ) -> tantivy::Result<Vec<InventoryItem>> {
+        self.get_items(query, database, localisation, unit, true)
+    }
+
+    /// Items whose name or alternative name match `query`, best matches first.
+    pub fn search_items(
+        &self,
+        query: &str,
+        database: Option<&str>,
+        localisation: Option<&str>,
+        unit: Option<&str>,
+    ) -> tantivy::Result<Vec<InventoryItem>> {
+        self.get_items(query, database, localisation, unit, false)
+    }
+
+    fn get_items(
+        &self,
+        query: &str,
+        database: Option<&str>,
+        localisation: Option<&str>,
+        unit: Option<&str>,
+        exact_name: bool,
+    ) -> tantivy::Result<Vec<InventoryItem>> {
         let searcher = self.reader.searcher();
-        let search_results = self._get_search_results(query, database, localisation, unit, true);
-        let res: Vec<String> = search_results?
+        let search_results = self._get_search_results(
+            query,
+            database,
+            localisation,
+            unit,
+            &ActivityFilters::default(),
+            exact_name,
+        );
+        let res: Vec<InventoryItem> = search_results?
             .into_iter()
             .filter_map(
                 |(_, address)| match searcher.doc::<TantivyDocument>(address) {
-                    Ok(doc) => Some(value_to_string(doc.get_first(self.id_field))),
+                    // CompactDoc is a private type, so doc can't be passed to a sub function
+                    Ok(doc) => Some(InventoryItem {
+                        id: value_to_string(doc.get_first(self.id_field)),
+                        database: value_to_string(doc.get_first(self.database_field)),
+                        name: value_to_string(doc.get_first(self.name_field)),
+                        alt_name: doc
+                            .get_first(self.alt_name_field)
+                            .map(|v| value_to_string(Some(v))),
+                        location: doc
+                            .get_first(self.location_field)
+                            .map(|v| value_to_string(Some(v))),
+                        unit: value_to_string(doc.get_first(self.unit_field)),
+                        metadata: doc
+                            .get_first(self.metadata_field)
+                            .and_then(|v| serde_json::from_str(v.as_str()?).ok())
+                            .unwrap_or_default(),
+                    }),
                     Err(_) => None,
                 },
             )
@@ -165,6 +418,24 @@ impl Search {
         Ok(res)
     }
 
+    /// Items whose name is exactly `query`, in the first of the `locations` that has any.
+    /// Returns the items and the location they were found in.
+    pub fn search_for_items_in(
+        &self,
+        query: &str,
+        database: Option<&str>,
+        locations: &[String],
+        unit: Option<&str>,
+    ) -> tantivy::Result<(Vec<InventoryItem>, Option<String>)> {
+        for location in locations {
+            let items = self.search_for_items(query, database, Some(location), unit)?;
+            if !items.is_empty() {
+                return Ok((items, Some(location.clone())));
+            }
+        }
+        Ok((vec![], None))
+    }
+
     /// Delete all entries of the given [database]
     pub fn delete_database(&mut self, database: &str) -> tantivy::Result<()> {
         let database_term = Term::from_field_text(self.database_field, database);
@@ -180,9 +451,11 @@ impl Search {
         database: Option<&str>,
         localisation: Option<&str>,
         unit: Option<&str>,
+        filters: &ActivityFilters,
     ) -> tantivy::Result<Vec<(f32, String)>> {
         let searcher = self.reader.searcher();
-        let search_results = self._get_search_results(query, database, localisation, unit, false);
+        let search_results =
+            self._get_search_results(query, database, localisation, unit, filters, false);
         let res: Vec<(f32, String)> = search_results?
             .into_iter()
             .filter_map(
@@ -193,9 +466,12 @@ impl Search {
                         let name = value_to_string(doc.get_first(self.name_field));
                         let location = doc.get_first(self.location_field).map(|v| Some(value_to_string(Some(v))));
                         let unit = value_to_string(doc.get_first(self.unit_field));
-                        let json = if let Some(location) = location {
+                        let mut json = if let Some(location) = location {
                             json!({"database": database, "name": name, "location": location, "unit": unit})
                         } else { json!({"database": database, "name": name, "unit": unit})};
+                        if let Some(metadata) = doc.get_first(self.metadata_field).and_then(|v| serde_json::from_str::<serde_json::Value>(v.as_str()?).ok()) {
+                            json["metadata"] = metadata;
+                        }
                         Some((
                             score,
                             format!("{}", json),
@@ -208,16 +484,17 @@ impl Search {
         Ok(res)
     }
 
-
     pub fn search(
         &self,
         query: &str,
         database: Option<&str>,
         localisation: Option<&str>,
         unit: Option<&str>,
+        filters: &ActivityFilters,
     ) -> tantivy::Result<Vec<(f32, String)>> {
         let searcher = self.reader.searcher();
-        let search_results = self._get_search_results(query, database, localisation, unit, false);
+        let search_results =
+            self._get_search_results(query, database, localisation, unit, filters, false);
         let res: Vec<(f32, String)> = search_results?
             .into_iter()
             .filter_map(
@@ -248,6 +525,21 @@ impl Search {
     }
 }
 
+/// Query of the terms of `field` starting with `prefix`.
+fn prefix_query(field: Field, prefix: &str) -> tantivy::Result<Box<dyn tantivy::query::Query>> {
+    let escaped: String = prefix
+        .chars()
+        .flat_map(|c| match c.is_alphanumeric() {
+            true => vec![c],
+            false => vec!['\\', c],
+        })
+        .collect();
+    Ok(Box::new(RegexQuery::from_pattern(
+        &format!("{escaped}.*"),
+        field,
+    )?))
+}
+
 fn value_to_string(doc: Option<CompactDocValue>) -> String {
     doc.unwrap().as_str().unwrap().to_string()
 }
@@ -0,0 +1,129 @@
+/// Physical dimension of a unit. Amounts can only be converted between units
+/// of the same dimension.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Dimension {
+    Mass,
+    Energy,
+    Volume,
+    Area,
+    Length,
+    /// Mass times distance, for freight transport
+    MassDistance,
+    /// Persons times distance, for passenger transport
+    PersonDistance,
+    /// Area times time, for land occupation
+    AreaTime,
+    Time,
+    Radioactivity,
+    Count,
+}
+
+impl Dimension {
+    /// Unit in which the amounts of the dimension are expressed in the registry.
+    pub fn base_unit(&self) -> &'static str {
+        match self {
+            Dimension::Mass => "kg",
+            Dimension::Energy => "MJ",
+            Dimension::Volume => "m3",
+            Dimension::Area => "m2",
+            Dimension::Length => "m",
+            Dimension::MassDistance => "t*km",
+            Dimension::PersonDistance => "person*km",
+            Dimension::AreaTime => "m2*year",
+            Dimension::Time => "hour",
+            Dimension::Radioactivity => "kBq",
+            Dimension::Count => "unit",
+        }
+    }
+}
+
+/// Units known to the registry, with their dimension and their value in the
+/// base unit of the dimension (kg, MJ, m3, m2, m, t*km, person*km, m2*year, hour, kBq, unit).
+const UNITS: &[(&str, Dimension, f64)] = &[
+    ("kg", Dimension::Mass, 1.),
+    ("g", Dimension::Mass, 1e-3),
+    ("mg", Dimension::Mass, 1e-6),
+    ("t", Dimension::Mass, 1e3),
+    ("ton", Dimension::Mass, 1e3),
+    ("metric ton", Dimension::Mass, 1e3),
+    ("lb", Dimension::Mass, 0.453_592_37),
+    ("MJ", Dimension::Energy, 1.),
+    ("J", Dimension::Energy, 1e-6),
+    ("kJ", Dimension::Energy, 1e-3),
+    ("GJ", Dimension::Energy, 1e3),
+    ("TJ", Dimension::Energy, 1e6),
+    ("Wh", Dimension::Energy, 3.6e-3),
+    ("kWh", Dimension::Energy, 3.6),
+    ("MWh", Dimension::Energy, 3.6e3),
+    ("GWh", Dimension::Energy, 3.6e6),
+    ("m3", Dimension::Volume, 1.),
+    ("dm3", Dimension::Volume, 1e-3),
+    ("cm3", Dimension::Volume, 1e-6),
+    ("l", Dimension::Volume, 1e-3),
+    ("L", Dimension::Volume, 1e-3),
+    ("ml", Dimension::Volume, 1e-6),
+    ("m2", Dimension::Area, 1.),
+    ("cm2", Dimension::Area, 1e-4),
+    ("ha", Dimension::Area, 1e4),
+    ("km2", Dimension::Area, 1e6),
+    ("m", Dimension::Length, 1.),
+    ("mm", Dimension::Length, 1e-3),
+    ("cm", Dimension::Length, 1e-2),
+    ("km", Dimension::Length, 1e3),
+    ("metric ton*km", Dimension::MassDistance, 1.),
+    ("t*km", Dimension::MassDistance, 1.),
+    ("tkm", Dimension::MassDistance, 1.),
+    ("kg*km", Dimension::MassDistance, 1e-3),
+    ("person*km", Dimension::PersonDistance, 1.),
+    ("pkm", Dimension::PersonDistance, 1.),
+    ("m2*year", Dimension::AreaTime, 1.),
+    ("ha*year", Dimension::AreaTime, 1e4),
+    ("hour", Dimension::Time, 1.),
+    ("h", Dimension::Time, 1.),
+    ("s", Dimension::Time, 1. / 3600.),
+    ("min", Dimension::Time, 1. / 60.),
+    ("day", Dimension::Time, 24.),
+    ("year", Dimension::Time, 8760.),
+    ("kBq", Dimension::Radioactivity, 1.),
+    ("Bq", Dimension::Radioactivity, 1e-3),
+    ("unit", Dimension::Count, 1.),
+    ("item(s)", Dimension::Count, 1.),
+];
+
+/// Dimension of `unit` and its value in the base unit of the dimension.
+pub fn lookup(unit: &str) -> Option<(Dimension, f64)> {
+    UNITS
+        .iter()
+        .find(|(name, _, _)| *name == unit)
+        .map(|(_, dimension, factor)| (*dimension, *factor))
+}
+
+pub fn dimension(unit: &str) -> Option<Dimension> {
+    lookup(unit).map(|(dimension, _)| dimension)
+}
+
+/// Factor converting an amount in `from` into an amount in `to`.
+/// Returns `None` if a unit is unknown or if their dimensions differ.
+pub fn conversion_factor(from: &str, to: &str) -> Option<f64> {
+    if from == to {
+        return Some(1.);
+    }
+    let (from_dimension, from_factor) = lookup(from)?;
+    let (to_dimension, to_factor) = lookup(to)?;
+    (from_dimension == to_dimension).then(|| from_factor / to_factor)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_conversion_factor() {
+        assert_eq!(conversion_factor("g", "kg"), Some(1e-3));
+        assert!((conversion_factor("MJ", "kWh").unwrap() - 1. / 3.6).abs() < 1e-12);
+        assert_eq!(conversion_factor("kg*km", "metric ton*km"), Some(1e-3));
+        assert_eq!(conversion_factor("kg", "MJ"), None);
+        assert_eq!(conversion_factor("furlong", "m"), None);
+        assert_eq!(conversion_factor("furlong", "furlong"), Some(1.));
+    }
+}